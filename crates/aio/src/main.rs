@@ -1,24 +1,27 @@
+use akd_watch_common::{events::AuditEvents, observability::Observability};
 use anyhow::Result;
 use tracing::{error, info};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
+    let _observability = Observability::from_config("akd-watch-aio")?.init()?;
 
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel(1);
+    // Lets the web server push audit events as they happen instead of polling storage
+    let events = AuditEvents::default();
 
     // Start the auditor service in a separate task
+    let auditor_events = events.clone();
     let auditor_handle = tokio::spawn(async move {
-        if let Err(e) = akd_watch_auditor::start(&mut shutdown_rx).await {
+        if let Err(e) = akd_watch_auditor::start_with_events(&mut shutdown_rx, auditor_events).await
+        {
             error!(error = ?e, "Auditor service failed");
         }
     });
 
     // Start the web service
-    let web_handle = tokio::spawn(async {
-        if let Err(e) = akd_watch_web::start().await {
+    let web_handle = tokio::spawn(async move {
+        if let Err(e) = akd_watch_web::start_with_events(events).await {
             error!(error = ?e, "Web service failed");
         }
     });
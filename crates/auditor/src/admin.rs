@@ -0,0 +1,538 @@
+use std::sync::Arc;
+
+use akd_watch_common::{
+    NamespaceInfo, NamespaceStatus, StatusActor, StatusTransition,
+    events::{AuditEvent, AuditEvents},
+    metrics,
+    storage::{
+        namespaces::{NamespaceRepository, NamespaceRepositoryError, NamespaceStorage},
+        signing_keys::{SigningKeyRepository, SigningKeyStorage},
+    },
+};
+use anyhow::{Context, Result};
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Request, State},
+    http::header,
+    middleware::{self, Next},
+    response::Response,
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::TcpListener,
+    sync::{RwLock, mpsc},
+};
+use tracing::{info, instrument, warn};
+
+use crate::config::AdminToken;
+use crate::error::AdminError;
+use crate::namespace_auditor::MAX_UPDATE_ATTEMPTS;
+
+/// An operator action on a namespace's status
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum NamespaceAction {
+    /// Resume auditing a disabled namespace
+    Enable,
+    /// Stop auditing an active namespace
+    Disable,
+    /// Accept a failed namespace's failure, disabling it until it is enabled or reset
+    Acknowledge,
+    /// Re-audit the namespace from its starting epoch, as `SignatureLost` requires
+    Reset,
+}
+
+impl NamespaceAction {
+    fn name(self) -> &'static str {
+        match self {
+            NamespaceAction::Enable => "enable",
+            NamespaceAction::Disable => "disable",
+            NamespaceAction::Acknowledge => "acknowledge",
+            NamespaceAction::Reset => "reset",
+        }
+    }
+
+    fn allowed_from(self, status: &NamespaceStatus) -> bool {
+        match self {
+            NamespaceAction::Enable => *status == NamespaceStatus::Disabled,
+            NamespaceAction::Disable => status.is_active(),
+            NamespaceAction::Acknowledge => matches!(
+                status,
+                NamespaceStatus::SignatureLost
+                    | NamespaceStatus::SignatureVerificationFailed
+                    | NamespaceStatus::SignatureConflict
+            ),
+            NamespaceAction::Reset => matches!(
+                status,
+                NamespaceStatus::SignatureLost | NamespaceStatus::Disabled
+            ),
+        }
+    }
+
+    fn apply(self, namespace_info: &NamespaceInfo) -> NamespaceInfo {
+        match self {
+            NamespaceAction::Enable => namespace_info.update_status(NamespaceStatus::Online),
+            NamespaceAction::Disable | NamespaceAction::Acknowledge => {
+                namespace_info.update_status(NamespaceStatus::Disabled)
+            }
+            NamespaceAction::Reset => NamespaceInfo {
+                last_verified_epoch: None,
+                ..namespace_info.update_status(NamespaceStatus::Online)
+            },
+        }
+    }
+
+    /// Whether the namespace's auditor must be started again after the action
+    fn restarts_auditor(self) -> bool {
+        matches!(self, NamespaceAction::Enable | NamespaceAction::Reset)
+    }
+}
+
+/// Optional body of an admin request
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdminRequest {
+    /// Why the operator took the action, recorded in the status history
+    reason: Option<String>,
+}
+
+/// Name of the token that authenticated the request
+#[derive(Clone, Debug)]
+struct Operator(String);
+
+/// State of the admin API
+#[derive(Clone)]
+pub(crate) struct AdminState {
+    namespace_repository: Arc<RwLock<NamespaceStorage>>,
+    signing_key_repository: Arc<RwLock<SigningKeyStorage>>,
+    /// Asks the auditor application to start a namespace's auditor again
+    restart_tx: mpsc::UnboundedSender<String>,
+    events: Option<AuditEvents>,
+    tokens: Arc<Vec<AdminToken>>,
+}
+
+impl AdminState {
+    pub(crate) fn new(
+        namespace_repository: Arc<RwLock<NamespaceStorage>>,
+        signing_key_repository: Arc<RwLock<SigningKeyStorage>>,
+        restart_tx: mpsc::UnboundedSender<String>,
+        events: Option<AuditEvents>,
+        tokens: Vec<AdminToken>,
+    ) -> Self {
+        Self {
+            namespace_repository,
+            signing_key_repository,
+            restart_tx,
+            events,
+            tokens: Arc::new(tokens),
+        }
+    }
+
+    /// The name of the token matching `authorization`, a `Bearer` authorization header value
+    fn operator(&self, authorization: &str) -> Option<Operator> {
+        let token = authorization.strip_prefix("Bearer ")?;
+        // Compare against every token without stopping early, so timing reveals nothing
+        let mut operator = None;
+        for admin_token in self.tokens.iter() {
+            if constant_time_eq(admin_token.token.as_bytes(), token.as_bytes()) {
+                operator = Some(Operator(admin_token.name.clone()));
+            }
+        }
+        operator
+    }
+
+    /// Applies `action` to the namespace with a compare-and-swap and records it in the namespace's
+    /// status history
+    async fn apply(
+        &self,
+        namespace: &str,
+        action: NamespaceAction,
+        operator: &Operator,
+        reason: Option<String>,
+    ) -> Result<StatusTransition, AdminError> {
+        let reason = describe(action.name(), operator, reason);
+        let mut repo = self.namespace_repository.write().await;
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let current = repo
+                .get_namespace_info(namespace)
+                .await?
+                .ok_or_else(|| AdminError::NamespaceNotFound(namespace.to_string()))?;
+            if !action.allowed_from(&current.status) {
+                return Err(AdminError::InvalidTransition {
+                    namespace: namespace.to_string(),
+                    status: current.status,
+                    action: action.name(),
+                });
+            }
+            let updated = action.apply(&current);
+            match repo.compare_and_swap_namespace(updated.clone()).await {
+                Ok(_) => {}
+                Err(NamespaceRepositoryError::VersionConflict { .. }) => continue,
+                Err(e) => return Err(e.into()),
+            }
+
+            let transition = StatusTransition::new(
+                &current,
+                updated.status.clone(),
+                current.last_verified_epoch,
+                Some(reason),
+                StatusActor::Operator,
+            );
+            repo.record_status_transition(transition.clone()).await?;
+            info!(
+                namespace,
+                action = action.name(),
+                operator = operator.0,
+                "Applied admin action"
+            );
+            metrics::record_namespace_status(namespace, &updated.status);
+            self.publish(&transition);
+            if action.restarts_auditor() && self.restart_tx.send(namespace.to_string()).is_err() {
+                warn!(
+                    namespace,
+                    "Auditor is not running, cannot restart namespace"
+                );
+            }
+            return Ok(transition);
+        }
+        Err(AdminError::ConcurrentModification(namespace.to_string()))
+    }
+
+    /// Rotates the signing key and records the rotation in every namespace's status history
+    async fn rotate_signing_key(
+        &self,
+        operator: &Operator,
+        reason: Option<String>,
+    ) -> Result<Vec<StatusTransition>, AdminError> {
+        self.signing_key_repository
+            .read()
+            .await
+            .force_key_rotation()
+            .await?;
+        info!(operator = operator.0, "Rotated signing key");
+
+        let reason = describe("rotate signing key", operator, reason);
+        let mut repo = self.namespace_repository.write().await;
+        let mut transitions = Vec::new();
+        for namespace_info in repo.list_namespaces().await? {
+            let transition = StatusTransition::new(
+                &namespace_info,
+                namespace_info.status.clone(),
+                namespace_info.last_verified_epoch,
+                Some(reason.clone()),
+                StatusActor::Operator,
+            );
+            repo.record_status_transition(transition.clone()).await?;
+            self.publish(&transition);
+            transitions.push(transition);
+        }
+        Ok(transitions)
+    }
+
+    fn publish(&self, transition: &StatusTransition) {
+        if let Some(events) = &self.events {
+            events.publish(AuditEvent::StatusChanged(transition.clone()));
+        }
+    }
+}
+
+/// The status history reason for an admin action
+fn describe(action: &str, operator: &Operator, reason: Option<String>) -> String {
+    match reason {
+        Some(reason) => format!("{action} by {}: {reason}", operator.0),
+        None => format!("{action} by {}", operator.0),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Rejects requests without a valid bearer token
+async fn authenticate(
+    State(state): State<AdminState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AdminError> {
+    let operator = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|authorization| state.operator(authorization))
+        .ok_or(AdminError::Unauthorized)?;
+    request.extensions_mut().insert(operator);
+    Ok(next.run(request).await)
+}
+
+async fn namespace_action(
+    state: AdminState,
+    namespace: String,
+    action: NamespaceAction,
+    operator: Operator,
+    request: Option<Json<AdminRequest>>,
+) -> Result<Json<StatusTransition>, AdminError> {
+    let reason = request.and_then(|Json(request)| request.reason);
+    state
+        .apply(&namespace, action, &operator, reason)
+        .await
+        .map(Json)
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+async fn enable_handler(
+    State(state): State<AdminState>,
+    Path(namespace): Path<String>,
+    Extension(operator): Extension<Operator>,
+    request: Option<Json<AdminRequest>>,
+) -> Result<Json<StatusTransition>, AdminError> {
+    namespace_action(state, namespace, NamespaceAction::Enable, operator, request).await
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+async fn disable_handler(
+    State(state): State<AdminState>,
+    Path(namespace): Path<String>,
+    Extension(operator): Extension<Operator>,
+    request: Option<Json<AdminRequest>>,
+) -> Result<Json<StatusTransition>, AdminError> {
+    namespace_action(
+        state,
+        namespace,
+        NamespaceAction::Disable,
+        operator,
+        request,
+    )
+    .await
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+async fn acknowledge_handler(
+    State(state): State<AdminState>,
+    Path(namespace): Path<String>,
+    Extension(operator): Extension<Operator>,
+    request: Option<Json<AdminRequest>>,
+) -> Result<Json<StatusTransition>, AdminError> {
+    namespace_action(
+        state,
+        namespace,
+        NamespaceAction::Acknowledge,
+        operator,
+        request,
+    )
+    .await
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+async fn reset_handler(
+    State(state): State<AdminState>,
+    Path(namespace): Path<String>,
+    Extension(operator): Extension<Operator>,
+    request: Option<Json<AdminRequest>>,
+) -> Result<Json<StatusTransition>, AdminError> {
+    namespace_action(state, namespace, NamespaceAction::Reset, operator, request).await
+}
+
+#[instrument(skip_all)]
+async fn rotate_key_handler(
+    State(state): State<AdminState>,
+    Extension(operator): Extension<Operator>,
+    request: Option<Json<AdminRequest>>,
+) -> Result<Json<Vec<StatusTransition>>, AdminError> {
+    let reason = request.and_then(|Json(request)| request.reason);
+    state.rotate_signing_key(&operator, reason).await.map(Json)
+}
+
+/// Serves the admin API until the process exits
+pub(crate) async fn serve(listener: TcpListener, state: AdminState) -> Result<()> {
+    let app = Router::new()
+        .route("/namespaces/:namespace/enable", post(enable_handler))
+        .route("/namespaces/:namespace/disable", post(disable_handler))
+        .route(
+            "/namespaces/:namespace/acknowledge",
+            post(acknowledge_handler),
+        )
+        .route("/namespaces/:namespace/reset", post(reset_handler))
+        .route("/signing-key/rotate", post(rotate_key_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .with_state(state);
+    info!(address = ?listener.local_addr().ok(), "Serving admin API");
+    axum::serve(listener, app.into_make_service())
+        .await
+        .context("Admin listener failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use akd_watch_common::{
+        Epoch,
+        akd_configurations::AkdConfiguration,
+        storage::{
+            namespaces::InMemoryNamespaceRepository, signing_keys::InMemorySigningKeyRepository,
+        },
+    };
+
+    use super::*;
+
+    async fn admin_state(status: NamespaceStatus) -> (AdminState, mpsc::UnboundedReceiver<String>) {
+        let mut namespace_repository =
+            NamespaceStorage::InMemory(InMemoryNamespaceRepository::new());
+        namespace_repository
+            .add_namespace(NamespaceInfo {
+                name: "operated".to_string(),
+                starting_epoch: Epoch::new(1),
+                configuration: AkdConfiguration::TestConfiguration,
+                log_directory: "test".to_string(),
+                last_verified_epoch: Some(Epoch::new(10)),
+                status,
+                version: 0,
+            })
+            .await
+            .unwrap();
+        let signing_key_repository = SigningKeyStorage::InMemory(
+            InMemorySigningKeyRepository::new(chrono::Duration::days(30)),
+        );
+        let (restart_tx, restart_rx) = mpsc::unbounded_channel();
+        let state = AdminState::new(
+            Arc::new(RwLock::new(namespace_repository)),
+            Arc::new(RwLock::new(signing_key_repository)),
+            restart_tx,
+            None,
+            vec![AdminToken {
+                name: "alice".to_string(),
+                token: "a".repeat(32),
+            }],
+        );
+        (state, restart_rx)
+    }
+
+    #[tokio::test]
+    async fn test_operator_requires_a_configured_bearer_token() {
+        let (state, _restart_rx) = admin_state(NamespaceStatus::Online).await;
+        let token = "a".repeat(32);
+        assert_eq!(
+            state.operator(&format!("Bearer {token}")).unwrap().0,
+            "alice"
+        );
+        assert!(state.operator(&token).is_none());
+        assert!(
+            state
+                .operator(&format!("Bearer {}", "b".repeat(32)))
+                .is_none()
+        );
+        assert!(state.operator("Bearer ").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reset_restarts_lost_namespace_from_starting_epoch() {
+        let (state, mut restart_rx) = admin_state(NamespaceStatus::SignatureLost).await;
+        let operator = Operator("alice".to_string());
+
+        let transition = state
+            .apply(
+                "operated",
+                NamespaceAction::Reset,
+                &operator,
+                Some("signatures restored".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            transition.previous_status,
+            Some(NamespaceStatus::SignatureLost)
+        );
+        assert_eq!(transition.new_status, NamespaceStatus::Online);
+        assert_eq!(transition.actor, StatusActor::Operator);
+        assert_eq!(
+            transition.reason.as_deref(),
+            Some("reset by alice: signatures restored")
+        );
+        assert_eq!(restart_rx.try_recv().unwrap(), "operated");
+
+        let repo = state.namespace_repository.read().await;
+        let namespace_info = repo.get_namespace_info("operated").await.unwrap().unwrap();
+        assert_eq!(namespace_info.status, NamespaceStatus::Online);
+        assert_eq!(namespace_info.last_verified_epoch, None);
+        assert_eq!(
+            repo.status_history("operated").await.unwrap().last(),
+            Some(&transition)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_actions_are_refused_from_other_statuses() {
+        let (state, mut restart_rx) =
+            admin_state(NamespaceStatus::SignatureVerificationFailed).await;
+        let operator = Operator("alice".to_string());
+
+        for action in [
+            NamespaceAction::Enable,
+            NamespaceAction::Disable,
+            NamespaceAction::Reset,
+        ] {
+            let error = state
+                .apply("operated", action, &operator, None)
+                .await
+                .unwrap_err();
+            assert!(matches!(error, AdminError::InvalidTransition { .. }));
+        }
+
+        // A failure must be acknowledged before the namespace can be enabled again
+        state
+            .apply("operated", NamespaceAction::Acknowledge, &operator, None)
+            .await
+            .unwrap();
+        assert!(restart_rx.try_recv().is_err());
+        let transition = state
+            .apply("operated", NamespaceAction::Enable, &operator, None)
+            .await
+            .unwrap();
+        assert_eq!(transition.previous_status, Some(NamespaceStatus::Disabled));
+        assert_eq!(restart_rx.try_recv().unwrap(), "operated");
+        assert_eq!(
+            state
+                .namespace_repository
+                .read()
+                .await
+                .status_history("operated")
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let error = state
+            .apply("missing", NamespaceAction::Enable, &operator, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AdminError::NamespaceNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_is_recorded_for_every_namespace() {
+        let (state, _restart_rx) = admin_state(NamespaceStatus::Online).await;
+        let operator = Operator("alice".to_string());
+        let before = state
+            .signing_key_repository
+            .read()
+            .await
+            .get_current_signing_key()
+            .await
+            .unwrap();
+
+        let transitions = state.rotate_signing_key(&operator, None).await.unwrap();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].new_status, NamespaceStatus::Online);
+        assert_eq!(
+            transitions[0].reason.as_deref(),
+            Some("rotate signing key by alice")
+        );
+
+        let after = state
+            .signing_key_repository
+            .read()
+            .await
+            .get_current_signing_key()
+            .await
+            .unwrap();
+        assert_ne!(before.key_id(), after.key_id());
+    }
+}
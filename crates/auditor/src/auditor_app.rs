@@ -1,29 +1,47 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock, mpsc};
+use tokio::task::JoinSet;
 
 use akd_watch_common::storage::{
-    namespaces::{NamespaceRepository, NamespaceStorage},
+    namespaces::{NamespaceRepository, NamespaceRepositoryError, NamespaceStorage},
     signatures::SignatureStorage,
-    signing_keys::SigningKeyStorage,
+    signing_keys::{SigningKeyRepository, SigningKeyStorage, VerifyingKeyStorage},
+};
+use akd_watch_common::{
+    NamespaceInfo, StatusActor, StatusTransition, events::AuditEvents, evidence::EvidenceStore,
+    transparency_log::TransparencyLog,
 };
 use anyhow::{Context, Result};
-use futures_util::future::{self, JoinAll};
 use tokio::sync::broadcast;
 use tracing::{info, warn};
 
-use crate::config::AuditorConfig;
-use crate::namespace_auditor::NamespaceAuditor;
+use crate::admin::AdminState;
+use crate::config::{AdminToken, AuditorConfig, NamespaceConfig};
+use crate::health::AuditorHealth;
+use crate::namespace_auditor::{MAX_UPDATE_ATTEMPTS, NamespaceAuditor};
+use crate::replica_reconciler::ReplicaReconciler;
+use crate::tree_head_publisher::TreeHeadPublisher;
 
 /// Main auditor application
 pub struct AuditorApp {
     namespace_repository: Arc<RwLock<NamespaceStorage>>,
     signing_key_repository: Arc<RwLock<SigningKeyStorage>>,
     signature_storage_map: HashMap<String, SignatureStorage>,
+    transparency_log: Arc<Mutex<TransparencyLog>>,
+    evidence_store: EvidenceStore,
+    events: Option<AuditEvents>,
+    health: AuditorHealth,
     sleep_duration: Duration,
+    heartbeat_interval: Duration,
+    tree_head_interval: Duration,
     shutdown_tx: broadcast::Sender<()>,
-    complete_handle: Option<JoinAll<tokio::task::JoinHandle<()>>>,
+    /// Namespaces whose auditor should be started again, sent by the admin API
+    restart_tx: mpsc::UnboundedSender<String>,
+    restart_rx: mpsc::UnboundedReceiver<String>,
+    /// Background tasks. Namespace auditor tasks return their namespace's name.
+    tasks: JoinSet<Option<String>>,
 }
 
 impl AuditorApp {
@@ -34,11 +52,14 @@ impl AuditorApp {
             config.namespaces.len()
         );
 
+        // Clean up after a crash before loading anything, since the auditor is the only writer
+        Self::recover_storage(&config).with_context(|| "Startup storage recovery failed")?;
+
         // Initialize repositories and storage based on config
         let namespace_repository = {
             let mut namespace_repository = config
                 .namespace_storage
-                .build_namespace_storage(&config.data_directory());
+                .build_namespace_storage(&config.data_directory())?;
             Self::populate_namespace_repository(&mut namespace_repository, &config)
                 .await
                 .with_context(|| "Failed to populate namespace repository")?;
@@ -51,22 +72,66 @@ impl AuditorApp {
         let signing_key_repository = Arc::new(RwLock::new(
             config
                 .signing
-                .build_signing_key_storage(&config.data_directory()),
+                .build_signing_key_storage(&config.data_directory())?,
         ));
 
+        let transparency_log = TransparencyLog::open_or_create(&config.data_directory())
+            .with_context(|| "Failed to open transparency log")?;
+        info!(entries = transparency_log.size(), "Opened transparency log");
+
         // Create shutdown channel
         let (shutdown_tx, _) = broadcast::channel(1);
+        let (restart_tx, restart_rx) = mpsc::unbounded_channel();
 
         Ok(AuditorApp {
             namespace_repository: Arc::new(RwLock::new(namespace_repository)),
             signing_key_repository,
             signature_storage_map,
+            transparency_log: Arc::new(Mutex::new(transparency_log)),
+            evidence_store: EvidenceStore::new(&config.data_directory()),
+            events: None,
+            health: AuditorHealth::new(config.health_stale_after()),
             sleep_duration: config.sleep_duration(),
+            heartbeat_interval: config.heartbeat_interval(),
+            tree_head_interval: config.tree_head_interval(),
             shutdown_tx,
-            complete_handle: None,
+            restart_tx,
+            restart_rx,
+            tasks: JoinSet::new(),
         })
     }
 
+    /// Publishes the namespace auditors' stored signatures and status transitions to `events`
+    pub fn with_events(mut self, events: AuditEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Liveness of the namespace auditor tasks, for the health listener
+    pub(crate) fn health(&self) -> AuditorHealth {
+        self.health.clone()
+    }
+
+    /// State of the admin API, authenticating requests with `tokens`
+    pub(crate) fn admin(&self, tokens: Vec<AdminToken>) -> AdminState {
+        AdminState::new(
+            self.namespace_repository.clone(),
+            self.signing_key_repository.clone(),
+            self.restart_tx.clone(),
+            self.events.clone(),
+            tokens,
+        )
+    }
+
+    /// The verifying keys of the auditor's signing keys, for the signing key age metric
+    pub(crate) async fn verifying_key_storage(&self) -> Result<VerifyingKeyStorage> {
+        self.signing_key_repository
+            .read()
+            .await
+            .verifying_key_repository()
+            .context("Failed to open verifying key storage")
+    }
+
     /// Run the auditor application
     pub async fn run(&mut self) -> Result<()> {
         // Get all namespaces from the repository
@@ -78,70 +143,153 @@ impl AuditorApp {
             .await
             .with_context(|| "Failed to get namespaces from repository")?;
 
-        let mut handles = Vec::new();
+        let publisher = TreeHeadPublisher::new(
+            self.transparency_log.clone(),
+            self.signing_key_repository.clone(),
+            self.tree_head_interval,
+            self.shutdown_tx.subscribe(),
+        );
+        self.tasks.spawn(async move {
+            publisher.run().await;
+            None
+        });
 
+        let mut running = HashSet::new();
         for namespace_info in namespace_infos {
-            let signature_storage = self
-                .signature_storage_map
-                .get(&namespace_info.name)
-                .with_context(|| {
-                    format!(
-                        "Missing signature storage for namespace {}",
-                        namespace_info.name
-                    )
-                })?
-                .clone();
-
-            let auditor = NamespaceAuditor::new(
-                namespace_info.clone(),
-                self.namespace_repository.clone(),
-                self.signing_key_repository.clone(),
-                signature_storage,
-                self.sleep_duration,
-                self.shutdown_tx.subscribe(),
-            );
-
-            let handle = tokio::spawn(async move {
-                info!(
-                    namespace = ?namespace_info,
-                    "Starting auditor for namespace"
+            if let SignatureStorage::Mirrored(mirrored) =
+                self.signature_storage(&namespace_info.name)?
+                && let Some(interval) = mirrored.reconcile_interval()
+            {
+                let reconciler = ReplicaReconciler::new(
+                    namespace_info.name.clone(),
+                    mirrored.clone(),
+                    interval,
+                    self.shutdown_tx.subscribe(),
                 );
+                self.tasks.spawn(async move {
+                    reconciler.run().await;
+                    None
+                });
+            }
 
-                if let Err(e) = auditor.run().await {
-                    warn!(
-                        namespace = namespace_info.name,
-                        error = %e,
-                        "Namespace auditor exited with error"
-                    );
+            self.spawn_namespace_auditor(&namespace_info)?;
+            running.insert(namespace_info.name);
+        }
+
+        info!("Started {} namespace auditors", running.len());
+
+        // Restart namespace auditors on request until every task has completed. A namespace that
+        // is still running is restarted once its current auditor stops.
+        let mut pending_restarts = HashSet::new();
+        loop {
+            tokio::select! {
+                Some(namespace) = self.restart_rx.recv() => {
+                    if running.contains(&namespace) {
+                        pending_restarts.insert(namespace);
+                    } else if self.restart_namespace_auditor(&namespace).await {
+                        running.insert(namespace);
+                    }
                 }
-            });
+                result = self.tasks.join_next() => match result {
+                    Some(Ok(Some(namespace))) => {
+                        running.remove(&namespace);
+                        if pending_restarts.remove(&namespace)
+                            && self.restart_namespace_auditor(&namespace).await
+                        {
+                            running.insert(namespace);
+                        }
+                    }
+                    Some(Ok(None)) => {}
+                    Some(Err(e)) => warn!(error = %e, "Auditor task failed"),
+                    None => break,
+                }
+            }
+        }
 
-            handles.push(handle);
+        info!("All auditors completed");
+        Ok(())
+    }
+
+    fn signature_storage(&self, namespace: &str) -> Result<&SignatureStorage> {
+        self.signature_storage_map
+            .get(namespace)
+            .with_context(|| format!("Missing signature storage for namespace {namespace}"))
+    }
+
+    /// Starts auditing a namespace again after its auditor stopped. Returns whether it started.
+    async fn restart_namespace_auditor(&mut self, namespace: &str) -> bool {
+        let namespace_info = match self
+            .namespace_repository
+            .read()
+            .await
+            .get_namespace_info(namespace)
+            .await
+        {
+            Ok(Some(namespace_info)) => namespace_info,
+            Ok(None) => {
+                warn!(namespace, "Cannot restart auditor of unknown namespace");
+                return false;
+            }
+            Err(e) => {
+                warn!(namespace, error = %e, "Failed to read namespace to restart its auditor");
+                return false;
+            }
+        };
+        match self.spawn_namespace_auditor(&namespace_info) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(namespace, error = %e, "Failed to restart namespace auditor");
+                false
+            }
         }
+    }
 
-        info!("Started {} namespace auditors", handles.len());
+    /// Starts auditing a namespace. The auditor stops on its own if the namespace is not active.
+    fn spawn_namespace_auditor(&mut self, namespace_info: &NamespaceInfo) -> Result<()> {
+        let namespace = namespace_info.name.clone();
+        let mut auditor = NamespaceAuditor::new(
+            namespace_info.clone(),
+            self.namespace_repository.clone(),
+            self.signing_key_repository.clone(),
+            self.signature_storage(&namespace)?.clone(),
+            self.sleep_duration,
+            self.heartbeat_interval,
+            self.shutdown_tx.subscribe(),
+        )
+        .with_transparency_log(self.transparency_log.clone())
+        .with_evidence_store(self.evidence_store.clone())
+        .with_health(self.health.clone());
+        if let Some(events) = &self.events {
+            auditor = auditor.with_events(events.clone());
+        }
 
-        // Wait for all auditors to complete
-        self.complete_handle = Some(future::join_all(handles));
-        self.wait_for_complete().await;
+        self.tasks.spawn(async move {
+            info!(namespace, "Starting auditor for namespace");
 
-        info!("All auditors completed");
+            if let Err(e) = auditor.run().await {
+                warn!(
+                    namespace,
+                    error = %e,
+                    "Namespace auditor exited with error"
+                );
+            }
+            Some(namespace)
+        });
         Ok(())
     }
 
     async fn wait_for_complete(&mut self) {
-        if let Some(handle) = self.complete_handle.take() {
-            info!("Waiting for auditors to complete");
-            let results = handle.await;
-            for result in results {
-                if let Err(e) = result {
-                    warn!(error = %e, "Auditor task completed with error during shutdown");
-                }
-            }
-            info!("All auditors completed during shutdown");
-        } else {
+        if self.tasks.is_empty() {
             info!("No auditors were running during shutdown");
+            return;
         }
+        info!("Waiting for auditors to complete");
+        while let Some(result) = self.tasks.join_next().await {
+            if let Err(e) = result {
+                warn!(error = %e, "Auditor task completed with error during shutdown");
+            }
+        }
+        info!("All auditors completed during shutdown");
     }
 
     /// Gracefully shutdown all auditors
@@ -156,49 +304,147 @@ impl AuditorApp {
         Ok(())
     }
 
+    /// Runs the startup recovery step of every file-based repository, reporting what was cleaned up
+    fn recover_storage(config: &AuditorConfig) -> Result<()> {
+        let data_directory = config.data_directory();
+        let mut report = config.namespace_storage.recover(&data_directory)?;
+        report.merge(config.signature_storage.recover(&data_directory)?);
+        report.merge(config.signing.recover(&data_directory)?);
+
+        if report.is_empty() {
+            return Ok(());
+        }
+        for path in &report.removed_temp_files {
+            warn!(path = %path.display(), "Removed leftover temporary file from an interrupted write");
+        }
+        for path in &report.quarantined_files {
+            warn!(path = %path.display(), "Quarantined corrupt file");
+        }
+        if report.migrated_signatures > 0 {
+            info!(
+                migrated = report.migrated_signatures,
+                "Migrated legacy signature files into segment files"
+            );
+        }
+        Ok(())
+    }
+
     async fn populate_namespace_repository<T: NamespaceRepository>(
         namespace_repository: &mut T,
         config: &AuditorConfig,
     ) -> Result<()> {
-        let existing_namespaces = namespace_repository
-            .list_namespaces()
-            .await
-            .unwrap_or_default();
-
         for ns_config in &config.namespaces {
-            let existing_info = existing_namespaces
-                .iter()
-                .find(|info| info.name == ns_config.name);
+            Self::apply_namespace_config(namespace_repository, ns_config).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the namespace described by `ns_config`, merged with its stored state. Updates use
+    /// compare-and-swap and are recomputed from the stored state if another writer changed the
+    /// namespace in between.
+    async fn apply_namespace_config<T: NamespaceRepository>(
+        namespace_repository: &mut T,
+        ns_config: &NamespaceConfig,
+    ) -> Result<()> {
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let existing_info = namespace_repository
+                .get_namespace_info(&ns_config.name)
+                .await
+                .with_context(|| format!("Failed to read namespace {}", ns_config.name))?;
 
             let (namespace_info, changed) = ns_config
-                .to_namespace_info(existing_info)
+                .to_namespace_info(existing_info.as_ref())
                 .with_context(|| format!("Configuration error for namespace {}", ns_config.name))?;
 
-            if existing_info.is_none() {
+            let Some(existing_info) = existing_info else {
                 info!(namespace = ?namespace_info, "Adding new namespace to repository");
-                namespace_repository
+                match namespace_repository
                     .add_namespace(namespace_info.clone())
                     .await
-                    .with_context(|| format!("Failed to add namespace {}", ns_config.name))?;
-            } else if changed {
-                info!(
-                    namespace = ns_config.name,
-                    old_status = ?existing_info.unwrap().status,
-                    new_status = ?namespace_info.status,
-                    "Updating namespace status in repository"
-                );
+                {
+                    Ok(()) => {}
+                    Err(NamespaceRepositoryError::NamespaceAlreadyExists(_)) => {
+                        warn!(
+                            namespace = ns_config.name,
+                            "Namespace was added concurrently, reapplying configuration"
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        return Err(e).with_context(|| {
+                            format!("Failed to add namespace {}", ns_config.name)
+                        });
+                    }
+                }
                 namespace_repository
-                    .update_namespace(namespace_info.clone())
+                    .record_status_transition(StatusTransition::added(
+                        &namespace_info,
+                        StatusActor::Config,
+                    ))
                     .await
-                    .with_context(|| format!("Failed to update namespace {}", ns_config.name))?;
-            } else {
+                    .with_context(|| {
+                        format!("Failed to record status of namespace {}", ns_config.name)
+                    })?;
+                return Ok(());
+            };
+
+            if !changed {
                 info!(
                     namespace = ns_config.name,
                     "Using existing namespace from repository (no changes)"
                 );
+                return Ok(());
+            }
+
+            info!(
+                namespace = ns_config.name,
+                old_status = ?existing_info.status,
+                new_status = ?namespace_info.status,
+                "Updating namespace status in repository"
+            );
+            match namespace_repository
+                .compare_and_swap_namespace(namespace_info.clone())
+                .await
+            {
+                Ok(_) => {}
+                Err(NamespaceRepositoryError::VersionConflict {
+                    expected, actual, ..
+                }) => {
+                    warn!(
+                        namespace = ns_config.name,
+                        expected,
+                        actual,
+                        "Namespace was modified concurrently, reapplying configuration"
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to update namespace {}", ns_config.name));
+                }
             }
+
+            if existing_info.status != namespace_info.status {
+                namespace_repository
+                    .record_status_transition(StatusTransition::new(
+                        &existing_info,
+                        namespace_info.status.clone(),
+                        existing_info.last_verified_epoch,
+                        Some("status changed in configuration".to_string()),
+                        StatusActor::Config,
+                    ))
+                    .await
+                    .with_context(|| {
+                        format!("Failed to record status of namespace {}", ns_config.name)
+                    })?;
+            }
+            return Ok(());
         }
 
-        Ok(())
+        anyhow::bail!(
+            "Namespace {} kept changing while applying its configuration",
+            ns_config.name
+        )
     }
 }
@@ -10,6 +10,19 @@ use std::time::Duration;
 /// Default constant for sleep duration between audit cycles.= 30 seconds
 const DEFAULT_SLEEP_SECONDS: u64 = 30;
 
+/// Default constant for the interval between signed heartbeats = 5 minutes
+const DEFAULT_HEARTBEAT_SECONDS: u64 = 300;
+
+/// Default constant for the interval between signed transparency log tree heads = 5 minutes
+const DEFAULT_TREE_HEAD_SECONDS: u64 = 300;
+
+/// Minimum length of an admin API token
+const MIN_ADMIN_TOKEN_LENGTH: usize = 32;
+
+/// Default constant for how long a namespace auditor may go without a successful cycle before it
+/// is reported as stale = 15 minutes
+const DEFAULT_HEALTH_STALE_SECONDS: u64 = 900;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum AkdConfigurationType {
     WhatsAppV1,
@@ -22,6 +35,23 @@ pub enum ConfigNamespaceStatus {
     Disabled,
 }
 
+/// A static bearer token accepted by the admin API
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AdminToken {
+    /// Name of the operator or system holding the token, recorded with every action it takes
+    pub name: String,
+    pub token: String,
+}
+
+impl std::fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminToken")
+            .field("name", &self.name)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AuditorConfig {
     /// How long to sleep between audit cycles
@@ -29,6 +59,35 @@ pub struct AuditorConfig {
     #[serde(default = "default_sleep_seconds")]
     pub sleep_seconds: u64,
 
+    /// How often to sign a freshness heartbeat for each namespace, in seconds
+    /// Defaults to 5 minutes
+    #[serde(default = "default_heartbeat_seconds")]
+    pub heartbeat_seconds: u64,
+
+    /// How often to sign and publish a new transparency log tree head, in seconds
+    /// Defaults to 5 minutes
+    #[serde(default = "default_tree_head_seconds")]
+    pub tree_head_seconds: u64,
+
+    /// Address to bind the auditor's health listener to
+    /// Defaults to 127.0.0.1:3002
+    #[serde(default = "default_health_bind_address")]
+    pub health_bind_address: String,
+
+    /// How long a namespace auditor may go without a successful audit cycle before the health
+    /// listener reports it as stale, in seconds
+    /// Defaults to 15 minutes
+    #[serde(default = "default_health_stale_seconds")]
+    pub health_stale_seconds: u64,
+
+    /// Address to bind the admin API to
+    /// The admin API is disabled if unset
+    pub admin_bind_address: Option<String>,
+
+    /// Bearer tokens accepted by the admin API
+    #[serde(default)]
+    pub admin_tokens: Vec<AdminToken>,
+
     /// Directory for storing runtime data (e.g. namespace info, signatures)
     data_directory: Option<String>,
 
@@ -147,6 +206,26 @@ impl AuditorConfig {
             )));
         }
 
+        if self.tree_head_seconds == 0 {
+            return Err(ConfigError::Message(
+                "tree_head_seconds must be greater than zero".to_string(),
+            ));
+        }
+
+        if let Err(e) = self.health_bind_address.parse::<std::net::SocketAddr>() {
+            return Err(ConfigError::Message(format!(
+                "Auditor health_bind_address is not a valid socket address: {e}"
+            )));
+        }
+        // Every cycle is followed by a sleep, so a shorter threshold would report idle auditors
+        if self.health_stale_seconds <= self.sleep_seconds {
+            return Err(ConfigError::Message(
+                "health_stale_seconds must be greater than sleep_seconds".to_string(),
+            ));
+        }
+
+        self.validate_admin()?;
+
         // Validate storage configuration
         self.namespace_storage.validate(data_directory)?;
         self.signature_storage.validate(data_directory)?;
@@ -160,10 +239,80 @@ impl AuditorConfig {
         Ok(())
     }
 
+    /// Validate the admin API settings, which only apply when `admin_bind_address` is set
+    fn validate_admin(&self) -> Result<(), ConfigError> {
+        let Some(admin_bind_address) = &self.admin_bind_address else {
+            return Ok(());
+        };
+        match admin_bind_address.parse::<std::net::SocketAddr>() {
+            Err(e) => {
+                return Err(ConfigError::Message(format!(
+                    "Auditor admin_bind_address is not a valid socket address: {e}"
+                )));
+            }
+            Ok(addr) if self.health_bind_address.parse() == Ok(addr) => {
+                return Err(ConfigError::Message(
+                    "Auditor admin_bind_address must differ from health_bind_address".to_string(),
+                ));
+            }
+            Ok(_) => {}
+        }
+        if self.admin_tokens.is_empty() {
+            return Err(ConfigError::Message(
+                "admin_tokens must not be empty when admin_bind_address is set".to_string(),
+            ));
+        }
+        for admin_token in &self.admin_tokens {
+            if admin_token.name.is_empty() {
+                return Err(ConfigError::Message(
+                    "Admin token names cannot be empty".to_string(),
+                ));
+            }
+            if admin_token.token.len() < MIN_ADMIN_TOKEN_LENGTH {
+                return Err(ConfigError::Message(format!(
+                    "Admin token {} must be at least {MIN_ADMIN_TOKEN_LENGTH} characters long",
+                    admin_token.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the socket address to bind the admin API to, if it is enabled
+    /// Will panic if the configured admin_bind_address string is not valid
+    pub fn admin_socket_addr(&self) -> Option<std::net::SocketAddr> {
+        self.admin_bind_address
+            .as_ref()
+            .map(|address| address.parse().expect("Failed to parse admin bind address"))
+    }
+
     /// Get sleep duration as Duration type
     pub fn sleep_duration(&self) -> Duration {
         Duration::from_secs(self.sleep_seconds)
     }
+
+    /// Get heartbeat interval as Duration type
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_seconds)
+    }
+
+    /// Get transparency log tree head interval as Duration type
+    pub fn tree_head_interval(&self) -> Duration {
+        Duration::from_secs(self.tree_head_seconds)
+    }
+
+    /// Get the time without a successful audit cycle after which a namespace auditor is stale
+    pub fn health_stale_after(&self) -> Duration {
+        Duration::from_secs(self.health_stale_seconds)
+    }
+
+    /// Get the socket address to bind the health listener to
+    /// Will panic if the configured health_bind_address string is not valid
+    pub fn health_socket_addr(&self) -> std::net::SocketAddr {
+        self.health_bind_address
+            .parse()
+            .expect("Failed to parse health bind address")
+    }
 }
 
 impl NamespaceConfig {
@@ -204,6 +353,8 @@ impl NamespaceConfig {
             last_verified_epoch,
             starting_epoch,
             status,
+            // Keep the version that was read so the update can be applied with compare-and-swap
+            version: existing_namespace_info.map_or(0, |info| info.version),
         };
 
         let changed = status_changed || last_verified_epoch_changed;
@@ -227,9 +378,11 @@ impl NamespaceConfig {
             // New namespace - use config status, no change to report
             None => (desired_status, false),
             // Error states are preserved and never count as changes
-            Some(NamespaceStatus::SignatureLost | NamespaceStatus::SignatureVerificationFailed) => {
-                (existing_status.unwrap().clone(), false)
-            }
+            Some(
+                NamespaceStatus::SignatureLost
+                | NamespaceStatus::SignatureVerificationFailed
+                | NamespaceStatus::SignatureConflict,
+            ) => (existing_status.unwrap().clone(), false),
             // All other states can transition normally
             Some(current_status) => {
                 let changed = *current_status != desired_status;
@@ -262,6 +415,22 @@ fn default_sleep_seconds() -> u64 {
     DEFAULT_SLEEP_SECONDS
 }
 
+fn default_heartbeat_seconds() -> u64 {
+    DEFAULT_HEARTBEAT_SECONDS
+}
+
+fn default_tree_head_seconds() -> u64 {
+    DEFAULT_TREE_HEAD_SECONDS
+}
+
+fn default_health_stale_seconds() -> u64 {
+    DEFAULT_HEALTH_STALE_SECONDS
+}
+
+fn default_health_bind_address() -> String {
+    "127.0.0.1:3002".to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use akd_watch_common::akd_configurations::AkdConfiguration;
@@ -293,6 +462,7 @@ mod tests {
             last_verified_epoch: Some(10u64.into()),
             starting_epoch: 5u64.into(),
             status: NamespaceStatus::Online,
+            version: 0,
         };
 
         let (namespace_info, status_changed) = namespace_config
@@ -354,6 +524,7 @@ mod tests {
             last_verified_epoch: Some(5u64.into()),
             starting_epoch: 1u64.into(),
             status: NamespaceStatus::Online,
+            version: 0,
         };
 
         let (info, changed) = disabled_config
@@ -373,6 +544,7 @@ mod tests {
             last_verified_epoch: Some(5u64.into()),
             starting_epoch: 1u64.into(),
             status: NamespaceStatus::Initialization,
+            version: 0,
         };
 
         let (info, changed) = disabled_config
@@ -392,6 +564,7 @@ mod tests {
             last_verified_epoch: Some(5u64.into()),
             starting_epoch: 1u64.into(),
             status: NamespaceStatus::SignatureLost,
+            version: 0,
         };
 
         let (info, changed) = disabled_config
@@ -408,6 +581,7 @@ mod tests {
             last_verified_epoch: Some(5u64.into()),
             starting_epoch: 1u64.into(),
             status: NamespaceStatus::SignatureVerificationFailed,
+            version: 0,
         };
 
         let (info, changed) = disabled_config
@@ -451,6 +625,13 @@ mod tests {
         ));
         assert!(!changed, "Error states should never be changed");
 
+        let (status, changed) = NamespaceConfig::resolve_status_transition(
+            &ConfigNamespaceStatus::Online,
+            Some(&NamespaceStatus::SignatureConflict),
+        );
+        assert!(matches!(status, NamespaceStatus::SignatureConflict));
+        assert!(!changed, "Error states should never be changed");
+
         // Test normal status transitions
         let (status, changed) = NamespaceConfig::resolve_status_transition(
             &ConfigNamespaceStatus::Disabled,
@@ -42,4 +42,75 @@ pub enum AuditError {
     NamespaceRepositoryError(
         #[from] akd_watch_common::storage::namespaces::NamespaceRepositoryError,
     ),
+    #[error("Transparency log error: {0}")]
+    TransparencyLogError(#[from] akd_watch_common::transparency_log::TransparencyLogError),
+}
+
+impl AuditError {
+    /// Short name of the error variant, used to label failure metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditError::SignatureNotFound(_) => "SignatureNotFound",
+            AuditError::StorageError(_) => "StorageError",
+            AuditError::SigningKeyError(_) => "SigningKeyError",
+            AuditError::VerifyingKeyError(_) => "VerifyingKeyError",
+            AuditError::SignatureStorageError(_) => "SignatureStorageError",
+            AuditError::VerifyError(_) => "VerifyError",
+            AuditError::LocalAuditorError(_) => "LocalAuditorError",
+            AuditError::BlobHashParseError(_) => "BlobHashParseError",
+            AuditError::AkdVerificationError(_) => "AkdVerificationError",
+            AuditError::SignError(_) => "SignError",
+            AuditError::NamespaceRepositoryError(_) => "NamespaceRepositoryError",
+            AuditError::TransparencyLogError(_) => "TransparencyLogError",
+        }
+    }
+}
+
+/// Errors returned by the admin API
+#[derive(Debug, thiserror::Error)]
+pub enum AdminError {
+    #[error("Missing or invalid bearer token")]
+    Unauthorized,
+    #[error("Namespace not found: {0}")]
+    NamespaceNotFound(String),
+    #[error("Cannot {action} namespace {namespace} while it is {status:?}")]
+    InvalidTransition {
+        namespace: String,
+        status: akd_watch_common::NamespaceStatus,
+        action: &'static str,
+    },
+    #[error("Namespace {0} kept changing, try again")]
+    ConcurrentModification(String),
+    #[error("{0}")]
+    NamespaceRepositoryError(
+        #[from] akd_watch_common::storage::namespaces::NamespaceRepositoryError,
+    ),
+    #[error("Signing key error: {0}")]
+    SigningKeyError(#[from] akd_watch_common::storage::signing_keys::SigningKeyRepositoryError),
+}
+
+impl axum::response::IntoResponse for AdminError {
+    fn into_response(self) -> axum::response::Response {
+        use axum::http::{StatusCode, header};
+
+        let status = match self {
+            AdminError::Unauthorized => {
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, "Bearer")],
+                    self.to_string(),
+                )
+                    .into_response();
+            }
+            AdminError::NamespaceNotFound(_) => StatusCode::NOT_FOUND,
+            AdminError::InvalidTransition { .. } | AdminError::ConcurrentModification(_) => {
+                StatusCode::CONFLICT
+            }
+            AdminError::NamespaceRepositoryError(_) | AdminError::SigningKeyError(_) => {
+                tracing::error!(error = %self, "Admin action failed");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, self.to_string()).into_response()
+    }
 }
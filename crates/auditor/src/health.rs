@@ -0,0 +1,203 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use akd_watch_common::{metrics, storage::signing_keys::VerifyingKeyStorage};
+use anyhow::{Context, Result};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+    routing::get,
+};
+use serde::Serialize;
+use tokio::{net::TcpListener, sync::RwLock};
+use tracing::{info, instrument};
+
+/// Liveness of one namespace auditor task
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct NamespaceHealth {
+    /// Whether the task is still auditing. Tasks stop on shutdown and when the namespace is
+    /// disabled or fails an audit.
+    pub running: bool,
+    pub started_at: i64,
+    /// Unix time of the end of the last audit cycle that completed without error
+    pub last_successful_cycle: Option<i64>,
+    /// The error that stopped the task, if any
+    pub last_error: Option<String>,
+    /// A running task that has not completed a cycle within the stale threshold is likely wedged
+    pub stale: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub namespaces: BTreeMap<String, NamespaceHealth>,
+}
+
+/// Shared record of the namespace auditor tasks' progress, served by the health listener.
+#[derive(Clone)]
+pub(crate) struct AuditorHealth {
+    namespaces: Arc<RwLock<BTreeMap<String, NamespaceHealth>>>,
+    stale_after: Duration,
+}
+
+impl AuditorHealth {
+    pub(crate) fn new(stale_after: Duration) -> Self {
+        Self {
+            namespaces: Arc::new(RwLock::new(BTreeMap::new())),
+            stale_after,
+        }
+    }
+
+    pub(crate) async fn task_started(&self, namespace: &str) {
+        self.namespaces.write().await.insert(
+            namespace.to_string(),
+            NamespaceHealth {
+                running: true,
+                started_at: chrono::Utc::now().timestamp(),
+                last_successful_cycle: None,
+                last_error: None,
+                stale: false,
+            },
+        );
+    }
+
+    pub(crate) async fn cycle_succeeded(&self, namespace: &str) {
+        if let Some(health) = self.namespaces.write().await.get_mut(namespace) {
+            health.last_successful_cycle = Some(chrono::Utc::now().timestamp());
+        }
+    }
+
+    pub(crate) async fn task_stopped(&self, namespace: &str, error: Option<String>) {
+        if let Some(health) = self.namespaces.write().await.get_mut(namespace) {
+            health.running = false;
+            // Keep the error that stopped the task when it exits afterwards
+            health.last_error = error.or(health.last_error.take());
+        }
+    }
+
+    /// Reports every namespace task. The auditor is unhealthy if a running task is stale; stopped
+    /// tasks are reported but do not make it unhealthy, since restarting would not resume them.
+    pub(crate) async fn report(&self) -> HealthReport {
+        let now = chrono::Utc::now().timestamp();
+        let stale_after = i64::try_from(self.stale_after.as_secs()).unwrap_or(i64::MAX);
+        let namespaces: BTreeMap<_, _> = self
+            .namespaces
+            .read()
+            .await
+            .iter()
+            .map(|(name, health)| {
+                let last_progress = health.last_successful_cycle.unwrap_or(health.started_at);
+                let stale = health.running && now.saturating_sub(last_progress) > stale_after;
+                (
+                    name.clone(),
+                    NamespaceHealth {
+                        stale,
+                        ..health.clone()
+                    },
+                )
+            })
+            .collect();
+        HealthReport {
+            healthy: namespaces.values().all(|health| !health.stale),
+            namespaces,
+        }
+    }
+}
+
+/// State of the health listener
+#[derive(Clone)]
+struct ListenerState {
+    health: AuditorHealth,
+    verifying_key_storage: VerifyingKeyStorage,
+}
+
+#[instrument(skip_all)]
+async fn health_handler(
+    State(ListenerState { health, .. }): State<ListenerState>,
+) -> (StatusCode, Json<HealthReport>) {
+    let report = health.report().await;
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+#[instrument(skip_all)]
+async fn metrics_handler(
+    State(ListenerState {
+        verifying_key_storage,
+        ..
+    }): State<ListenerState>,
+) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::render(&verifying_key_storage).await,
+    )
+}
+
+/// Serves the health report at `/healthz` and Prometheus metrics at `/metrics` until the process
+/// exits
+pub(crate) async fn serve(
+    listener: TcpListener,
+    health: AuditorHealth,
+    verifying_key_storage: VerifyingKeyStorage,
+) -> Result<()> {
+    let app = Router::new()
+        .route("/healthz", get(health_handler))
+        .route("/metrics", get(metrics_handler))
+        .with_state(ListenerState {
+            health,
+            verifying_key_storage,
+        });
+    info!(address = ?listener.local_addr().ok(), "Serving auditor health");
+    axum::serve(listener, app.into_make_service())
+        .await
+        .context("Health listener failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_marks_running_tasks_without_progress_stale() {
+        let health = AuditorHealth::new(Duration::from_secs(60));
+        health.task_started("idle").await;
+        health.cycle_succeeded("idle").await;
+        health.task_started("wedged").await;
+        health.task_started("disabled").await;
+        health
+            .task_stopped(
+                "disabled",
+                Some("Namespace disabled is not online".to_string()),
+            )
+            .await;
+        // Backdate the wedged and disabled tasks past the stale threshold
+        for name in ["wedged", "disabled"] {
+            health
+                .namespaces
+                .write()
+                .await
+                .get_mut(name)
+                .unwrap()
+                .started_at -= 120;
+        }
+
+        let report = health.report().await;
+        assert!(!report.healthy, "A wedged task makes the auditor unhealthy");
+        assert!(!report.namespaces["idle"].stale);
+        assert!(report.namespaces["idle"].last_successful_cycle.is_some());
+        assert!(report.namespaces["wedged"].stale);
+        assert!(!report.namespaces["disabled"].running);
+        assert!(
+            !report.namespaces["disabled"].stale,
+            "Stopped tasks are not stale"
+        );
+
+        health.cycle_succeeded("wedged").await;
+        assert!(health.report().await.healthy);
+    }
+}
@@ -1,17 +1,38 @@
-use anyhow::Result;
+use akd_watch_common::events::AuditEvents;
+use anyhow::{Context, Result};
+use tokio::net::TcpListener;
 use tokio::sync::broadcast::Receiver;
 use tracing::{error, info, instrument, trace};
 
+mod admin;
 mod auditor_app;
 mod config;
 mod error;
+mod health;
 mod namespace_auditor;
+mod offline;
+mod replica_reconciler;
+mod tree_head_publisher;
 
 use auditor_app::AuditorApp;
 use config::AuditorConfig;
+pub use offline::{export, import, scan, verify_bundle, verify_evidence};
 
-#[instrument(skip_all, name = "start_auditor")]
 pub async fn start(shutdown_signal: &mut Receiver<()>) -> Result<()> {
+    run(shutdown_signal, None).await
+}
+
+/// Starts the auditor, publishing its stored signatures and status transitions to `events`.
+/// Used when the web server runs in the same process.
+pub async fn start_with_events(
+    shutdown_signal: &mut Receiver<()>,
+    events: AuditEvents,
+) -> Result<()> {
+    run(shutdown_signal, Some(events)).await
+}
+
+#[instrument(skip_all, name = "start_auditor")]
+async fn run(shutdown_signal: &mut Receiver<()>, events: Option<AuditEvents>) -> Result<()> {
     trace!("Starting auditor application");
 
     let config = AuditorConfig::load()
@@ -22,7 +43,38 @@ pub async fn start(shutdown_signal: &mut Receiver<()>) -> Result<()> {
         config.namespaces.len()
     );
 
+    akd_watch_common::metrics::install();
+    let health_listener = TcpListener::bind(config.health_socket_addr())
+        .await
+        .context("Health listener binding failed")?;
+    let admin_listener = match config.admin_socket_addr() {
+        Some(addr) => Some(
+            TcpListener::bind(addr)
+                .await
+                .context("Admin listener binding failed")?,
+        ),
+        None => None,
+    };
+    let admin_tokens = config.admin_tokens.clone();
     let mut app = AuditorApp::from_config(config).await?;
+    let health = app.health();
+    let verifying_key_storage = app.verifying_key_storage().await?;
+    tokio::spawn(async move {
+        if let Err(e) = health::serve(health_listener, health, verifying_key_storage).await {
+            error!(error = %e, "Health listener stopped");
+        }
+    });
+    if let Some(events) = events {
+        app = app.with_events(events);
+    }
+    if let Some(admin_listener) = admin_listener {
+        let admin = app.admin(admin_tokens);
+        tokio::spawn(async move {
+            if let Err(e) = admin::serve(admin_listener, admin).await {
+                error!(error = %e, "Admin listener stopped");
+            }
+        });
+    }
 
     // Handle graceful shutdown with signal handling at the application level
     tokio::select! {
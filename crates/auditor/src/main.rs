@@ -1,11 +1,24 @@
-use akd_watch_auditor::start;
+use akd_watch_auditor::{export, import, scan, start, verify_bundle, verify_evidence};
+use akd_watch_common::{
+    bundle::AuditBundle, crypto::VerifyingKey, evidence::EvidenceRecord,
+    observability::Observability,
+};
+use anyhow::{Context, Result};
 use tracing::{error, info};
 
+const USAGE: &str = "usage: akd_watch_auditor [scan [namespace] | export <namespace> <bundle> [first_epoch] [last_epoch] | import <bundle> | verify-bundle <bundle> | verify-evidence <evidence> <published_keys>]";
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if let Some(&command) = args.first() {
+        std::process::exit(run_command(command, &args[1..]).await);
+    }
+
+    let observability = Observability::from_config("akd-watch-auditor")
+        .and_then(Observability::init)
+        .expect("Failed to initialize observability");
 
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel(1);
 
@@ -19,8 +32,101 @@ async fn main() {
         result = handle => {
             if let Err(e) = result {
                 error!(error = ?e, "Application error");
+                drop(observability);
                 std::process::exit(1);
             }
         }
     }
 }
+
+/// Runs an offline command, returning the process exit code
+async fn run_command(command: &str, args: &[&str]) -> i32 {
+    // Keep stdout for reports
+    let _observability = match Observability::from_config("akd-watch-auditor")
+        .and_then(|observability| observability.with_stderr().init())
+    {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+
+    let result = match (command, args) {
+        ("scan", [] | [_]) => scan(args.first().copied())
+            .await
+            .and_then(|reports| print_reports(&reports, reports.iter().all(|r| r.is_clean()))),
+        ("export", [namespace, path, range @ ..]) if range.len() <= 2 => {
+            run_export(namespace, path, range).await.map(|()| 0)
+        }
+        ("import", [path]) => match read_bundle(path) {
+            Ok(bundle) => import(&bundle)
+                .await
+                .and_then(|report| print_reports(&report, true)),
+            Err(e) => Err(e),
+        },
+        ("verify-bundle", [path]) => match read_bundle(path) {
+            Ok(bundle) => verify_bundle(&bundle)
+                .await
+                .and_then(|report| print_reports(&report, report.is_clean())),
+            Err(e) => Err(e),
+        },
+        ("verify-evidence", [path, keys_path]) => {
+            match read_evidence(path).and_then(|record| Ok((record, read_keys(keys_path)?))) {
+                Ok((record, keys)) => verify_evidence(&record, keys)
+                    .await
+                    .and_then(|report| print_reports(&report, report.is_confirmed())),
+                Err(e) => Err(e),
+            }
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return 1;
+        }
+    };
+
+    result.unwrap_or_else(|e| {
+        error!(error = %e, command, "Command failed");
+        1
+    })
+}
+
+/// Prints a report as JSON on stdout. Returns exit code 0 if the report is clean, 2 otherwise.
+fn print_reports(reports: &impl serde::Serialize, clean: bool) -> Result<i32> {
+    println!("{}", serde_json::to_string_pretty(reports)?);
+    Ok(if clean { 0 } else { 2 })
+}
+
+async fn run_export(namespace: &str, path: &str, range: &[&str]) -> Result<()> {
+    let parse = |index: usize, default: u64| -> Result<u64> {
+        range.get(index).map_or(Ok(default), |epoch| {
+            epoch
+                .parse()
+                .with_context(|| format!("Invalid epoch {epoch}"))
+        })
+    };
+    let bundle = export(namespace, parse(0, 0)?..=parse(1, u64::MAX)?).await?;
+    std::fs::write(path, bundle.to_json()?).with_context(|| format!("Failed to write {path}"))?;
+    info!(
+        path,
+        signatures = bundle.signatures.len(),
+        "Wrote audit bundle"
+    );
+    Ok(())
+}
+
+fn read_bundle(path: &str) -> Result<AuditBundle> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+    Ok(AuditBundle::from_json(&bytes)?)
+}
+
+fn read_evidence(path: &str) -> Result<EvidenceRecord> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+    Ok(EvidenceRecord::from_json(&bytes)?)
+}
+
+/// Reads the auditor's published keys, as served by the web server's `/info` endpoint
+fn read_keys(path: &str) -> Result<Vec<VerifyingKey>> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+    VerifyingKey::from_published_json(&bytes).map_err(anyhow::Error::msg)
+}
@@ -1,24 +1,34 @@
-use akd_watch_common::{NamespaceStatus, timed_event};
+use akd::local_auditing::AuditBlob;
+use akd_watch_common::{NamespaceStatus, StatusActor, StatusTransition, timed_event};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 use akd_watch_common::{
-    EpochSignature, NamespaceInfo, SerializableAuditBlobName,
+    EpochSignature, Heartbeat, NamespaceInfo, SerializableAuditBlobName,
     akd_configurations::verify_consecutive_append_only,
     akd_storage_factory::AkdStorageFactory,
+    events::{AuditEvent, AuditEvents},
+    evidence::{EvidenceRecord, EvidenceStore},
+    metrics,
     storage::{
-        AkdStorage, namespaces::NamespaceRepository, signatures::SignatureRepository,
+        AkdStorage,
+        namespaces::{NamespaceRepository, NamespaceRepositoryError},
+        signatures::{SignatureRepository, SignatureRepositoryError},
         signing_keys::SigningKeyRepository,
     },
+    transparency_log::{TransparencyLog, TransparencyLogError},
 };
 use anyhow::Result;
 use tokio::sync::broadcast::Receiver;
 use tracing::{debug, error, info, instrument, trace, warn};
 
 use crate::error::AuditError;
+use crate::health::AuditorHealth;
 
 const MAX_EPOCHS_PER_POLL: usize = 50;
+/// Compare-and-swap attempts before a namespace update gives up on concurrent writers
+pub(crate) const MAX_UPDATE_ATTEMPTS: usize = 5;
 
 /// Service responsible for auditing a single namespace
 pub struct NamespaceAuditor<NR, SKR, SS> {
@@ -27,6 +37,12 @@ pub struct NamespaceAuditor<NR, SKR, SS> {
     signing_key_repository: Arc<RwLock<SKR>>,
     signature_storage: SS,
     sleep_duration: Duration,
+    heartbeat_interval: Duration,
+    last_heartbeat: Option<tokio::time::Instant>,
+    transparency_log: Option<Arc<Mutex<TransparencyLog>>>,
+    evidence_store: Option<EvidenceStore>,
+    events: Option<AuditEvents>,
+    health: Option<AuditorHealth>,
     shutdown_rx: Receiver<()>,
 }
 
@@ -42,6 +58,7 @@ where
         signing_key_repository: Arc<RwLock<SKR>>,
         signature_storage: SS,
         sleep_duration: Duration,
+        heartbeat_interval: Duration,
         shutdown_rx: Receiver<()>,
     ) -> Self {
         Self {
@@ -50,19 +67,61 @@ where
             signing_key_repository,
             signature_storage,
             sleep_duration,
+            heartbeat_interval,
+            last_heartbeat: None,
+            transparency_log: None,
+            evidence_store: None,
+            events: None,
+            health: None,
             shutdown_rx,
         }
     }
 
+    /// Records every signature this auditor emits in `transparency_log` before storing it
+    pub fn with_transparency_log(mut self, transparency_log: Arc<Mutex<TransparencyLog>>) -> Self {
+        self.transparency_log = Some(transparency_log);
+        self
+    }
+
+    /// Stores signed evidence in `evidence_store` whenever an audit blob fails verification
+    pub fn with_evidence_store(mut self, evidence_store: EvidenceStore) -> Self {
+        self.evidence_store = Some(evidence_store);
+        self
+    }
+
+    /// Publishes every stored signature and recorded status transition to `events`
+    pub fn with_events(mut self, events: AuditEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Reports this auditor's task liveness and successful cycles to `health`
+    pub(crate) fn with_health(mut self, health: AuditorHealth) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    fn publish(&self, event: impl FnOnce() -> AuditEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event());
+        }
+    }
+
     /// Start the auditing loop for this namespace
     #[instrument(level = "info", skip_all, fields(namespace = self.namespace_name))]
     pub async fn run(mut self) -> Result<()> {
+        if let Some(health) = &self.health {
+            health.task_started(&self.namespace_name).await;
+        }
         loop {
             let should_shutdown = self.audit_cycle().await;
             if should_shutdown {
                 break;
             }
         }
+        if let Some(health) = &self.health {
+            health.task_stopped(&self.namespace_name, None).await;
+        }
 
         info!(namespace = ?self.namespace_name, "Namespace auditor stopped");
         Ok(())
@@ -81,6 +140,18 @@ where
                     processed_count,
                     "Audit cycle complete"
                 );
+                if let Some(health) = &self.health {
+                    health.cycle_succeeded(&self.namespace_name).await;
+                }
+
+                if let Err(e) = self.maybe_emit_heartbeat().await {
+                    // A missed heartbeat is visible to clients as staleness, so it is not fatal here
+                    warn!(
+                        namespace = self.namespace_name,
+                        error = %e,
+                        "Failed to emit heartbeat"
+                    );
+                }
 
                 self.interruptible_sleep(&processed_count).await
             }
@@ -90,6 +161,11 @@ where
                     error = %e,
                     "Critical audit failure - stopping namespace auditor"
                 );
+                if let Some(health) = &self.health {
+                    health
+                        .task_stopped(&self.namespace_name, Some(e.to_string()))
+                        .await;
+                }
                 // TODO: Consider whether we should attempt recovery or permanently stop
                 // For now, we stop the auditor when audit failures occur
                 true // Signal shutdown
@@ -130,7 +206,54 @@ where
         }
     }
 
+    /// Signs and stores a heartbeat for the latest verified epoch if the heartbeat interval has
+    /// elapsed since the last heartbeat was emitted.
+    /// Returns true if a heartbeat was stored, false if none was due or nothing has been verified yet
+    async fn maybe_emit_heartbeat(&mut self) -> Result<bool> {
+        if self
+            .last_heartbeat
+            .is_some_and(|last| last.elapsed() < self.heartbeat_interval)
+        {
+            return Ok(false);
+        }
+
+        let namespace_info = self.get_fresh_namespace_info().await?;
+        let Some(latest_epoch) = namespace_info.last_verified_epoch else {
+            trace!(
+                namespace = self.namespace_name,
+                "No verified epoch yet, skipping heartbeat"
+            );
+            return Ok(false);
+        };
+
+        let latest_signature = self
+            .get_and_verify_signature(latest_epoch.value())
+            .await?
+            .ok_or(AuditError::SignatureNotFound(latest_epoch))?;
+        let current_signing_key = self
+            .signing_key_repository
+            .read()
+            .await
+            .get_current_signing_key()
+            .await?;
+        let heartbeat = Heartbeat::sign(
+            &namespace_info.name,
+            &latest_signature,
+            &current_signing_key,
+        )?;
+
+        self.signature_storage.set_heartbeat(heartbeat).await?;
+        self.last_heartbeat = Some(tokio::time::Instant::now());
+        debug!(
+            namespace = self.namespace_name,
+            epoch = %latest_epoch,
+            "Stored heartbeat"
+        );
+        Ok(true)
+    }
+
     /// Perform one complete audit cycle
+    #[instrument(level = "info", skip_all, fields(namespace = self.namespace_name))]
     async fn run_audit_cycle(&mut self) -> Result<usize> {
         // Refresh namespace info from repository
         let namespace_info = self.get_fresh_namespace_info().await?;
@@ -138,6 +261,10 @@ where
             namespace = ?namespace_info,
             "Running audit cycle for namespace"
         );
+        metrics::record_namespace_status(&namespace_info.name, &namespace_info.status);
+        if let Some(epoch) = &namespace_info.last_verified_epoch {
+            metrics::record_last_verified_epoch(&namespace_info.name, *epoch.value());
+        }
 
         // Refuse to audit if the namespace is disabled or in a failed state
         if !namespace_info.status.is_active() {
@@ -162,6 +289,19 @@ where
             count = blob_names.len(),
             "Polled for new epochs"
         );
+        // The batch is capped, so only a full batch can stop short of the log head
+        let head = match blob_names.last() {
+            Some(last) if blob_names.len() >= MAX_EPOCHS_PER_POLL => {
+                self.find_log_head(&namespace_info, last.epoch).await
+            }
+            Some(last) => last.epoch,
+            None => 0,
+        };
+        if let Some(first) = blob_names.first() {
+            metrics::record_epochs_behind_head(&namespace_info.name, head - first.epoch + 1);
+        } else {
+            metrics::record_epochs_behind_head(&namespace_info.name, 0);
+        }
 
         if !blob_names.is_empty() {
             trace!(
@@ -206,11 +346,12 @@ where
                 ));
             } else {
                 // record the successful audit
-                let mut repo = self.namespace_repository.write().await;
-                repo.update_namespace(
-                    namespace_info.update_last_verified_epoch(blob_name.epoch.into()),
-                )
+                self.update_namespace_info(|current| {
+                    current.update_last_verified_epoch(blob_name.epoch.into())
+                })
                 .await?;
+                metrics::record_last_verified_epoch(&namespace_info.name, blob_name.epoch);
+                metrics::record_epochs_behind_head(&namespace_info.name, head - blob_name.epoch);
             }
         }
 
@@ -228,18 +369,46 @@ where
             error = %error,
             "Handling audit failure"
         );
+        metrics::record_audit_failure(&self.namespace_name, error.kind());
 
-        match error {
+        let status = match error {
             AuditError::SignatureNotFound(epoch) => {
                 error!(
                 namespace = namespace_info.name,
                 epoch = %epoch,
                 "Signature not found for epoch - this may indicate a gap in the audit chain"
                 );
-                // Update namespace to indicate signature storage failure, not AKD failure
-                let mut repo = self.namespace_repository.write().await;
-                repo.update_namespace(namespace_info.update_status(NamespaceStatus::SignatureLost))
-                    .await?;
+                // Signature storage failure, not AKD failure
+                NamespaceStatus::SignatureLost
+            }
+            AuditError::SignatureStorageError(SignatureRepositoryError::SignatureConflict {
+                epoch,
+                existing_digest,
+                new_digest,
+            }) => {
+                error!(
+                    namespace = namespace_info.name,
+                    epoch,
+                    existing_digest,
+                    new_digest,
+                    "CRITICAL: a different signature is already stored for this epoch - signature storage may have been tampered with or another auditor is running"
+                );
+                NamespaceStatus::SignatureConflict
+            }
+            AuditError::TransparencyLogError(TransparencyLogError::ConflictingEntry {
+                epoch,
+                logged_digest,
+                new_digest,
+                ..
+            }) => {
+                error!(
+                    namespace = namespace_info.name,
+                    epoch,
+                    logged_digest,
+                    new_digest,
+                    "CRITICAL: the transparency log already holds a different digest for this epoch - refusing to sign it again"
+                );
+                NamespaceStatus::SignatureConflict
             }
             _ => {
                 error!(
@@ -249,17 +418,65 @@ where
                     error = %error,
                     "Audit request failed - stopping further processing for this namespace"
                 );
-                // Update namespace status to indicate failure
-                let mut repo = self.namespace_repository.write().await;
-                repo.update_namespace(
-                    namespace_info.update_status(NamespaceStatus::SignatureVerificationFailed),
-                )
-                .await?;
+                NamespaceStatus::SignatureVerificationFailed
             }
         };
+
+        let previous = self
+            .update_namespace_info(|current| current.update_status(status.clone()))
+            .await?;
+        metrics::record_namespace_status(&self.namespace_name, &status);
+        let transition = StatusTransition::new(
+            &previous,
+            status,
+            Some(blob_name.epoch.into()),
+            Some(error.to_string()),
+            StatusActor::Auditor,
+        );
+        self.namespace_repository
+            .write()
+            .await
+            .record_status_transition(transition.clone())
+            .await?;
+        self.publish(|| AuditEvent::StatusChanged(transition));
         Ok(())
     }
 
+    /// Applies `update` to the stored namespace info with a compare-and-swap, re-reading the
+    /// namespace and retrying if another writer changed it in between. Returns the info that the
+    /// successful update was applied to.
+    async fn update_namespace_info(
+        &self,
+        update: impl Fn(&NamespaceInfo) -> NamespaceInfo,
+    ) -> Result<NamespaceInfo, AuditError> {
+        let mut repo = self.namespace_repository.write().await;
+        let mut attempt = 1;
+        loop {
+            let current = repo
+                .get_namespace_info(&self.namespace_name)
+                .await?
+                .ok_or_else(|| {
+                    NamespaceRepositoryError::NamespaceNotFound(self.namespace_name.clone())
+                })?;
+            match repo.compare_and_swap_namespace(update(&current)).await {
+                Ok(_) => return Ok(current),
+                Err(NamespaceRepositoryError::VersionConflict {
+                    expected, actual, ..
+                }) if attempt < MAX_UPDATE_ATTEMPTS => {
+                    debug!(
+                        namespace = self.namespace_name,
+                        expected,
+                        actual,
+                        attempt,
+                        "Namespace was modified concurrently, retrying update"
+                    );
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
     /// Get fresh namespace info from the repository
     async fn get_fresh_namespace_info(&self) -> Result<NamespaceInfo> {
         let repo = self.namespace_repository.read().await;
@@ -318,8 +535,42 @@ where
         Ok(result)
     }
 
+    /// Finds the latest epoch the AKD has published, given that `published_epoch` is published.
+    ///
+    /// The AKD can only be asked whether a given epoch is published, so this doubles the distance
+    /// probed past `published_epoch` until an epoch is missing, then bisects between the last
+    /// published and the first missing epoch. A namespace `n` epochs behind costs about `2 log2(n)`
+    /// requests.
+    #[instrument(level = "debug", skip_all, fields(namespace = namespace_info.name))]
+    async fn find_log_head(&self, namespace_info: &NamespaceInfo, published_epoch: u64) -> u64 {
+        let akd = AkdStorageFactory::create_storage(namespace_info);
+
+        let mut published = published_epoch;
+        let mut step = 1u64;
+        let mut missing = loop {
+            let Some(probe) = published.checked_add(step) else {
+                break u64::MAX;
+            };
+            if !akd.has_proof(&probe).await {
+                break probe;
+            }
+            published = probe;
+            step = step.saturating_mul(2);
+        };
+        while missing - published > 1 {
+            let probe = published + (missing - published) / 2;
+            if akd.has_proof(&probe).await {
+                published = probe;
+            } else {
+                missing = probe;
+            }
+        }
+        debug!(akd = %akd, head = published, "Found log head");
+        published
+    }
+
     /// Downloads the audit proof for the given `AuditRequest`, verifies it, and stores the signature if successful.
-    #[instrument(level = "debug", skip_all, fields(namespace = namespace_info.name, blob_name = blob_name.to_string()))]
+    #[instrument(level = "info", skip_all, fields(namespace = namespace_info.name, epoch = blob_name.epoch, blob_name = blob_name.to_string()))]
     async fn process_audit_request(
         &mut self,
         blob_name: &SerializableAuditBlobName,
@@ -379,18 +630,31 @@ where
         namespace_info: &NamespaceInfo,
     ) -> Result<(), AuditError> {
         // download the blob
-        let audit_blob = AkdStorageFactory::create_storage(namespace_info)
-            .get_proof(&blob_name.into())
-            .await?;
-        trace!(
+        let audit_blob =
+            timed_event!(histogram(metrics::download_duration(&namespace_info.name)) TRACE,
+            AkdStorageFactory::create_storage(namespace_info).get_proof(&blob_name.into());
             namespace = namespace_info.name,
             blob_name = blob_name.to_string(),
-            "Downloaded audit blob"
-        );
+            "Downloaded audit blob")
+            .await?;
+
+        let downloaded_at = chrono::Utc::now().timestamp();
 
         // decode the blob
-        let (end_epoch, previous_hash_from_blob, end_hash, proof) =
-            audit_blob.decode().map_err(AuditError::LocalAuditorError)?;
+        let (end_epoch, previous_hash_from_blob, end_hash, proof) = match audit_blob.decode() {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                self.record_evidence(
+                    namespace_info,
+                    &audit_blob,
+                    None,
+                    format!("{e:?}"),
+                    downloaded_at,
+                )
+                .await;
+                return Err(AuditError::LocalAuditorError(e));
+            }
+        };
 
         // Get and verify the previous epoch's signature to establish the chain
         let previous_hash = if blob_name.epoch == *namespace_info.starting_epoch.value() {
@@ -416,18 +680,82 @@ where
         };
 
         // verify the proof using the chained previous hash
-        verify_consecutive_append_only(
-            &namespace_info.configuration,
-            &proof,
-            previous_hash,
-            end_hash,
-            end_epoch,
-        )
-        .await?;
+        let verification =
+            timed_event!(histogram(metrics::verification_duration(&namespace_info.name)) TRACE,
+            verify_consecutive_append_only(
+                &namespace_info.configuration,
+                &proof,
+                previous_hash,
+                end_hash,
+                end_epoch,
+            );
+            namespace = namespace_info.name,
+            end_epoch = end_epoch,
+            "Checked append-only proof")
+            .await;
+        if let Err(e) = verification {
+            self.record_evidence(
+                namespace_info,
+                &audit_blob,
+                Some(previous_hash),
+                e.to_string(),
+                downloaded_at,
+            )
+            .await;
+            return Err(e.into());
+        }
         trace!(namespace = namespace_info.name, end_epoch, previous_hash = ?previous_hash, end_hash = ?end_hash, "Verified audit proof");
         Ok(())
     }
 
+    /// Signs and stores evidence that `audit_blob` failed verification. Failing to record evidence
+    /// is logged but does not replace the verification error.
+    async fn record_evidence(
+        &self,
+        namespace_info: &NamespaceInfo,
+        audit_blob: &AuditBlob,
+        previous_digest: Option<[u8; 32]>,
+        error: String,
+        downloaded_at: i64,
+    ) {
+        let Some(evidence_store) = &self.evidence_store else {
+            return;
+        };
+        let result = async {
+            let signing_key = self
+                .signing_key_repository
+                .read()
+                .await
+                .get_current_signing_key()
+                .await?;
+            let record = EvidenceRecord::sign(
+                namespace_info,
+                audit_blob,
+                previous_digest,
+                error,
+                downloaded_at,
+                &signing_key,
+            )?;
+            Ok::<_, anyhow::Error>(evidence_store.save(&record)?)
+        }
+        .await;
+
+        match result {
+            Ok(id) => warn!(
+                namespace = namespace_info.name,
+                epoch = audit_blob.name.epoch,
+                evidence_id = id,
+                "Recorded evidence of failed verification"
+            ),
+            Err(e) => error!(
+                namespace = namespace_info.name,
+                epoch = audit_blob.name.epoch,
+                error = %e,
+                "Failed to record evidence of failed verification"
+            ),
+        }
+    }
+
     async fn sign_blob(
         &mut self,
         blob_name: &SerializableAuditBlobName,
@@ -450,14 +778,24 @@ where
             blob_name.epoch, "Signed audit proof"
         );
 
+        // log the signature before it becomes visible, so every served signature is in the log
+        if let Some(transparency_log) = &self.transparency_log {
+            let index = transparency_log.lock().await.append(&signature)?;
+            trace!(
+                namespace = namespace_info.name,
+                blob_name.epoch, index, "Logged signature in transparency log"
+            );
+        }
+
         // store the signature
         self.signature_storage
-            .set_signature(&blob_name.epoch, signature)
+            .set_signature(&blob_name.epoch, signature.clone())
             .await?;
         trace!(
             namespace = namespace_info.name,
             blob_name.epoch, "Stored signature for audit proof"
         );
+        self.publish(|| AuditEvent::SignatureStored(signature));
         Ok(())
     }
 }
@@ -481,6 +819,7 @@ mod tests {
     use akd_watch_common::{
         Epoch, NamespaceStatus,
         akd_configurations::AkdConfiguration,
+        evidence::RecheckOutcome,
         storage::test_akd_storage::TestAkdStorage,
         testing::{MockNamespaceRepository, MockSignatureStorage, MockSigningKeyRepository},
     };
@@ -495,6 +834,7 @@ mod tests {
             log_directory: "test".to_string(),
             last_verified_epoch: Some(Epoch::new(0)),
             status: NamespaceStatus::Online,
+            version: 0,
         }
     }
 
@@ -576,6 +916,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -599,6 +940,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -619,6 +961,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -655,6 +998,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -665,6 +1009,33 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_find_log_head() {
+        let (namespace_repo, signing_key_repo, signature_storage, shutdown_rx, _shutdown_tx) =
+            create_test_components();
+        let namespace_info = create_test_namespace("test-namespace", 1);
+        let auditor = NamespaceAuditor::new(
+            namespace_info.clone(),
+            Arc::new(RwLock::new(namespace_repo)),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage,
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        );
+
+        // The test AKD publishes epochs 1 to 100, beyond what a single poll returns
+        for published_epoch in [1, MAX_EPOCHS_PER_POLL as u64, 99, 100] {
+            assert_eq!(
+                auditor
+                    .find_log_head(&namespace_info, published_epoch)
+                    .await,
+                100,
+                "Head found from epoch {published_epoch}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_poll_for_new_epochs() {
         let (namespace_repo, signing_key_repo, signature_storage, shutdown_rx, _shutdown_tx) =
@@ -677,6 +1048,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -724,6 +1096,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -760,6 +1133,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage,
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
 
@@ -777,6 +1151,64 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_failed_verification_records_evidence() {
+        let (namespace_repo, signing_key_repo, mut signature_storage, shutdown_rx, _shutdown_tx) =
+            create_test_components();
+        let namespace_info = create_test_namespace("test-namespace", 1);
+        let signing_key = signing_key_repo.get_current_signing_key().await.unwrap();
+        let previous = EpochSignature::sign(
+            namespace_info.clone(),
+            Epoch::new(1),
+            TestAkdStorage::hash(1),
+            &signing_key,
+        )
+        .unwrap();
+        signature_storage.set_signature(&1, previous).await.unwrap();
+        let data_directory =
+            std::env::temp_dir().join(format!("akd_watch_evidence_{}", std::process::id()));
+        let evidence_store = EvidenceStore::new(data_directory.to_str().unwrap());
+        let verifying_keys = signing_key_repo.verifying_key_repository().unwrap();
+
+        // The test AKD serves an empty proof, which cannot connect epoch 1 to epoch 2
+        let blob_name = SerializableAuditBlobName {
+            epoch: 2,
+            previous_hash: TestAkdStorage::hash(2),
+            current_hash: TestAkdStorage::hash(2),
+        };
+        let auditor = NamespaceAuditor::new(
+            namespace_info.clone(),
+            Arc::new(RwLock::new(namespace_repo)),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage,
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        )
+        .with_evidence_store(evidence_store.clone());
+
+        let error = auditor
+            .verify_blob(&blob_name, &namespace_info)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuditError::AkdVerificationError(_)));
+
+        let summaries = evidence_store.list("test-namespace").unwrap();
+        assert_eq!(summaries.len(), 1);
+        let record = evidence_store
+            .get("test-namespace", &summaries[0].id)
+            .unwrap()
+            .unwrap();
+        record.verify(&verifying_keys).await.unwrap();
+        assert_eq!(record.signing_key_id(), signing_key.key_id());
+        assert!(matches!(
+            record.recheck().await.unwrap(),
+            RecheckOutcome::Reproduced { .. }
+        ));
+
+        std::fs::remove_dir_all(&data_directory).unwrap();
+    }
+
     // TODO: verify epoch equal to starting epoch case, but this requires verifiable proof data or service we can mock the verify on
     // TODO: verify blob success case, but this requires verifiable proof data
 
@@ -797,6 +1229,7 @@ mod tests {
             Arc::new(RwLock::new(signing_key_repo)),
             signature_storage.clone(),
             Duration::from_millis(100),
+            Duration::from_secs(300),
             shutdown_rx,
         );
         // Sign the blob
@@ -813,6 +1246,265 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sign_blob_publishes_stored_signature() {
+        let (namespace_repo, signing_key_repo, signature_storage, shutdown_rx, _shutdown_tx) =
+            create_test_components();
+        let namespace_info = create_test_namespace("test-namespace", 1);
+        let blob_name = SerializableAuditBlobName {
+            epoch: 1,
+            previous_hash: TestAkdStorage::hash(1),
+            current_hash: TestAkdStorage::hash(1),
+        };
+        let events = AuditEvents::default();
+        let mut subscriber = events.subscribe();
+
+        let mut auditor = NamespaceAuditor::new(
+            namespace_info.clone(),
+            Arc::new(RwLock::new(namespace_repo)),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage.clone(),
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        )
+        .with_events(events);
+        auditor
+            .sign_blob(&blob_name, &namespace_info)
+            .await
+            .unwrap();
+
+        match subscriber.try_recv().unwrap() {
+            AuditEvent::SignatureStored(signature) => {
+                assert_eq!(signature.epoch(), Epoch::new(1));
+                assert_eq!(signature.namespace(), "test-namespace");
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+        assert!(
+            subscriber.try_recv().is_err(),
+            "Signing one blob should publish one event"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transparency_log_refuses_second_digest_after_storage_reset() {
+        let (namespace_repo, signing_key_repo, signature_storage, shutdown_rx, _shutdown_tx) =
+            create_test_components();
+        let namespace_info = create_test_namespace("test-namespace", 1);
+        let data_directory =
+            std::env::temp_dir().join(format!("akd_watch_auditor_log_{}", std::process::id()));
+        let transparency_log = Arc::new(tokio::sync::Mutex::new(
+            TransparencyLog::open_or_create(data_directory.to_str().unwrap()).unwrap(),
+        ));
+        let blob_name = |hash| SerializableAuditBlobName {
+            epoch: 1,
+            previous_hash: TestAkdStorage::hash(0),
+            current_hash: TestAkdStorage::hash(hash),
+        };
+
+        let mut auditor = NamespaceAuditor::new(
+            namespace_info.clone(),
+            Arc::new(RwLock::new(namespace_repo)),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage.clone(),
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        )
+        .with_transparency_log(transparency_log.clone());
+        auditor
+            .sign_blob(&blob_name(1), &namespace_info)
+            .await
+            .unwrap();
+        assert_eq!(
+            transparency_log.lock().await.find("test-namespace", 1),
+            Some(0)
+        );
+
+        // Signature storage is wiped, so only the log remembers the first digest
+        auditor.signature_storage = MockSignatureStorage::new();
+        let error = auditor
+            .sign_blob(&blob_name(2), &namespace_info)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AuditError::TransparencyLogError(TransparencyLogError::ConflictingEntry {
+                epoch: 1,
+                ..
+            })
+        ));
+        assert!(
+            auditor
+                .signature_storage
+                .get_signature(&1)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(transparency_log.lock().await.size(), 1);
+
+        std::fs::remove_dir_all(&data_directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_signature_marks_namespace_conflicted() {
+        let (
+            mut namespace_repo,
+            signing_key_repo,
+            mut signature_storage,
+            shutdown_rx,
+            _shutdown_tx,
+        ) = create_test_components();
+        let namespace_info = create_test_namespace("test-namespace", 1);
+        namespace_repo
+            .add_namespace(namespace_info.clone())
+            .await
+            .unwrap();
+        let blob_name = SerializableAuditBlobName {
+            epoch: 1,
+            previous_hash: TestAkdStorage::hash(0),
+            current_hash: TestAkdStorage::hash(1),
+        };
+
+        // Another signature with a different digest is already stored for the epoch
+        let signing_key = signing_key_repo.get_current_signing_key().await.unwrap();
+        let existing = EpochSignature::sign(
+            namespace_info.clone(),
+            Epoch::new(1),
+            TestAkdStorage::hash(2),
+            &signing_key,
+        )
+        .unwrap();
+        signature_storage.set_signature(&1, existing).await.unwrap();
+
+        let namespace_repo = Arc::new(RwLock::new(namespace_repo));
+        let mut auditor = NamespaceAuditor::new(
+            namespace_info.clone(),
+            namespace_repo.clone(),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage.clone(),
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        );
+
+        let error = auditor
+            .sign_blob(&blob_name, &namespace_info)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AuditError::SignatureStorageError(SignatureRepositoryError::SignatureConflict {
+                epoch: 1,
+                ..
+            })
+        ));
+
+        auditor
+            .handle_audit_failure(&namespace_info, &blob_name, &error)
+            .await
+            .unwrap();
+        let info = namespace_repo
+            .read()
+            .await
+            .get_namespace_info("test-namespace")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.status, NamespaceStatus::SignatureConflict);
+        let history = namespace_repo
+            .read()
+            .await
+            .status_history("test-namespace")
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].previous_status, Some(NamespaceStatus::Online));
+        assert_eq!(history[0].new_status, NamespaceStatus::SignatureConflict);
+        assert_eq!(history[0].epoch, Some(Epoch::new(1)));
+        assert_eq!(history[0].actor, StatusActor::Auditor);
+        assert!(history[0].reason.is_some());
+        // The original signature is kept
+        let stored = signature_storage.get_signature(&1).await.unwrap().unwrap();
+        assert_eq!(stored.digest(), TestAkdStorage::hash(2).to_vec());
+    }
+
     // TODO: Test failure to sign and set signature, requires mocking for signing and signature storage
     // TODO: test process_audit_request success and failure cases
+
+    #[tokio::test]
+    async fn test_maybe_emit_heartbeat_signs_latest_verified_epoch() {
+        let (
+            mut namespace_repo,
+            signing_key_repo,
+            mut signature_storage,
+            shutdown_rx,
+            _shutdown_tx,
+        ) = create_test_components();
+        let mut namespace_info = create_test_namespace("test-namespace", 1);
+        namespace_info.last_verified_epoch = Some(Epoch::new(1));
+        namespace_repo
+            .add_namespace(namespace_info.clone())
+            .await
+            .unwrap();
+
+        let signing_key = signing_key_repo.get_current_signing_key().await.unwrap();
+        let signature = EpochSignature::sign(
+            namespace_info.clone(),
+            Epoch::new(1),
+            TestAkdStorage::hash(1),
+            &signing_key,
+        )
+        .unwrap();
+        signature_storage
+            .set_signature(&1, signature)
+            .await
+            .unwrap();
+
+        let verifying_repo = signing_key_repo.verifying_key_repository().unwrap();
+        let mut auditor = NamespaceAuditor::new(
+            namespace_info,
+            Arc::new(RwLock::new(namespace_repo)),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage.clone(),
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        );
+
+        assert!(auditor.maybe_emit_heartbeat().await.unwrap());
+        let heartbeat = signature_storage.get_heartbeat().await.unwrap().unwrap();
+        assert_eq!(heartbeat.epoch(), Epoch::new(1));
+        heartbeat.verify(&verifying_repo).await.unwrap();
+
+        // A second heartbeat within the interval is skipped
+        assert!(!auditor.maybe_emit_heartbeat().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_maybe_emit_heartbeat_skips_without_verified_epoch() {
+        let (mut namespace_repo, signing_key_repo, signature_storage, shutdown_rx, _shutdown_tx) =
+            create_test_components();
+        let mut namespace_info = create_test_namespace("test-namespace", 1);
+        namespace_info.last_verified_epoch = None;
+        namespace_repo
+            .add_namespace(namespace_info.clone())
+            .await
+            .unwrap();
+
+        let mut auditor = NamespaceAuditor::new(
+            namespace_info,
+            Arc::new(RwLock::new(namespace_repo)),
+            Arc::new(RwLock::new(signing_key_repo)),
+            signature_storage.clone(),
+            Duration::from_millis(100),
+            Duration::from_secs(300),
+            shutdown_rx,
+        );
+
+        assert!(!auditor.maybe_emit_heartbeat().await.unwrap());
+        assert!(signature_storage.get_heartbeat().await.unwrap().is_none());
+    }
 }
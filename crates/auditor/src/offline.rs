@@ -0,0 +1,173 @@
+//! Operations on the configured storage that run without starting the auditor
+
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use akd_watch_common::{
+    NamespaceInfo,
+    bundle::{AuditBundle, ImportReport, export_bundle, import_bundle},
+    config::VerifyingConfig,
+    crypto::VerifyingKey,
+    evidence::{EvidenceRecord, RecheckOutcome},
+    integrity::{ScanReport, scan_namespace},
+    storage::{
+        namespaces::{InMemoryNamespaceRepository, NamespaceRepository, NamespaceStorage},
+        signatures::SignatureStorage,
+        signing_keys::{InMemoryVerifyingKeyRepository, VerifyingKeyStorage},
+    },
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::config::AuditorConfig;
+
+/// The configured storage, opened without running startup recovery or populating namespaces
+struct OfflineStorage {
+    config: AuditorConfig,
+    namespace_repository: NamespaceStorage,
+    signature_storage_map: HashMap<String, SignatureStorage>,
+    verifying_keys: VerifyingKeyStorage,
+}
+
+impl OfflineStorage {
+    async fn open() -> Result<Self> {
+        let config = AuditorConfig::load()
+            .map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))?;
+        let data_directory = config.data_directory();
+
+        let namespace_repository = config
+            .namespace_storage
+            .build_namespace_storage(&data_directory)?;
+        let signature_storage_map = config
+            .signature_storage
+            .build_signature_storage(&namespace_repository, &data_directory)
+            .await?;
+        // Read-only, so an offline command never creates or rewrites the auditor's key file
+        let verifying_keys = VerifyingConfig {}.build_verifying_key_storage(&data_directory)?;
+
+        Ok(Self {
+            config,
+            namespace_repository,
+            signature_storage_map,
+            verifying_keys,
+        })
+    }
+
+    fn signature_storage(&self, namespace: &str) -> Result<&SignatureStorage> {
+        self.signature_storage_map
+            .get(namespace)
+            .with_context(|| format!("Missing signature storage for namespace {namespace}"))
+    }
+
+    /// Builds signature storage for a namespace that may not be in the namespace repository yet
+    async fn build_signature_storage(&self, namespace: &NamespaceInfo) -> Result<SignatureStorage> {
+        let mut namespaces = InMemoryNamespaceRepository::new();
+        namespaces.add_namespace(namespace.clone()).await?;
+        let mut storage_map = self
+            .config
+            .signature_storage
+            .build_signature_storage(
+                &NamespaceStorage::InMemory(namespaces),
+                &self.config.data_directory(),
+            )
+            .await?;
+        storage_map
+            .remove(&namespace.name)
+            .with_context(|| format!("Missing signature storage for namespace {}", namespace.name))
+    }
+}
+
+/// Scans the stored signatures of every configured namespace, or only `namespace` if given
+#[instrument(skip_all, name = "scan_signatures")]
+pub async fn scan(namespace: Option<&str>) -> Result<Vec<ScanReport>> {
+    let storage = OfflineStorage::open().await?;
+
+    let mut reports = Vec::new();
+    for namespace_info in storage.namespace_repository.list_namespaces().await? {
+        if namespace.is_some_and(|name| name != namespace_info.name) {
+            continue;
+        }
+        let signatures = storage.signature_storage(&namespace_info.name)?;
+        reports.push(scan_namespace(&namespace_info, signatures, &storage.verifying_keys).await?);
+    }
+    if let Some(name) = namespace
+        && reports.is_empty()
+    {
+        anyhow::bail!("Unknown namespace {name}");
+    }
+    Ok(reports)
+}
+
+/// Exports a namespace's signatures within `range` as an audit bundle
+#[instrument(skip_all, name = "export_bundle")]
+pub async fn export(namespace: &str, range: RangeInclusive<u64>) -> Result<AuditBundle> {
+    let storage = OfflineStorage::open().await?;
+    let namespace_info = storage
+        .namespace_repository
+        .get_namespace_info(namespace)
+        .await?
+        .with_context(|| format!("Unknown namespace {namespace}"))?;
+    let signatures = storage.signature_storage(namespace)?;
+    Ok(export_bundle(&namespace_info, signatures, &storage.verifying_keys, range).await?)
+}
+
+/// Verifies an audit bundle and imports it into the configured storage. The auditor must not be
+/// running, since it is the only writer of the storage.
+#[instrument(skip_all, name = "import_bundle")]
+pub async fn import(bundle: &AuditBundle) -> Result<ImportReport> {
+    let mut storage = OfflineStorage::open().await?;
+    let mut signatures = storage.build_signature_storage(&bundle.namespace).await?;
+    Ok(import_bundle(
+        bundle,
+        &mut storage.namespace_repository,
+        &mut signatures,
+        &storage.verifying_keys,
+    )
+    .await?)
+}
+
+/// Verifies an audit bundle using only its own contents, without any configuration
+pub async fn verify_bundle(bundle: &AuditBundle) -> Result<ScanReport> {
+    Ok(bundle.verify().await?)
+}
+
+/// Outcome of [`verify_evidence`]
+#[derive(Clone, Debug, Serialize)]
+pub struct EvidenceReport {
+    pub namespace: String,
+    pub epoch: u64,
+    /// Key that signed the evidence
+    pub key_id: String,
+    /// Why the signature does not verify against the auditor's published keys, if it does not
+    pub signature_error: Option<String>,
+    pub recheck: RecheckOutcome,
+}
+
+impl EvidenceReport {
+    /// Whether the evidence is signed by one of the auditor's published keys and its verification
+    /// failure reproduces
+    pub fn is_confirmed(&self) -> bool {
+        self.signature_error.is_none() && matches!(self.recheck, RecheckOutcome::Reproduced { .. })
+    }
+}
+
+/// Checks the signature of an evidence record against the auditor's published keys and re-runs the
+/// failed verification it records, using only the record's own contents
+#[instrument(skip_all, name = "verify_evidence")]
+pub async fn verify_evidence(
+    record: &EvidenceRecord,
+    published_keys: Vec<VerifyingKey>,
+) -> Result<EvidenceReport> {
+    let published_keys = InMemoryVerifyingKeyRepository::new(published_keys);
+    Ok(EvidenceReport {
+        namespace: record.namespace().to_string(),
+        epoch: *record.epoch().value(),
+        key_id: record.signing_key_id().to_string(),
+        signature_error: record
+            .verify(&published_keys)
+            .await
+            .err()
+            .map(|e| e.to_string()),
+        recheck: record.recheck().await?,
+    })
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use akd_watch_common::storage::signatures::{MirroredSignatureStorage, ReconcileReport};
+use tokio::sync::broadcast::Receiver;
+use tracing::{error, info, instrument, warn};
+
+/// Periodically repairs the replicas of a namespace's mirrored signature storage
+pub struct ReplicaReconciler {
+    namespace_name: String,
+    signature_storage: MirroredSignatureStorage,
+    interval: Duration,
+    shutdown_rx: Receiver<()>,
+}
+
+impl ReplicaReconciler {
+    pub fn new(
+        namespace_name: String,
+        signature_storage: MirroredSignatureStorage,
+        interval: Duration,
+        shutdown_rx: Receiver<()>,
+    ) -> Self {
+        Self {
+            namespace_name,
+            signature_storage,
+            interval,
+            shutdown_rx,
+        }
+    }
+
+    #[instrument(level = "info", skip_all, fields(namespace = self.namespace_name))]
+    pub async fn run(mut self) {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = self.shutdown_rx.recv() => break,
+            }
+
+            match self.signature_storage.reconcile().await {
+                Ok(report) => self.log_report(&report),
+                Err(e) => warn!(
+                    namespace = self.namespace_name,
+                    error = %e,
+                    "Replica reconciliation failed"
+                ),
+            }
+        }
+        info!(
+            namespace = self.namespace_name,
+            "Replica reconciler stopped"
+        );
+    }
+
+    fn log_report(&self, report: &ReconcileReport) {
+        for disagreement in &report.disagreements {
+            error!(
+                namespace = self.namespace_name,
+                epoch = disagreement.epoch,
+                digests = ?disagreement.digests,
+                "CRITICAL: replicas store signatures over different digests for this epoch - signature storage may have been tampered with"
+            );
+        }
+        if report.unreadable_copies > 0 {
+            warn!(
+                namespace = self.namespace_name,
+                unreadable_copies = report.unreadable_copies,
+                "Some replica copies could not be read"
+            );
+        }
+        info!(
+            namespace = self.namespace_name,
+            checked_epochs = report.checked_epochs,
+            repaired_copies = report.repaired_copies,
+            repaired_heartbeat = report.repaired_heartbeat,
+            "Reconciled signature replicas"
+        );
+    }
+}
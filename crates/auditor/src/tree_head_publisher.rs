@@ -0,0 +1,94 @@
+use std::{sync::Arc, time::Duration};
+
+use akd_watch_common::{
+    storage::signing_keys::{SigningKeyRepository, SigningKeyStorage},
+    transparency_log::{SignedTreeHead, TransparencyLog},
+};
+use anyhow::{Context, Result};
+use tokio::sync::{Mutex, RwLock, broadcast::Receiver};
+use tracing::{debug, error, info, instrument, warn};
+
+/// Periodically signs and publishes the head of the auditor's transparency log
+pub struct TreeHeadPublisher {
+    transparency_log: Arc<Mutex<TransparencyLog>>,
+    signing_key_repository: Arc<RwLock<SigningKeyStorage>>,
+    interval: Duration,
+    shutdown_rx: Receiver<()>,
+}
+
+impl TreeHeadPublisher {
+    pub fn new(
+        transparency_log: Arc<Mutex<TransparencyLog>>,
+        signing_key_repository: Arc<RwLock<SigningKeyStorage>>,
+        interval: Duration,
+        shutdown_rx: Receiver<()>,
+    ) -> Self {
+        Self {
+            transparency_log,
+            signing_key_repository,
+            interval,
+            shutdown_rx,
+        }
+    }
+
+    #[instrument(level = "info", skip_all)]
+    pub async fn run(mut self) {
+        loop {
+            if let Err(e) = self.publish_if_grown().await {
+                warn!(error = %e, "Failed to publish transparency log tree head");
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = self.shutdown_rx.recv() => break,
+            }
+        }
+        // Cover everything logged since the last interval
+        if let Err(e) = self.publish_if_grown().await {
+            warn!(error = %e, "Failed to publish transparency log tree head");
+        }
+        info!("Tree head publisher stopped");
+    }
+
+    /// Signs a tree head if the log has grown since the last published one.
+    /// Returns true if a tree head was published.
+    async fn publish_if_grown(&self) -> Result<bool> {
+        let transparency_log = self.transparency_log.lock().await;
+        let tree_size = transparency_log.size();
+        let published_size = transparency_log
+            .latest_tree_head()?
+            .map(|tree_head| tree_head.tree_size());
+        match published_size {
+            Some(published_size) if published_size == tree_size => {
+                debug!(
+                    tree_size,
+                    "Transparency log unchanged, not signing a tree head"
+                );
+                return Ok(false);
+            }
+            Some(published_size) if published_size > tree_size => {
+                error!(
+                    tree_size,
+                    published_size,
+                    "CRITICAL: transparency log is smaller than its last signed tree head - refusing to sign an inconsistent tree head"
+                );
+                return Ok(false);
+            }
+            _ => {}
+        }
+
+        let signing_key = self
+            .signing_key_repository
+            .read()
+            .await
+            .get_current_signing_key()
+            .await?;
+        let root_hash = transparency_log
+            .root(tree_size)
+            .context("Transparency log root missing for its own size")?;
+        let tree_head = SignedTreeHead::sign(tree_size, root_hash, &signing_key)?;
+        transparency_log.publish_tree_head(&tree_head)?;
+        info!(tree_size, "Published transparency log tree head");
+        Ok(true)
+    }
+}
@@ -91,6 +91,7 @@ mod tests {
             starting_epoch: 1000000.into(),
             status: crate::NamespaceStatus::Online,
             last_verified_epoch: Some(1000000.into()),
+            version: 0,
         };
 
         let storage = AkdStorageFactory::create_storage(&namespace);
@@ -107,6 +108,7 @@ mod tests {
             starting_epoch: 1000000.into(),
             status: crate::NamespaceStatus::Online,
             last_verified_epoch: Some(1000000.into()),
+            version: 0,
         };
 
         let storage = AkdStorageFactory::create_storage(&namespace);
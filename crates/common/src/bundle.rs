@@ -0,0 +1,397 @@
+//! Self-contained bundles of a namespace's signed audit history.
+//!
+//! A bundle holds the namespace state, the signatures of a range of epochs and the verifying keys
+//! needed to check them, so it can be verified without access to the auditor that produced it.
+
+use std::{collections::BTreeSet, ops::RangeInclusive};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    Epoch, EpochSignature, NamespaceInfo,
+    crypto::VerifyingKey,
+    integrity::{ScanError, ScanReport, scan_namespace},
+    storage::{
+        namespaces::{NamespaceRepository, NamespaceRepositoryError},
+        signatures::{InMemorySignatureStorage, SignatureRepository, SignatureRepositoryError},
+        signing_keys::{
+            InMemoryVerifyingKeyRepository, VerifyingKeyRepository, VerifyingKeyRepositoryError,
+        },
+    },
+};
+
+/// Current version of the bundle format
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Number of signatures read per storage query while exporting
+const EXPORT_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("Unsupported bundle format version {0}")]
+    UnsupportedFormatVersion(u32),
+    #[error("Bundle JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Signature for epoch {0} is outside the bundle's epoch range")]
+    SignatureOutOfRange(u64),
+    #[error("Bundle contains more than one signature for epoch {0}")]
+    DuplicateEpoch(u64),
+    #[error("Verifying key {0} is not available")]
+    MissingVerifyingKey(Uuid),
+    #[error("Bundle is signed with keys the target does not publish: {0:?}")]
+    UntrustedVerifyingKeys(Vec<Uuid>),
+    #[error("Bundle failed verification")]
+    VerificationFailed(Box<ScanReport>),
+    #[error("{0}")]
+    ScanError(#[from] ScanError),
+    #[error("Signature storage error: {0}")]
+    SignatureStorageError(#[from] SignatureRepositoryError),
+    #[error("Namespace repository error: {0}")]
+    NamespaceRepositoryError(#[from] NamespaceRepositoryError),
+    #[error("Verifying key repository error: {0}")]
+    VerifyingKeyRepositoryError(#[from] VerifyingKeyRepositoryError),
+}
+
+/// Signed audit history of one namespace over an inclusive range of epochs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditBundle {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub namespace: NamespaceInfo,
+    pub first_epoch: u64,
+    pub last_epoch: u64,
+    /// Keys that made the signatures
+    pub verifying_keys: Vec<VerifyingKey>,
+    /// Signatures in ascending epoch order
+    pub signatures: Vec<EpochSignature>,
+}
+
+/// Outcome of [`import_bundle`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub imported_signatures: u64,
+    /// Whether the namespace was added to the namespace repository. An existing namespace is left untouched.
+    pub namespace_added: bool,
+}
+
+impl AuditBundle {
+    pub fn from_json(bytes: &[u8]) -> Result<Self, BundleError> {
+        let bundle: Self = serde_json::from_slice(bytes)?;
+        if bundle.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedFormatVersion(bundle.format_version));
+        }
+        Ok(bundle)
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, BundleError> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Verifies the bundle using only its own contents.
+    ///
+    /// Fails if the bundle is malformed. Otherwise returns an integrity report over the bundle's
+    /// range, as produced by [`scan_namespace`], which is clean if every signature verifies against
+    /// the bundled keys and no verified epoch in the range is missing.
+    #[instrument(skip_all, fields(namespace = self.namespace.name))]
+    pub async fn verify(&self) -> Result<ScanReport, BundleError> {
+        if self.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedFormatVersion(self.format_version));
+        }
+
+        let mut signatures = InMemorySignatureStorage::new();
+        let mut epochs = BTreeSet::new();
+        for signature in &self.signatures {
+            let epoch = *signature.epoch().value();
+            if !(self.first_epoch..=self.last_epoch).contains(&epoch) {
+                return Err(BundleError::SignatureOutOfRange(epoch));
+            }
+            if !epochs.insert(epoch) {
+                return Err(BundleError::DuplicateEpoch(epoch));
+            }
+            signatures.set_signature(&epoch, signature.clone()).await?;
+        }
+        let verifying_keys = InMemoryVerifyingKeyRepository::new(self.verifying_keys.clone());
+
+        // Only expect continuity over the part of the namespace's history the bundle covers
+        let mut namespace = self.namespace.clone();
+        namespace.starting_epoch =
+            Epoch::new(self.first_epoch.max(*namespace.starting_epoch.value()));
+        namespace.last_verified_epoch = namespace
+            .last_verified_epoch
+            .map(|epoch| Epoch::new(self.last_epoch.min(*epoch.value())))
+            .filter(|epoch| epoch.value() >= namespace.starting_epoch.value());
+
+        Ok(scan_namespace(&namespace, &signatures, &verifying_keys).await?)
+    }
+}
+
+/// Exports the signatures of a namespace within `range`, together with the keys that made them
+#[instrument(skip_all, fields(namespace = namespace.name))]
+pub async fn export_bundle(
+    namespace: &NamespaceInfo,
+    signatures: &impl SignatureRepository,
+    verifying_keys: &impl VerifyingKeyRepository,
+    range: RangeInclusive<u64>,
+) -> Result<AuditBundle, BundleError> {
+    let mut bundle = AuditBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: Utc::now(),
+        namespace: namespace.clone(),
+        first_epoch: *range.start(),
+        last_epoch: *range.end(),
+        verifying_keys: Vec::new(),
+        signatures: Vec::new(),
+    };
+
+    let mut cursor = *range.start();
+    loop {
+        let batch = signatures
+            .get_signatures(cursor..=*range.end(), EXPORT_BATCH_SIZE)
+            .await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        let next = last.epoch().value().checked_add(1);
+        bundle.signatures.extend(batch);
+        match next {
+            Some(epoch) if epoch <= *range.end() => cursor = epoch,
+            _ => break,
+        }
+    }
+
+    let key_ids: BTreeSet<Uuid> = bundle
+        .signatures
+        .iter()
+        .map(EpochSignature::signing_key_id)
+        .collect();
+    for key_id in key_ids {
+        let key = verifying_keys
+            .get_verifying_key(key_id)
+            .await?
+            .ok_or(BundleError::MissingVerifyingKey(key_id))?;
+        bundle.verifying_keys.push(key);
+    }
+
+    info!(
+        signatures = bundle.signatures.len(),
+        keys = bundle.verifying_keys.len(),
+        "Exported audit bundle"
+    );
+    Ok(bundle)
+}
+
+/// Verifies a bundle and stores its signatures, adding the namespace if it does not exist yet.
+///
+/// Nothing is imported unless the bundle verifies cleanly and every bundled key is one the target's
+/// verifying key repository already holds, since the target serves the signatures under its own
+/// published keys. Signatures are write-once, so importing
+/// a bundle twice is a no-op, while a bundle that disagrees with stored signatures fails with
+/// [`SignatureRepositoryError::SignatureConflict`].
+#[instrument(skip_all, fields(namespace = bundle.namespace.name))]
+pub async fn import_bundle(
+    bundle: &AuditBundle,
+    namespaces: &mut impl NamespaceRepository,
+    signatures: &mut impl SignatureRepository,
+    verifying_keys: &impl VerifyingKeyRepository,
+) -> Result<ImportReport, BundleError> {
+    let verification = bundle.verify().await?;
+    if !verification.is_clean() {
+        return Err(BundleError::VerificationFailed(Box::new(verification)));
+    }
+
+    let mut untrusted_key_ids = Vec::new();
+    for key in &bundle.verifying_keys {
+        let known = verifying_keys.get_verifying_key(key.key_id).await?;
+        if known.is_none_or(|known| known.verifying_key != key.verifying_key) {
+            warn!(key_id = %key.key_id, "Bundle key is not in the verifying key repository");
+            untrusted_key_ids.push(key.key_id);
+        }
+    }
+    if !untrusted_key_ids.is_empty() {
+        return Err(BundleError::UntrustedVerifyingKeys(untrusted_key_ids));
+    }
+
+    let mut report = ImportReport::default();
+
+    for signature in &bundle.signatures {
+        signatures
+            .set_signature(signature.epoch().value(), signature.clone())
+            .await?;
+        report.imported_signatures += 1;
+    }
+
+    match namespaces.add_namespace(bundle.namespace.clone()).await {
+        Ok(()) => report.namespace_added = true,
+        Err(NamespaceRepositoryError::NamespaceAlreadyExists(_)) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    info!(?report, "Imported audit bundle");
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::{
+            namespaces::InMemoryNamespaceRepository,
+            signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+        },
+        testing::test_namespace,
+    };
+
+    fn namespace(last_verified_epoch: u64) -> NamespaceInfo {
+        test_namespace("test").update_last_verified_epoch(Epoch::new(last_verified_epoch))
+    }
+
+    async fn signed_history(
+        info: &NamespaceInfo,
+        epochs: RangeInclusive<u64>,
+    ) -> (InMemorySignatureStorage, InMemorySigningKeyRepository) {
+        let keys = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = keys.get_current_signing_key().await.unwrap();
+        let mut storage = InMemorySignatureStorage::new();
+        for epoch in epochs {
+            let signature =
+                EpochSignature::sign(info.clone(), Epoch::new(epoch), [epoch as u8; 32], &key)
+                    .unwrap();
+            storage.set_signature(&epoch, signature).await.unwrap();
+        }
+        (storage, keys)
+    }
+
+    #[tokio::test]
+    async fn test_export_verify_import_roundtrip() {
+        let info = namespace(10);
+        let (storage, keys) = signed_history(&info, 1..=10).await;
+        let verifying_keys = keys.verifying_key_repository().unwrap();
+
+        let bundle = export_bundle(&info, &storage, &verifying_keys, 3..=7)
+            .await
+            .unwrap();
+        assert_eq!(bundle.signatures.len(), 5);
+        assert_eq!(bundle.verifying_keys.len(), 1);
+
+        let bundle = AuditBundle::from_json(&bundle.to_json().unwrap()).unwrap();
+        let report = bundle.verify().await.unwrap();
+        assert!(report.is_clean(), "{report:?}");
+
+        let mut namespaces = InMemoryNamespaceRepository::new();
+        let mut target = InMemorySignatureStorage::new();
+        let report = import_bundle(&bundle, &mut namespaces, &mut target, &verifying_keys)
+            .await
+            .unwrap();
+        assert_eq!(report.imported_signatures, 5);
+        assert!(report.namespace_added);
+        assert_eq!(
+            target.list_epochs(0..=u64::MAX, 10).await.unwrap(),
+            vec![3, 4, 5, 6, 7]
+        );
+
+        // Importing again is idempotent
+        let report = import_bundle(&bundle, &mut namespaces, &mut target, &verifying_keys)
+            .await
+            .unwrap();
+        assert!(!report.namespace_added);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_bundle_is_rejected() {
+        let info = namespace(5);
+        let (storage, keys) = signed_history(&info, 1..=5).await;
+        let verifying_keys = keys.verifying_key_repository().unwrap();
+        let bundle = export_bundle(&info, &storage, &verifying_keys, 0..=u64::MAX)
+            .await
+            .unwrap();
+
+        // A signature over a different digest no longer verifies
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&bundle.to_json().unwrap()).unwrap();
+        json["signatures"][2]["digest"][0] = serde_json::json!(0xff);
+        let tampered = AuditBundle::from_json(&serde_json::to_vec(&json).unwrap()).unwrap();
+        let report = tampered.verify().await.unwrap();
+        assert_eq!(report.bad_signatures.len(), 1);
+        assert_eq!(report.bad_signatures[0].epoch, 3);
+
+        let mut target = InMemorySignatureStorage::new();
+        let result = import_bundle(
+            &tampered,
+            &mut InMemoryNamespaceRepository::new(),
+            &mut target,
+            &verifying_keys,
+        )
+        .await;
+        assert!(matches!(result, Err(BundleError::VerificationFailed(_))));
+        assert_eq!(target.count_signatures().await.unwrap(), 0);
+
+        // A dropped signature shows up as a gap
+        let mut incomplete = bundle.clone();
+        incomplete.signatures.remove(1);
+        let report = incomplete.verify().await.unwrap();
+        assert_eq!(report.gaps.len(), 1);
+
+        let mut unsupported = bundle.clone();
+        unsupported.format_version = 99;
+        assert!(matches!(
+            AuditBundle::from_json(&unsupported.to_json().unwrap()),
+            Err(BundleError::UnsupportedFormatVersion(99))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bundle_signed_by_foreign_key_is_rejected() {
+        let info = namespace(5);
+        let (storage, foreign_keys) = signed_history(&info, 1..=5).await;
+        let bundle = export_bundle(
+            &info,
+            &storage,
+            &foreign_keys.verifying_key_repository().unwrap(),
+            0..=u64::MAX,
+        )
+        .await
+        .unwrap();
+        // The bundle is self-consistent, but the target never published its key
+        assert!(bundle.verify().await.unwrap().is_clean());
+
+        let target_keys = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        target_keys.get_current_signing_key().await.unwrap();
+        let mut namespaces = InMemoryNamespaceRepository::new();
+        let mut target = InMemorySignatureStorage::new();
+        let result = import_bundle(
+            &bundle,
+            &mut namespaces,
+            &mut target,
+            &target_keys.verifying_key_repository().unwrap(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(BundleError::UntrustedVerifyingKeys(ref key_ids))
+                if *key_ids == vec![bundle.verifying_keys[0].key_id]
+        ));
+
+        // A published key with the same ID but different key material is not trusted either
+        let impostor_keys = InMemoryVerifyingKeyRepository::new(vec![VerifyingKey {
+            key_id: bundle.verifying_keys[0].key_id,
+            ..target_keys
+                .verifying_key_repository()
+                .unwrap()
+                .list_keys()
+                .await
+                .unwrap()[0]
+                .clone()
+        }]);
+        let result = import_bundle(&bundle, &mut namespaces, &mut target, &impostor_keys).await;
+        assert!(matches!(
+            result,
+            Err(BundleError::UntrustedVerifyingKeys(_))
+        ));
+
+        assert_eq!(target.count_signatures().await.unwrap(), 0);
+        assert!(namespaces.list_namespaces().await.unwrap().is_empty());
+    }
+}
@@ -1,7 +1,9 @@
 mod namespace_storage_config;
+mod observability_config;
 mod signature_storage_config;
 mod signing_config;
 
 pub use namespace_storage_config::NamespaceStorageConfig;
+pub use observability_config::{LogFormat, ObservabilityConfig};
 pub use signature_storage_config::SignatureStorageConfig;
 pub use signing_config::{SigningConfig, VerifyingConfig};
@@ -1,8 +1,12 @@
 use config::ConfigError;
 use serde::{Deserialize, Serialize};
 
-use crate::storage::namespaces::{
-    FileNamespaceRepository, InMemoryNamespaceRepository, NamespaceStorage,
+use crate::storage::{
+    RecoveryReport, SqliteDatabase,
+    namespaces::{
+        FileNamespaceRepository, InMemoryNamespaceRepository, NamespaceStorage,
+        SqliteNamespaceRepository,
+    },
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,6 +16,10 @@ pub enum NamespaceStorageConfig {
     InMemory,
     #[serde(rename = "File")]
     File,
+    /// Stores namespace state in the SQLite database in the data directory.
+    /// Use together with `SignatureStorageConfig::Sqlite` to commit signatures and namespace updates atomically.
+    #[serde(rename = "Sqlite")]
+    Sqlite,
 }
 
 impl NamespaceStorageConfig {
@@ -19,7 +27,7 @@ impl NamespaceStorageConfig {
     pub fn validate(&self, data_directory: &str) -> Result<(), ConfigError> {
         match self {
             NamespaceStorageConfig::InMemory => Ok(()),
-            NamespaceStorageConfig::File => {
+            NamespaceStorageConfig::File | NamespaceStorageConfig::Sqlite => {
                 if data_directory.is_empty() {
                     return Err(ConfigError::Message(
                         "Data directory cannot be empty".to_string(),
@@ -38,15 +46,38 @@ impl NamespaceStorageConfig {
         }
     }
 
-    /// Creates a namespace storage instance based on the given configuration.
-    pub fn build_namespace_storage(&self, data_directory: &str) -> NamespaceStorage {
+    /// Runs the startup recovery step of file-based storage. Should only be called by the process
+    /// that writes namespace state, before building the storage.
+    pub fn recover(&self, data_directory: &str) -> Result<RecoveryReport, ConfigError> {
         match self {
+            NamespaceStorageConfig::File => FileNamespaceRepository::recover(data_directory)
+                .map_err(|e| ConfigError::Message(e.to_string())),
+            NamespaceStorageConfig::InMemory | NamespaceStorageConfig::Sqlite => {
+                Ok(RecoveryReport::default())
+            }
+        }
+    }
+
+    /// Creates a namespace storage instance based on the given configuration.
+    pub fn build_namespace_storage(
+        &self,
+        data_directory: &str,
+    ) -> Result<NamespaceStorage, ConfigError> {
+        let storage = match self {
             NamespaceStorageConfig::File => {
-                NamespaceStorage::File(FileNamespaceRepository::new(data_directory))
+                NamespaceStorage::File(FileNamespaceRepository::new(data_directory).map_err(
+                    |e| ConfigError::Message(format!("Failed to load namespace storage: {e}")),
+                )?)
             }
             NamespaceStorageConfig::InMemory => {
                 NamespaceStorage::InMemory(InMemoryNamespaceRepository::new())
             }
-        }
+            NamespaceStorageConfig::Sqlite => NamespaceStorage::Sqlite(
+                SqliteNamespaceRepository::new(SqliteDatabase::open(data_directory).map_err(
+                    |e| ConfigError::Message(format!("Failed to open SQLite database: {e}")),
+                )?),
+            ),
+        };
+        Ok(storage)
     }
 }
@@ -0,0 +1,144 @@
+use config::{Config, ConfigError, Environment, File};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::{EnvFilter, filter::LevelFilter};
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Configuration for logging and trace export, shared by every binary
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ObservabilityConfig {
+    /// Maximum level of logged events and exported spans
+    /// Defaults to info
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+
+    /// Filter directives such as `akd_watch_auditor=debug,hyper=warn`, overriding `log_level`
+    pub log_filter: Option<String>,
+
+    /// Format of the logs written to the console
+    /// Defaults to text
+    #[serde(default)]
+    pub log_format: LogFormat,
+
+    /// OTLP gRPC endpoint to export spans to, such as `http://localhost:4317`.
+    /// Spans are not exported if unset.
+    pub otlp_endpoint: Option<String>,
+
+    /// Service name reported with exported spans
+    /// Defaults to the binary's name
+    pub service_name: Option<String>,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            log_level: default_log_level(),
+            log_filter: None,
+            log_format: LogFormat::default(),
+            otlp_endpoint: None,
+            service_name: None,
+        }
+    }
+}
+
+impl ObservabilityConfig {
+    /// Load the `observability` section from the same sources as the auditor and web
+    /// configuration, so it can be applied before they are loaded. Every field has a default.
+    ///
+    /// For field `log_level`, use `AKD_WATCH__OBSERVABILITY__LOG_LEVEL`
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut builder = Config::builder();
+
+        if let Ok(config_path) = std::env::var("AKD_WATCH_CONFIG_PATH") {
+            builder = builder.add_source(File::with_name(&config_path).required(true));
+        } else {
+            builder = builder.add_source(File::with_name("config").required(false));
+        }
+
+        let config = builder
+            .add_source(Environment::with_prefix("AKD_WATCH").separator("__"))
+            .build()?;
+        let observability_config = match config.get::<ObservabilityConfig>("observability") {
+            Ok(observability_config) => observability_config,
+            Err(ConfigError::NotFound(_)) => ObservabilityConfig::default(),
+            Err(e) => return Err(e),
+        };
+
+        observability_config.validate()?;
+
+        Ok(observability_config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.env_filter()?;
+        if let Some(endpoint) = &self.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                return Err(ConfigError::Message(format!(
+                    "Observability otlp_endpoint must be an http or https URL: {endpoint}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The filter selecting which events are logged and which spans are exported
+    pub fn env_filter(&self) -> Result<EnvFilter, ConfigError> {
+        match &self.log_filter {
+            Some(directives) => EnvFilter::builder().parse(directives).map_err(|e| {
+                ConfigError::Message(format!("Invalid observability log_filter: {e}"))
+            }),
+            None => {
+                let level = self.log_level.parse::<LevelFilter>().map_err(|e| {
+                    ConfigError::Message(format!("Invalid observability log_level: {e}"))
+                })?;
+                Ok(EnvFilter::default().add_directive(level.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rejects_invalid_settings() {
+        assert!(ObservabilityConfig::default().validate().is_ok());
+
+        let config = ObservabilityConfig {
+            log_level: "loud".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = ObservabilityConfig {
+            log_filter: Some("akd_watch_auditor=debug,hyper=warn".to_string()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = ObservabilityConfig {
+            log_filter: Some("akd_watch_auditor=[".to_string()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = ObservabilityConfig {
+            otlp_endpoint: Some("localhost:4317".to_string()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,11 +1,21 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use config::ConfigError;
 use serde::{Deserialize, Serialize};
 
-use crate::storage::{
-    namespaces::{NamespaceRepository, NamespaceStorage},
-    signatures::{FilesystemSignatureStorage, InMemorySignatureStorage, SignatureStorage},
+use crate::{
+    NamespaceInfo,
+    storage::{
+        RecoveryReport, SqliteDatabase,
+        namespaces::{NamespaceRepository, NamespaceStorage},
+        signatures::{
+            AWS_ACCESS_KEY_ID_ENV, AWS_SECRET_ACCESS_KEY_ENV, AWS_SESSION_TOKEN_ENV,
+            AZURE_STORAGE_CONNECTION_STRING_ENV, AzureBlobClient, AzureConnectionString,
+            AzureSignatureStorage, FilesystemSignatureStorage, InMemorySignatureStorage,
+            MirroredSignatureStorage, S3Client, S3Credentials, S3SignatureStorage, Secret,
+            SignatureStorage, SqliteSignatureStorage,
+        },
+    },
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,15 +27,61 @@ pub enum SignatureStorageConfig {
     #[serde(rename = "File")]
     File,
 
+    /// Stores signatures in the SQLite database in the data directory.
+    /// Use together with `NamespaceStorageConfig::Sqlite` to commit signatures and namespace updates atomically.
+    #[serde(rename = "Sqlite")]
+    Sqlite,
+
+    /// Stores signatures as blobs named `<namespace>/<epoch>` in an Azure Blob Storage container.
+    /// The container is created on startup if it does not exist.
     #[serde(rename = "Azure")]
     Azure {
-        /// Azure storage account name
+        /// Azure storage account name, used if the connection string does not specify one
         account_name: String,
         /// Azure container name
         container_name: String,
-        /// Azure connection string (required)
+        /// Azure connection string. Falls back to the `AZURE_STORAGE_CONNECTION_STRING` environment variable.
         connection_string: Option<String>,
     },
+
+    /// Stores signatures as objects with key `<prefix>/<namespace>/<epoch>` in an S3 bucket.
+    /// Works with S3-compatible stores such as MinIO by setting `endpoint`.
+    #[serde(rename = "S3")]
+    S3 {
+        /// Bucket name. The bucket must already exist.
+        bucket: String,
+        /// Bucket region
+        #[serde(default = "default_s3_region")]
+        region: String,
+        /// Custom endpoint URL for S3-compatible stores. Enables path-style addressing.
+        endpoint: Option<String>,
+        /// Key prefix prepended to all objects
+        #[serde(default)]
+        prefix: String,
+        /// Falls back to the `AWS_ACCESS_KEY_ID` environment variable.
+        access_key_id: Option<String>,
+        /// Falls back to the `AWS_SECRET_ACCESS_KEY` environment variable.
+        secret_access_key: Option<String>,
+        /// Falls back to the `AWS_SESSION_TOKEN` environment variable.
+        session_token: Option<String>,
+    },
+
+    /// Writes every signature to several of the other storage types, e.g. `File` plus `Azure`, so
+    /// that losing one of them does not lose signatures.
+    #[serde(rename = "Mirrored")]
+    Mirrored {
+        /// Storage backends, in the order they are read from. Cannot themselves be `Mirrored`.
+        replicas: Vec<SignatureStorageConfig>,
+        /// Number of replicas that must store a signature for the write to succeed. Defaults to all replicas.
+        write_quorum: Option<usize>,
+        /// Seconds between passes that repair missing copies and report disagreeing digests.
+        /// Reconciliation is disabled if unset.
+        reconcile_interval_seconds: Option<u64>,
+    },
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
 }
 
 impl SignatureStorageConfig {
@@ -33,7 +89,7 @@ impl SignatureStorageConfig {
     pub fn validate(&self, data_directory: &str) -> Result<(), ConfigError> {
         match self {
             SignatureStorageConfig::InMemory => Ok(()),
-            SignatureStorageConfig::File => {
+            SignatureStorageConfig::File | SignatureStorageConfig::Sqlite => {
                 if data_directory.is_empty() {
                     return Err(ConfigError::Message(
                         "Data directory cannot be empty".to_string(),
@@ -49,35 +105,243 @@ impl SignatureStorageConfig {
 
                 Ok(())
             }
-            SignatureStorageConfig::Azure {
-                connection_string, ..
+            SignatureStorageConfig::Azure { container_name, .. } => {
+                if !is_valid_container_name(container_name) {
+                    return Err(ConfigError::Message(format!(
+                        "Invalid Azure container name '{container_name}': must be 3-63 lowercase letters, digits or single hyphens"
+                    )));
+                }
+                self.azure_connection_string().map(|_| ())
+            }
+            SignatureStorageConfig::S3 { bucket, .. } => {
+                if bucket.is_empty() {
+                    return Err(ConfigError::Message(
+                        "S3 storage requires a bucket".to_string(),
+                    ));
+                }
+                self.s3_client().map(|_| ())
+            }
+            SignatureStorageConfig::Mirrored {
+                replicas,
+                reconcile_interval_seconds,
+                ..
             } => {
-                if connection_string.is_none() {
-                    Err(ConfigError::Message(
-                        "Azure storage requires connection_string in config".to_string(),
-                    ))
-                } else {
-                    Ok(())
+                if replicas.is_empty() {
+                    return Err(ConfigError::Message(
+                        "Mirrored storage requires at least one replica".to_string(),
+                    ));
+                }
+                let write_quorum = self.write_quorum();
+                if write_quorum == 0 || write_quorum > replicas.len() {
+                    return Err(ConfigError::Message(format!(
+                        "Mirrored storage write_quorum must be between 1 and the number of replicas ({}), got {write_quorum}",
+                        replicas.len()
+                    )));
+                }
+                if *reconcile_interval_seconds == Some(0) {
+                    return Err(ConfigError::Message(
+                        "Mirrored storage reconcile_interval_seconds must be positive".to_string(),
+                    ));
+                }
+                for replica in replicas {
+                    if matches!(replica, SignatureStorageConfig::Mirrored { .. }) {
+                        return Err(ConfigError::Message(
+                            "Mirrored storage replicas cannot be Mirrored".to_string(),
+                        ));
+                    }
+                    replica.validate(data_directory)?;
                 }
+                Ok(())
             }
         }
     }
 
+    /// Number of replicas a mirrored write must reach, defaulting to all of them
+    fn write_quorum(&self) -> usize {
+        match self {
+            SignatureStorageConfig::Mirrored {
+                replicas,
+                write_quorum,
+                ..
+            } => write_quorum.unwrap_or(replicas.len()),
+            _ => 1,
+        }
+    }
+
+    /// Builds the S3 client, resolving credentials from config with a fallback to the environment
+    fn s3_client(&self) -> Result<S3Client, ConfigError> {
+        let SignatureStorageConfig::S3 {
+            bucket,
+            region,
+            endpoint,
+            access_key_id,
+            secret_access_key,
+            session_token,
+            ..
+        } = self
+        else {
+            return Err(ConfigError::Message(
+                "Not an S3 storage configuration".to_string(),
+            ));
+        };
+
+        let from_config_or_env =
+            |value: &Option<String>, env: &str| value.clone().or_else(|| std::env::var(env).ok());
+        let (Some(access_key_id), Some(secret_access_key)) = (
+            from_config_or_env(access_key_id, AWS_ACCESS_KEY_ID_ENV),
+            from_config_or_env(secret_access_key, AWS_SECRET_ACCESS_KEY_ENV),
+        ) else {
+            return Err(ConfigError::Message(format!(
+                "S3 storage requires access_key_id and secret_access_key in config or the {AWS_ACCESS_KEY_ID_ENV} and {AWS_SECRET_ACCESS_KEY_ENV} environment variables"
+            )));
+        };
+        let credentials = S3Credentials {
+            access_key_id,
+            secret_access_key: Secret(secret_access_key),
+            session_token: from_config_or_env(session_token, AWS_SESSION_TOKEN_ENV).map(Secret),
+        };
+
+        S3Client::new(bucket, region, endpoint.as_deref(), credentials)
+            .map_err(|e| ConfigError::Message(e.to_string()))
+    }
+
+    /// Resolves the Azure connection string from config, falling back to the environment
+    fn azure_connection_string(&self) -> Result<AzureConnectionString, ConfigError> {
+        let SignatureStorageConfig::Azure {
+            account_name,
+            connection_string,
+            ..
+        } = self
+        else {
+            return Err(ConfigError::Message(
+                "Not an Azure storage configuration".to_string(),
+            ));
+        };
+
+        let connection_string = connection_string
+            .clone()
+            .or_else(|| std::env::var(AZURE_STORAGE_CONNECTION_STRING_ENV).ok())
+            .ok_or_else(|| {
+                ConfigError::Message(format!(
+                    "Azure storage requires connection_string in config or the {AZURE_STORAGE_CONNECTION_STRING_ENV} environment variable"
+                ))
+            })?;
+        AzureConnectionString::parse(&connection_string, account_name)
+            .map_err(|e| ConfigError::Message(e.to_string()))
+    }
+
     pub fn signatures_directory(data_directory: &str) -> String {
         format!("{data_directory}/signatures")
     }
 
+    /// Runs the startup recovery step of file-based storage for every namespace directory. Should
+    /// only be called by the process that writes signatures, before building the storage.
+    pub fn recover(&self, data_directory: &str) -> Result<RecoveryReport, ConfigError> {
+        let mut report = RecoveryReport::default();
+        if let SignatureStorageConfig::Mirrored { replicas, .. } = self {
+            for replica in replicas {
+                report.merge(replica.recover(data_directory)?);
+            }
+            return Ok(report);
+        }
+        if !matches!(self, SignatureStorageConfig::File) {
+            return Ok(report);
+        }
+
+        let signatures_directory = Self::signatures_directory(data_directory);
+        if !std::path::Path::new(&signatures_directory).is_dir() {
+            return Ok(report);
+        }
+        let entries = std::fs::read_dir(&signatures_directory).map_err(|e| {
+            ConfigError::Message(format!("Failed to read signatures directory: {e}"))
+        })?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| {
+                    ConfigError::Message(format!("Failed to read signatures directory: {e}"))
+                })?
+                .path();
+            if path.is_dir() {
+                report.merge(
+                    FilesystemSignatureStorage::recover(&path.to_string_lossy())
+                        .map_err(|e| ConfigError::Message(e.to_string()))?,
+                );
+            }
+        }
+        Ok(report)
+    }
+
     pub async fn build_signature_storage(
         &self,
         namespace_storage: &NamespaceStorage,
         data_directory: &str,
     ) -> Result<HashMap<String, SignatureStorage>, ConfigError> {
-        let mut storage_map = HashMap::new();
-
         let namespaces = namespace_storage
             .list_namespaces()
             .await
             .map_err(|e| ConfigError::Message(format!("Failed to list namespaces: {e}")))?;
+        self.build_for_namespaces(&namespaces, data_directory).await
+    }
+
+    /// Builds the signature storage of a single namespace, e.g. one added after startup
+    pub async fn build_namespace_signature_storage(
+        &self,
+        namespace: &NamespaceInfo,
+        data_directory: &str,
+    ) -> Result<SignatureStorage, ConfigError> {
+        self.build_for_namespaces(std::slice::from_ref(namespace), data_directory)
+            .await?
+            .remove(&namespace.name)
+            .ok_or_else(|| {
+                ConfigError::Message(format!(
+                    "No signature storage built for namespace {}",
+                    namespace.name
+                ))
+            })
+    }
+
+    async fn build_for_namespaces(
+        &self,
+        namespaces: &[NamespaceInfo],
+        data_directory: &str,
+    ) -> Result<HashMap<String, SignatureStorage>, ConfigError> {
+        let SignatureStorageConfig::Mirrored {
+            replicas,
+            reconcile_interval_seconds,
+            ..
+        } = self
+        else {
+            return self.build_backend(namespaces, data_directory).await;
+        };
+
+        let mut replica_maps = Vec::with_capacity(replicas.len());
+        for replica in replicas {
+            replica_maps.push(replica.build_backend(namespaces, data_directory).await?);
+        }
+        let mut storage_map = HashMap::new();
+        for ns_config in namespaces {
+            let namespace_replicas = replica_maps
+                .iter_mut()
+                .filter_map(|replica_map| replica_map.remove(&ns_config.name))
+                .collect();
+            let storage = MirroredSignatureStorage::new(
+                namespace_replicas,
+                self.write_quorum(),
+                reconcile_interval_seconds.map(Duration::from_secs),
+            )
+            .map_err(|e| ConfigError::Message(e.to_string()))?;
+            storage_map.insert(ns_config.name.clone(), SignatureStorage::Mirrored(storage));
+        }
+        Ok(storage_map)
+    }
+
+    /// Builds the storage of a single, non-mirrored backend for every namespace
+    async fn build_backend(
+        &self,
+        namespaces: &[NamespaceInfo],
+        data_directory: &str,
+    ) -> Result<HashMap<String, SignatureStorage>, ConfigError> {
+        let mut storage_map = HashMap::new();
 
         match self {
             SignatureStorageConfig::File => {
@@ -87,11 +351,14 @@ impl SignatureStorageConfig {
                         Self::signatures_directory(data_directory),
                         ns_config.name.clone()
                     );
+                    let storage = FilesystemSignatureStorage::new(&ns_directory).map_err(|e| {
+                        ConfigError::Message(format!(
+                            "Failed to create signature directory {ns_directory}: {e}"
+                        ))
+                    })?;
                     storage_map.insert(
                         ns_config.name.clone(),
-                        SignatureStorage::Filesystem(FilesystemSignatureStorage::new(
-                            &ns_directory,
-                        )),
+                        SignatureStorage::Filesystem(storage),
                     );
                 }
             }
@@ -103,8 +370,55 @@ impl SignatureStorageConfig {
                     );
                 }
             }
-            SignatureStorageConfig::Azure { .. } => {
-                todo!("Azure storage not yet implemented for signature storage");
+            SignatureStorageConfig::Sqlite => {
+                let database = SqliteDatabase::open(data_directory).map_err(|e| {
+                    ConfigError::Message(format!("Failed to open SQLite database: {e}"))
+                })?;
+                for ns_config in namespaces {
+                    storage_map.insert(
+                        ns_config.name.clone(),
+                        SignatureStorage::Sqlite(SqliteSignatureStorage::new(
+                            database.clone(),
+                            &ns_config.name,
+                        )),
+                    );
+                }
+            }
+            SignatureStorageConfig::Azure { container_name, .. } => {
+                let client = AzureBlobClient::new(self.azure_connection_string()?, container_name);
+                client.create_container_if_not_exists().await.map_err(|e| {
+                    ConfigError::Message(format!("Failed to create Azure container: {e}"))
+                })?;
+                for ns_config in namespaces {
+                    storage_map.insert(
+                        ns_config.name.clone(),
+                        SignatureStorage::Azure(AzureSignatureStorage::new(
+                            client.clone(),
+                            &ns_config.name,
+                        )),
+                    );
+                }
+            }
+            SignatureStorageConfig::S3 { prefix, .. } => {
+                let client = self.s3_client()?;
+                client.check_bucket().await.map_err(|e| {
+                    ConfigError::Message(format!("Failed to access S3 bucket: {e}"))
+                })?;
+                for ns_config in namespaces {
+                    storage_map.insert(
+                        ns_config.name.clone(),
+                        SignatureStorage::S3(S3SignatureStorage::new(
+                            client.clone(),
+                            prefix,
+                            &ns_config.name,
+                        )),
+                    );
+                }
+            }
+            SignatureStorageConfig::Mirrored { .. } => {
+                return Err(ConfigError::Message(
+                    "Mirrored storage replicas cannot be Mirrored".to_string(),
+                ));
             }
         }
 
@@ -112,6 +426,18 @@ impl SignatureStorageConfig {
     }
 }
 
+/// Azure container names are 3-63 characters of lowercase letters, digits and non-consecutive hyphens,
+/// starting and ending with a letter or digit.
+fn is_valid_container_name(name: &str) -> bool {
+    (3..=63).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && !name.contains("--")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,24 +471,129 @@ mod tests {
         let azure_with_conn = SignatureStorageConfig::Azure {
             account_name: "test".to_string(),
             container_name: "test".to_string(),
-            connection_string: Some("DefaultEndpointsProtocol=https;AccountName=test;".to_string()),
+            connection_string: Some(
+                "DefaultEndpointsProtocol=https;AccountName=test;AccountKey=a2V5".to_string(),
+            ),
         };
         let directory = "this/shouldn't/matter";
         assert!(azure_with_conn.validate(directory).is_ok());
 
-        // Test Azure without connection string (should fail)
+        // Test Azure with a connection string lacking credentials (should fail)
+        let azure_no_key = SignatureStorageConfig::Azure {
+            account_name: "test".to_string(),
+            container_name: "test".to_string(),
+            connection_string: Some("DefaultEndpointsProtocol=https;AccountName=test;".to_string()),
+        };
+        assert!(azure_no_key.validate(directory).is_err());
+
+        // Test Azure with an invalid container name (should fail)
+        let azure_bad_container = SignatureStorageConfig::Azure {
+            account_name: "test".to_string(),
+            container_name: "Bad_Container".to_string(),
+            connection_string: Some("UseDevelopmentStorage=true".to_string()),
+        };
+        let result = azure_bad_container.validate(directory);
+        assert!(result.unwrap_err().to_string().contains("container name"));
+
+        // Test Azure without connection string (should fail unless provided via environment)
         let azure_no_conn = SignatureStorageConfig::Azure {
             account_name: "test".to_string(),
             container_name: "test".to_string(),
             connection_string: None,
         };
         let result = azure_no_conn.validate(directory);
-        assert!(result.is_err());
+        if std::env::var(AZURE_STORAGE_CONNECTION_STRING_ENV).is_err() {
+            assert!(
+                result
+                    .unwrap_err()
+                    .to_string()
+                    .contains("requires connection_string")
+            );
+        }
+    }
+
+    #[test]
+    fn test_s3_storage_config_validation() {
+        let s3 = |endpoint: Option<&str>| SignatureStorageConfig::S3 {
+            bucket: "signatures".to_string(),
+            region: default_s3_region(),
+            endpoint: endpoint.map(str::to_string),
+            prefix: String::new(),
+            access_key_id: Some("minioadmin".to_string()),
+            secret_access_key: Some("minioadmin".to_string()),
+            session_token: None,
+        };
+        assert!(s3(None).validate("").is_ok());
+        assert!(s3(Some("http://127.0.0.1:9000")).validate("").is_ok());
+
+        let result = s3(Some("not a url")).validate("");
         assert!(
             result
                 .unwrap_err()
                 .to_string()
-                .contains("requires connection_string")
+                .contains("Invalid S3 endpoint")
         );
+
+        let SignatureStorageConfig::S3 { region, prefix, .. } =
+            toml_config("type = \"S3\"\nbucket = \"signatures\"")
+        else {
+            panic!("Expected S3 config");
+        };
+        assert_eq!(region, "us-east-1");
+        assert_eq!(prefix, "");
+    }
+
+    #[test]
+    fn test_mirrored_storage_config_validation() {
+        let mirrored = toml_config(
+            r#"
+            type = "Mirrored"
+            reconcile_interval_seconds = 300
+            [[replicas]]
+            type = "File"
+            [[replicas]]
+            type = "InMemory"
+            "#,
+        );
+        assert_eq!(mirrored.write_quorum(), 2);
+        assert!(mirrored.validate("/tmp").is_ok());
+
+        let with_quorum = |write_quorum| SignatureStorageConfig::Mirrored {
+            replicas: vec![
+                SignatureStorageConfig::File,
+                SignatureStorageConfig::InMemory,
+            ],
+            write_quorum: Some(write_quorum),
+            reconcile_interval_seconds: None,
+        };
+        assert!(with_quorum(1).validate("/tmp").is_ok());
+        let result = with_quorum(3).validate("/tmp");
+        assert!(result.unwrap_err().to_string().contains("write_quorum"));
+
+        // Replica configuration is validated too
+        let result = with_quorum(1).validate("");
+        assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+
+        let nested = SignatureStorageConfig::Mirrored {
+            replicas: vec![with_quorum(1)],
+            write_quorum: None,
+            reconcile_interval_seconds: None,
+        };
+        let result = nested.validate("/tmp");
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("cannot be Mirrored")
+        );
+    }
+
+    fn toml_config(toml: &str) -> SignatureStorageConfig {
+        config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
     }
 }
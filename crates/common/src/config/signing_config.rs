@@ -1,8 +1,12 @@
 use config::ConfigError;
 use serde::{Deserialize, Serialize};
 
-use crate::storage::signing_keys::{
-    FileSigningKeyRepository, FileVerifyingKeyRepository, SigningKeyStorage, VerifyingKeyStorage,
+use crate::storage::{
+    RecoveryReport,
+    signing_keys::{
+        FileSigningKeyRepository, FileVerifyingKeyRepository, SigningKeyStorage,
+        VerifyingKeyStorage,
+    },
 };
 
 /// Default key lifetime in seconds = 30 days
@@ -32,14 +36,24 @@ impl SigningConfig {
         validate_directory(data_directory, "Signing key directory")
     }
 
-    /// Panics if initialization of key directory fails
-    pub fn build_signing_key_storage(&self, data_directory: &str) -> SigningKeyStorage {
+    /// Runs the startup recovery step of the key directory, before building the storage.
+    pub fn recover(&self, data_directory: &str) -> Result<RecoveryReport, ConfigError> {
+        FileSigningKeyRepository::recover(data_directory)
+            .map_err(|e| ConfigError::Message(e.to_string()))
+    }
+
+    pub fn build_signing_key_storage(
+        &self,
+        data_directory: &str,
+    ) -> Result<SigningKeyStorage, ConfigError> {
         // For now, we'll only use FileSigningKeyRepository
         // This could be configurable in the future
-        SigningKeyStorage::File(FileSigningKeyRepository::new(
+        let repository = FileSigningKeyRepository::new(
             data_directory,
             chrono::Duration::seconds(self.key_lifetime_seconds),
-        ))
+        )
+        .map_err(|e| ConfigError::Message(format!("Failed to load signing keys: {e}")))?;
+        Ok(SigningKeyStorage::File(repository))
     }
 }
 
@@ -130,3 +130,68 @@ pub struct VerifyingKey {
     pub key_id: Uuid,
     pub not_before: DateTime<Utc>,
 }
+
+/// A key as listed by the web server's `/info` endpoint
+#[derive(Debug, Deserialize)]
+struct PublishedKey {
+    /// Hex encoded Ed25519 public key
+    public_key: String,
+    key_id: Uuid,
+    not_before: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PublishedKeys {
+    keys: Vec<PublishedKey>,
+}
+
+impl VerifyingKey {
+    /// Parses the keys an auditor publishes, given the JSON served at the web server's `/info`
+    pub fn from_published_json(bytes: &[u8]) -> Result<Vec<VerifyingKey>, String> {
+        let published: PublishedKeys =
+            serde_json::from_slice(bytes).map_err(|e| format!("Invalid published keys: {e}"))?;
+        published
+            .keys
+            .into_iter()
+            .map(|key| {
+                let public_key: [u8; 32] = hex::decode(&key.public_key)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| format!("Invalid public key for key {}", key.key_id))?;
+                Ok(VerifyingKey {
+                    verifying_key: ed25519_dalek::VerifyingKey::from_bytes(&public_key)
+                        .map_err(|e| format!("Invalid public key for key {}: {e}", key.key_id))?,
+                    key_id: key.key_id,
+                    not_before: DateTime::from_timestamp(key.not_before, 0)
+                        .ok_or_else(|| format!("Invalid not_before for key {}", key.key_id))?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_keys_are_parsed() {
+        let key = SigningKey::generate(Duration::days(1))
+            .verifying_key()
+            .unwrap();
+        let json = serde_json::json!({
+            "keys": [{
+                "public_key": hex::encode(key.verifying_key),
+                "key_id": key.key_id.to_string(),
+                "not_before": key.not_before.timestamp(),
+            }]
+        });
+
+        let keys = VerifyingKey::from_published_json(json.to_string().as_bytes()).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key_id, key.key_id);
+        assert_eq!(keys[0].verifying_key, key.verifying_key);
+
+        assert!(VerifyingKey::from_published_json(br#"{"keys": [{"public_key": "00", "key_id": "00000000-0000-0000-0000-000000000000", "not_before": 0}]}"#).is_err());
+    }
+}
@@ -166,12 +166,55 @@ impl EpochSignature {
         }
     }
 
+    pub fn epoch(&self) -> Epoch {
+        match self {
+            EpochSignature::V1(signature) => signature.epoch,
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        match self {
+            EpochSignature::V1(signature) => &signature.namespace,
+        }
+    }
+
     pub fn signing_key_id(&self) -> Uuid {
         match self {
             EpochSignature::V1(signature) => signature.key_id,
         }
     }
 
+    pub fn signature_bytes(&self) -> &[u8] {
+        match self {
+            EpochSignature::V1(signature) => &signature.signature,
+        }
+    }
+
+    /// The exact bytes the signature was computed over, so clients can verify it without
+    /// re-encoding the message themselves
+    pub fn signed_message(&self) -> Result<Vec<u8>, SerializationError> {
+        match self {
+            EpochSignature::V1(signature) => signature.to_message().to_vec(),
+        }
+    }
+
+    /// Encodes the signature as a protobuf `SignatureEnvelope`
+    pub fn to_protobuf(&self) -> Result<Vec<u8>, SerializationError> {
+        let envelope = match self {
+            EpochSignature::V1(signature) => {
+                let message = signature.to_message();
+                crate::proto::types::SignatureEnvelope {
+                    version: self.version_int(),
+                    message: crate::proto::types::SignatureMessage::from(&message),
+                    signed_message: message.to_vec()?,
+                    signature: signature.signature.clone(),
+                    key_id: signature.key_id.to_string(),
+                }
+            }
+        };
+        Ok(envelope.encode_to_vec())
+    }
+
     pub async fn verify(
         &self,
         verifying_key_repo: &impl VerifyingKeyRepository,
@@ -187,3 +230,52 @@ impl EpochSignature {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        NamespaceStatus,
+        akd_configurations::AkdConfiguration,
+        storage::signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+    };
+
+    #[tokio::test]
+    async fn test_served_encodings_carry_the_signed_bytes() {
+        let repo = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = repo.get_current_signing_key().await.unwrap();
+        let verifying_keys = repo.verifying_key_repository().unwrap();
+        let namespace = NamespaceInfo {
+            configuration: AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: Epoch::new(1),
+            status: NamespaceStatus::Online,
+            version: 0,
+        };
+        let signature = EpochSignature::sign(namespace, Epoch::new(4), [4u8; 32], &key).unwrap();
+
+        // A client holding only the served bytes, signature and key can verify
+        let verifying_key = verifying_keys
+            .get_verifying_key(signature.signing_key_id())
+            .await
+            .unwrap()
+            .unwrap();
+        let raw_signature =
+            ed25519_dalek::Signature::from_slice(signature.signature_bytes()).unwrap();
+        let signed_message = signature.signed_message().unwrap();
+        verifying_key
+            .verifying_key
+            .verify(&signed_message, &raw_signature)
+            .unwrap();
+
+        let envelope = crate::proto::types::SignatureEnvelope::decode(
+            signature.to_protobuf().unwrap().as_slice(),
+        )
+        .unwrap();
+        assert_eq!(envelope.signed_message, signed_message);
+        assert_eq!(envelope.message.encode_to_vec(), signed_message);
+        assert_eq!(envelope.key_id, signature.signing_key_id().to_string());
+    }
+}
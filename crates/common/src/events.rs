@@ -0,0 +1,57 @@
+use tokio::sync::broadcast;
+
+use crate::{EpochSignature, StatusTransition};
+
+/// Events buffered per subscriber before the slowest subscriber starts missing events
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// A change made by the auditor that live subscribers may want to act on.
+#[derive(Clone, Debug)]
+pub enum AuditEvent {
+    /// A signature was stored for a newly audited epoch
+    SignatureStored(EpochSignature),
+    /// A namespace status transition was recorded
+    StatusChanged(StatusTransition),
+}
+
+impl AuditEvent {
+    pub fn namespace(&self) -> &str {
+        match self {
+            AuditEvent::SignatureStored(signature) => signature.namespace(),
+            AuditEvent::StatusChanged(transition) => &transition.namespace,
+        }
+    }
+}
+
+/// In-process channel of audit events, for deployments that run the auditor and the web server in
+/// one process.
+///
+/// Events are published after the change is written to storage, so storage stays the source of
+/// truth: subscribers that lag behind and miss events can recover by re-reading storage.
+#[derive(Clone, Debug)]
+pub struct AuditEvents {
+    sender: broadcast::Sender<AuditEvent>,
+}
+
+impl Default for AuditEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl AuditEvents {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Publishes `event` to the current subscribers. Events published without subscribers are dropped.
+    pub fn publish(&self, event: AuditEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AuditEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,579 @@
+//! Signed, self-contained evidence of an audit proof that failed verification.
+//!
+//! An evidence record holds everything needed to repeat the failed check without the auditor: the
+//! raw audit blob, the previous root hash it was checked against and the AKD configuration. It is
+//! signed by the auditor, so a third party holding the auditor's published keys can check who
+//! produced it with [`EvidenceRecord::verify`] and then re-run the verification with
+//! [`EvidenceRecord::recheck`].
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use akd::local_auditing::{AuditBlob, AuditBlobName};
+use ed25519_dalek::Verifier;
+use ed25519_dalek::ed25519::signature::SignerMut;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use crate::{
+    Ciphersuite, Epoch, NamespaceInfo, SignError, VerifyError,
+    akd_configurations::{AkdConfiguration, verify_consecutive_append_only},
+    crypto::{SigningKey, VerifyingKey},
+    error::SerializationError,
+    storage::{signing_keys::VerifyingKeyRepository, write_atomic},
+};
+
+/// Prefix prepended to every serialized evidence record before signing, so an evidence signature
+/// can never be presented as any other kind of auditor signature.
+const EVIDENCE_DOMAIN_SEPARATOR: &[u8] = b"akd-watch/evidence/v1";
+
+const EVIDENCE_DIRECTORY: &str = "evidence";
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvidenceError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Evidence JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Signing error: {0}")]
+    SignError(#[from] SignError),
+    #[error("Signing key error: {0}")]
+    SigningKeyError(String),
+    #[error("Invalid blob name {0}")]
+    InvalidBlobName(String),
+    #[error("Previous digest has length {0}, expected 32")]
+    InvalidPreviousDigest(usize),
+}
+
+/// A signed statement that the audit blob `blob_name` of `namespace` failed verification with
+/// `error` when checked against `previous_digest`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "evidence_version")]
+pub enum EvidenceRecord {
+    #[allow(private_interfaces)]
+    V1(EvidenceRecordV1),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EvidenceRecordV1 {
+    pub ciphersuite: Ciphersuite,
+    pub namespace: String,
+    pub configuration: AkdConfiguration,
+    pub epoch: Epoch,
+    pub blob_name: String,
+    /// Raw audit blob as downloaded from the AKD
+    #[serde(with = "hex::serde")]
+    pub blob: Vec<u8>,
+    /// Root hash of the previous epoch the blob was checked against. `None` if the blob could not be
+    /// decoded far enough to tell, which is only the case for the namespace's starting epoch.
+    pub previous_digest: Option<Vec<u8>>,
+    /// The verification error, exactly as reported by the auditor
+    pub error: String,
+    pub downloaded_at: i64,
+    pub detected_at: i64,
+    pub signature: Vec<u8>,
+    /// Key that made `signature`. Informational only: the signature is checked against the
+    /// auditor's published key with the same `key_id`, never against this copy.
+    pub verifying_key: VerifyingKey,
+}
+
+impl EvidenceRecordV1 {
+    fn verify(&self, verifying_key: &VerifyingKey) -> Result<(), VerifyError> {
+        let message = self.to_message().to_vec()?;
+
+        let signature =
+            ed25519_dalek::Signature::from_bytes(self.signature.as_slice().try_into().map_err(
+                |_| VerifyError::SignatureLengthError {
+                    expected: 64,
+                    actual: self.signature.len(),
+                },
+            )?);
+
+        verifying_key
+            .verifying_key
+            .verify(&message, &signature)
+            .map_err(VerifyError::from)
+    }
+
+    fn to_message(&self) -> EvidenceSignedMessage {
+        EvidenceSignedMessage {
+            ciphersuite: self.ciphersuite,
+            namespace: self.namespace.clone(),
+            configuration: format!("{:?}", self.configuration),
+            epoch: self.epoch,
+            blob_name: self.blob_name.clone(),
+            blob_hash: Sha256::digest(&self.blob).to_vec(),
+            previous_digest: self.previous_digest.clone(),
+            error: self.error.clone(),
+            downloaded_at: self.downloaded_at,
+            detected_at: self.detected_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EvidenceSignedMessage {
+    pub(crate) ciphersuite: Ciphersuite,
+    pub(crate) namespace: String,
+    pub(crate) configuration: String,
+    pub(crate) epoch: Epoch,
+    pub(crate) blob_name: String,
+    pub(crate) blob_hash: Vec<u8>,
+    pub(crate) previous_digest: Option<Vec<u8>>,
+    pub(crate) error: String,
+    pub(crate) downloaded_at: i64,
+    pub(crate) detected_at: i64,
+}
+
+impl EvidenceSignedMessage {
+    pub fn to_vec(&self) -> Result<Vec<u8>, SerializationError> {
+        let body = match self.ciphersuite {
+            Ciphersuite::ProtobufEd25519 => {
+                crate::proto::types::EvidenceMessage::from(self).encode_to_vec()
+            }
+            _ => {
+                return Err(SerializationError::UnknownFormat(format!(
+                    "{:?}",
+                    self.ciphersuite
+                )));
+            }
+        };
+        Ok([EVIDENCE_DOMAIN_SEPARATOR, body.as_slice()].concat())
+    }
+}
+
+/// Result of re-running the verification recorded in an evidence record
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum RecheckOutcome {
+    /// The blob still fails verification
+    Reproduced { error: String },
+    /// The blob passes verification against the recorded previous digest
+    NotReproduced,
+}
+
+impl EvidenceRecord {
+    pub fn version_int(&self) -> u32 {
+        match self {
+            EvidenceRecord::V1(_) => 0x00_01,
+        }
+    }
+
+    /// Signs evidence that `blob` failed verification for `namespace` with `error`
+    pub fn sign(
+        namespace: &NamespaceInfo,
+        blob: &AuditBlob,
+        previous_digest: Option<[u8; 32]>,
+        error: String,
+        downloaded_at: i64,
+        signing_key: &SigningKey,
+    ) -> Result<Self, EvidenceError> {
+        let verifying_key = signing_key
+            .verifying_key()
+            .map_err(EvidenceError::SigningKeyError)?;
+        let mut record = EvidenceRecordV1 {
+            ciphersuite: Ciphersuite::ProtobufEd25519,
+            namespace: namespace.name.clone(),
+            configuration: namespace.configuration.clone(),
+            epoch: blob.name.epoch.into(),
+            blob_name: blob.name.to_string(),
+            blob: blob.data.clone(),
+            previous_digest: previous_digest.map(|digest| digest.to_vec()),
+            error,
+            downloaded_at,
+            detected_at: chrono::Utc::now().timestamp(),
+            signature: Vec::new(),
+            verifying_key,
+        };
+        let message = record.to_message().to_vec().map_err(SignError::from)?;
+        record.signature = signing_key
+            .signing_key()
+            .write()
+            .expect("Poisoned signing key")
+            .sign(&message)
+            .to_bytes()
+            .to_vec();
+        Ok(EvidenceRecord::V1(record))
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, EvidenceError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, EvidenceError> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Identifier of the record among the namespace's evidence. Ends with a hash of the signature,
+    /// so failures of one epoch detected within the same second get distinct identifiers.
+    pub fn id(&self) -> String {
+        match self {
+            EvidenceRecord::V1(record) => format!(
+                "{}-{}-{}",
+                record.epoch,
+                record.detected_at,
+                hex::encode(&Sha256::digest(&record.signature)[..8])
+            ),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        match self {
+            EvidenceRecord::V1(record) => &record.namespace,
+        }
+    }
+
+    pub fn epoch(&self) -> Epoch {
+        match self {
+            EvidenceRecord::V1(record) => record.epoch,
+        }
+    }
+
+    pub fn signing_key_id(&self) -> Uuid {
+        match self {
+            EvidenceRecord::V1(record) => record.verifying_key.key_id,
+        }
+    }
+
+    /// Checks the signature against the published key with the record's key id. The key carried in
+    /// the record is not trusted, since anyone can sign a record with a key of their own.
+    pub async fn verify(
+        &self,
+        verifying_key_repo: &impl VerifyingKeyRepository,
+    ) -> Result<(), VerifyError> {
+        let signing_key_id = self.signing_key_id();
+        let verifying_key = verifying_key_repo
+            .get_verifying_key(signing_key_id)
+            .await?
+            .ok_or_else(|| VerifyError::VerifyingKeyNotFound(signing_key_id))?;
+
+        match self {
+            EvidenceRecord::V1(record) => record.verify(&verifying_key),
+        }
+    }
+
+    /// Re-runs the verification of the recorded blob against the recorded previous digest
+    #[instrument(skip_all, fields(namespace = self.namespace(), epoch = %self.epoch()))]
+    pub async fn recheck(&self) -> Result<RecheckOutcome, EvidenceError> {
+        let EvidenceRecord::V1(record) = self;
+        let name = AuditBlobName::try_from(record.blob_name.as_str())
+            .map_err(|e| EvidenceError::InvalidBlobName(format!("{}: {e:?}", record.blob_name)))?;
+        let blob = AuditBlob {
+            name,
+            data: record.blob.clone(),
+        };
+
+        let (end_epoch, previous_hash_from_blob, end_hash, proof) = match blob.decode() {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                return Ok(RecheckOutcome::Reproduced {
+                    error: format!("{e:?}"),
+                });
+            }
+        };
+        let previous_hash = match &record.previous_digest {
+            Some(digest) => digest
+                .as_slice()
+                .try_into()
+                .map_err(|_| EvidenceError::InvalidPreviousDigest(digest.len()))?,
+            None => previous_hash_from_blob,
+        };
+
+        let outcome = match verify_consecutive_append_only(
+            &record.configuration,
+            &proof,
+            previous_hash,
+            end_hash,
+            end_epoch,
+        )
+        .await
+        {
+            Ok(()) => RecheckOutcome::NotReproduced,
+            Err(e) => RecheckOutcome::Reproduced {
+                error: e.to_string(),
+            },
+        };
+        debug!(?outcome, "Rechecked evidence");
+        Ok(outcome)
+    }
+}
+
+/// Listing entry for a stored evidence record
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EvidenceSummary {
+    pub id: String,
+    pub epoch: Epoch,
+    pub blob_name: String,
+    pub error: String,
+    pub detected_at: i64,
+}
+
+impl From<&EvidenceRecord> for EvidenceSummary {
+    fn from(record: &EvidenceRecord) -> Self {
+        let id = record.id();
+        match record {
+            EvidenceRecord::V1(record) => EvidenceSummary {
+                id,
+                epoch: record.epoch,
+                blob_name: record.blob_name.clone(),
+                error: record.error.clone(),
+                detected_at: record.detected_at,
+            },
+        }
+    }
+}
+
+/// Evidence records on disk, as `<data_directory>/evidence/<namespace>/<id>.json`
+#[derive(Clone, Debug)]
+pub struct EvidenceStore {
+    directory: PathBuf,
+}
+
+impl EvidenceStore {
+    pub fn new(data_directory: &str) -> Self {
+        Self {
+            directory: Path::new(data_directory).join(EVIDENCE_DIRECTORY),
+        }
+    }
+
+    /// Directory of a namespace's evidence, or `None` if the name cannot be a single path component
+    fn namespace_directory(&self, namespace: &str) -> Option<PathBuf> {
+        let valid = !namespace.is_empty()
+            && namespace != "."
+            && namespace != ".."
+            && !namespace.contains(['/', '\\']);
+        valid.then(|| self.directory.join(namespace))
+    }
+
+    /// Stores a record and returns its id
+    pub fn save(&self, record: &EvidenceRecord) -> Result<String, EvidenceError> {
+        let directory = self
+            .namespace_directory(record.namespace())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid namespace name {}", record.namespace()),
+                )
+            })?;
+        fs::create_dir_all(&directory)?;
+        let id = record.id();
+        write_atomic(directory.join(format!("{id}.json")), &record.to_json()?)?;
+        Ok(id)
+    }
+
+    pub fn get(&self, namespace: &str, id: &str) -> Result<Option<EvidenceRecord>, EvidenceError> {
+        let Some(directory) = self.namespace_directory(namespace) else {
+            return Ok(None);
+        };
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Ok(None);
+        }
+        match fs::read(directory.join(format!("{id}.json"))) {
+            Ok(bytes) => Ok(Some(EvidenceRecord::from_json(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Summaries of a namespace's evidence, oldest first
+    pub fn list(&self, namespace: &str) -> Result<Vec<EvidenceSummary>, EvidenceError> {
+        let Some(directory) = self.namespace_directory(namespace) else {
+            return Ok(Vec::new());
+        };
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut summaries = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let record = EvidenceRecord::from_json(&fs::read(&path)?)?;
+            summaries.push(EvidenceSummary::from(&record));
+        }
+        summaries.sort_by_key(|summary| (summary.detected_at, *summary.epoch.value()));
+        Ok(summaries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        TestAkdConfiguration,
+        storage::signing_keys::{
+            InMemorySigningKeyRepository, InMemoryVerifyingKeyRepository, SigningKeyRepository,
+        },
+        testing::test_namespace,
+    };
+    use akd::SingleAppendOnlyProof;
+
+    fn namespace() -> NamespaceInfo {
+        test_namespace("test").update_last_verified_epoch(Epoch::new(1))
+    }
+
+    /// A well-formed blob for epoch 2 whose empty proof cannot connect `[1; 32]` to `[2; 32]`
+    fn failing_blob() -> AuditBlob {
+        AuditBlob::new(
+            [1; 32],
+            [2; 32],
+            2,
+            &SingleAppendOnlyProof {
+                inserted: vec![],
+                unchanged_nodes: vec![],
+            },
+        )
+        .unwrap()
+    }
+
+    async fn signing_key() -> SigningKey {
+        InMemorySigningKeyRepository::new(chrono::Duration::days(1))
+            .get_current_signing_key()
+            .await
+            .unwrap()
+    }
+
+    /// The keys an auditor holding `key` would publish
+    fn published(key: &SigningKey) -> InMemoryVerifyingKeyRepository {
+        InMemoryVerifyingKeyRepository::new(vec![key.verifying_key().unwrap()])
+    }
+
+    #[tokio::test]
+    async fn test_evidence_sign_verify_and_recheck() {
+        let key = signing_key().await;
+        let blob = failing_blob();
+        let (_, _, end_hash, proof) = blob.decode().unwrap();
+        let error = akd::auditor::verify_consecutive_append_only::<TestAkdConfiguration>(
+            &proof, [1; 32], end_hash, 2,
+        )
+        .await
+        .unwrap_err();
+
+        let record = EvidenceRecord::sign(
+            &namespace(),
+            &blob,
+            Some([1; 32]),
+            error.to_string(),
+            1,
+            &key,
+        )
+        .unwrap();
+        record.verify(&published(&key)).await.unwrap();
+        assert_eq!(record.signing_key_id(), key.key_id());
+
+        // The record survives a JSON round trip and the failure reproduces from its contents alone
+        let record = EvidenceRecord::from_json(&record.to_json().unwrap()).unwrap();
+        record.verify(&published(&key)).await.unwrap();
+        assert_eq!(
+            record.recheck().await.unwrap(),
+            RecheckOutcome::Reproduced {
+                error: error.to_string()
+            }
+        );
+
+        let EvidenceRecord::V1(mut tampered) = record;
+        tampered.blob.push(0);
+        assert!(matches!(
+            EvidenceRecord::V1(tampered).verify(&published(&key)).await,
+            Err(VerifyError::SignatureVerificationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_evidence_signed_by_unpublished_key_is_rejected() {
+        let key = signing_key().await;
+        let forger = signing_key().await;
+        let record = EvidenceRecord::sign(
+            &namespace(),
+            &failing_blob(),
+            Some([1; 32]),
+            "error".to_string(),
+            1,
+            &forger,
+        )
+        .unwrap();
+        // The record is consistent with the key it carries, but that key was never published
+        assert!(matches!(
+            record.verify(&published(&key)).await,
+            Err(VerifyError::VerifyingKeyNotFound(key_id)) if key_id == forger.key_id()
+        ));
+
+        // Claiming a published key id does not help, since the published key material is used
+        let EvidenceRecord::V1(mut impostor) = record;
+        impostor.verifying_key.key_id = key.key_id();
+        assert!(matches!(
+            EvidenceRecord::V1(impostor).verify(&published(&key)).await,
+            Err(VerifyError::SignatureVerificationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_blob_reproduces() {
+        let key = signing_key().await;
+        let mut blob = failing_blob();
+        blob.data = vec![0xff; 8];
+        let record =
+            EvidenceRecord::sign(&namespace(), &blob, None, "bad blob".to_string(), 1, &key)
+                .unwrap();
+        assert!(matches!(
+            record.recheck().await.unwrap(),
+            RecheckOutcome::Reproduced { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_evidence_store() {
+        let directory =
+            std::env::temp_dir().join(format!("akd_watch_evidence_{}", uuid::Uuid::new_v4()));
+        let store = EvidenceStore::new(directory.to_str().unwrap());
+        let key = signing_key().await;
+        assert!(store.list("test").unwrap().is_empty());
+
+        let record = EvidenceRecord::sign(
+            &namespace(),
+            &failing_blob(),
+            Some([1; 32]),
+            "error".to_string(),
+            1,
+            &key,
+        )
+        .unwrap();
+        let id = store.save(&record).unwrap();
+
+        let summaries = store.list("test").unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].id, id);
+        assert_eq!(summaries[0].epoch, Epoch::new(2));
+        let stored = store.get("test", &id).unwrap().unwrap();
+        stored.verify(&published(&key)).await.unwrap();
+        assert!(store.get("test", "../test").unwrap().is_none());
+        assert!(store.get("..", &id).unwrap().is_none());
+
+        // A second failure of the same epoch detected in the same second is kept alongside the first
+        let EvidenceRecord::V1(mut second) = EvidenceRecord::sign(
+            &namespace(),
+            &failing_blob(),
+            Some([1; 32]),
+            "another error".to_string(),
+            1,
+            &key,
+        )
+        .unwrap();
+        second.detected_at = summaries[0].detected_at;
+        let second = EvidenceRecord::V1(second);
+        assert_ne!(store.save(&second).unwrap(), id);
+        assert_eq!(store.list("test").unwrap().len(), 2);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+use bincode::{Decode, Encode};
+use ed25519_dalek::Verifier;
+use ed25519_dalek::ed25519::signature::SignerMut;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    Ciphersuite, Epoch, EpochSignature, SignError, VerifyError,
+    crypto::{SigningKey, VerifyingKey},
+    error::SerializationError,
+    storage::signing_keys::VerifyingKeyRepository,
+};
+
+/// Prefix prepended to every serialized heartbeat message before signing.
+///
+/// Heartbeats carry the same fields as an epoch signature, so without a distinct prefix a heartbeat
+/// signature could be presented as a signature over an epoch (and vice versa).
+const HEARTBEAT_DOMAIN_SEPARATOR: &[u8] = b"akd-watch/heartbeat/v1";
+
+/// A signed statement that the auditor was alive at `observed_at` and that the latest epoch it had
+/// verified for the namespace at that time was `epoch`, with root hash `digest`.
+///
+/// Clients can use heartbeats to distinguish an AKD that has not published anything new from an
+/// auditor that has stopped auditing.
+#[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode)]
+#[serde(tag = "heartbeat_version")]
+pub enum Heartbeat {
+    #[allow(private_interfaces)]
+    V1(HeartbeatV1),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode)]
+pub struct HeartbeatV1 {
+    pub ciphersuite: Ciphersuite,
+    pub namespace: String,
+    pub observed_at: i64,
+    pub epoch: Epoch,
+    pub digest: Vec<u8>,
+    pub signature: Vec<u8>,
+    #[bincode(with_serde)]
+    pub key_id: Uuid,
+}
+
+impl HeartbeatV1 {
+    fn verify(&self, verifying_key: &VerifyingKey) -> Result<(), VerifyError> {
+        let message = self.to_message().to_vec()?;
+
+        let signature =
+            ed25519_dalek::Signature::from_bytes(self.signature.as_slice().try_into().map_err(
+                |_| VerifyError::SignatureLengthError {
+                    expected: 64,
+                    actual: self.signature.len(),
+                },
+            )?);
+
+        verifying_key
+            .verifying_key
+            .verify(&message, &signature)
+            .map_err(VerifyError::from)
+    }
+
+    fn to_message(&self) -> HeartbeatSignedMessage {
+        HeartbeatSignedMessage {
+            ciphersuite: self.ciphersuite,
+            namespace: self.namespace.clone(),
+            observed_at: self.observed_at,
+            epoch: self.epoch,
+            digest: self.digest.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Encode)]
+pub struct HeartbeatSignedMessage {
+    pub(crate) ciphersuite: Ciphersuite,
+    pub(crate) namespace: String,
+    pub(crate) observed_at: i64,
+    pub(crate) epoch: Epoch,
+    pub(crate) digest: Vec<u8>,
+}
+
+impl HeartbeatSignedMessage {
+    pub fn to_vec(&self) -> Result<Vec<u8>, SerializationError> {
+        let body = match self.ciphersuite {
+            Ciphersuite::ProtobufEd25519 => {
+                crate::proto::types::HeartbeatMessage::from(self).encode_to_vec()
+            }
+            Ciphersuite::BincodeEd25519 => bincode::encode_to_vec(self, crate::BINCODE_CONFIG)?,
+            _ => {
+                return Err(SerializationError::UnknownFormat(format!(
+                    "{:?}",
+                    self.ciphersuite
+                )));
+            }
+        };
+        Ok([HEARTBEAT_DOMAIN_SEPARATOR, body.as_slice()].concat())
+    }
+}
+
+impl Heartbeat {
+    pub fn version_int(&self) -> u32 {
+        match self {
+            Heartbeat::V1(_) => 0x00_01,
+        }
+    }
+
+    /// Signs a heartbeat for the given namespace, vouching for `latest_signature` as the most recent
+    /// verified epoch at the current time.
+    pub fn sign(
+        namespace: &str,
+        latest_signature: &EpochSignature,
+        signing_key: &SigningKey,
+    ) -> Result<Self, SignError> {
+        let message = HeartbeatSignedMessage {
+            ciphersuite: Ciphersuite::default(),
+            namespace: namespace.to_string(),
+            observed_at: chrono::Utc::now().timestamp(),
+            epoch: latest_signature.epoch(),
+            digest: latest_signature.digest(),
+        };
+        let signature = signing_key
+            .signing_key()
+            .write()
+            .expect("Poisoned signing key")
+            .sign(&message.to_vec()?);
+        Ok(Heartbeat::V1(HeartbeatV1 {
+            ciphersuite: message.ciphersuite,
+            namespace: message.namespace,
+            observed_at: message.observed_at,
+            epoch: message.epoch,
+            digest: message.digest,
+            signature: signature.to_bytes().to_vec(),
+            key_id: signing_key.key_id(),
+        }))
+    }
+
+    pub fn namespace(&self) -> &str {
+        match self {
+            Heartbeat::V1(heartbeat) => &heartbeat.namespace,
+        }
+    }
+
+    pub fn epoch(&self) -> Epoch {
+        match self {
+            Heartbeat::V1(heartbeat) => heartbeat.epoch,
+        }
+    }
+
+    pub fn observed_at(&self) -> i64 {
+        match self {
+            Heartbeat::V1(heartbeat) => heartbeat.observed_at,
+        }
+    }
+
+    pub fn signing_key_id(&self) -> Uuid {
+        match self {
+            Heartbeat::V1(heartbeat) => heartbeat.key_id,
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        verifying_key_repo: &impl VerifyingKeyRepository,
+    ) -> Result<(), VerifyError> {
+        let signing_key_id = self.signing_key_id();
+        let verifying_key = verifying_key_repo
+            .get_verifying_key(signing_key_id)
+            .await?
+            .ok_or_else(|| VerifyError::VerifyingKeyNotFound(signing_key_id))?;
+
+        match self {
+            Heartbeat::V1(heartbeat) => heartbeat.verify(&verifying_key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        NamespaceInfo,
+        storage::signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+        testing,
+    };
+
+    fn test_namespace() -> NamespaceInfo {
+        testing::test_namespace("test").update_last_verified_epoch(Epoch::new(7))
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_sign_and_verify() {
+        let repo = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = repo.get_current_signing_key().await.unwrap();
+        let epoch_signature =
+            EpochSignature::sign(test_namespace(), Epoch::new(7), [7u8; 32], &key).unwrap();
+
+        let heartbeat = Heartbeat::sign("test", &epoch_signature, &key).unwrap();
+
+        assert_eq!(heartbeat.epoch(), Epoch::new(7));
+        assert_eq!(heartbeat.namespace(), "test");
+        heartbeat
+            .verify(&repo.verifying_key_repository().unwrap())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_tampered_epoch_fails_verification() {
+        let repo = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = repo.get_current_signing_key().await.unwrap();
+        let epoch_signature =
+            EpochSignature::sign(test_namespace(), Epoch::new(7), [7u8; 32], &key).unwrap();
+
+        let Heartbeat::V1(mut heartbeat) = Heartbeat::sign("test", &epoch_signature, &key).unwrap();
+        heartbeat.epoch = Epoch::new(8);
+
+        let result = Heartbeat::V1(heartbeat)
+            .verify(&repo.verifying_key_repository().unwrap())
+            .await;
+        assert!(matches!(
+            result,
+            Err(VerifyError::SignatureVerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_heartbeat_message_is_domain_separated() {
+        let message = HeartbeatSignedMessage {
+            ciphersuite: Ciphersuite::ProtobufEd25519,
+            namespace: "test".to_string(),
+            observed_at: 1,
+            epoch: Epoch::new(1),
+            digest: vec![1u8; 32],
+        };
+        let epoch_message = crate::EpochSignedMessage {
+            ciphersuite: Ciphersuite::ProtobufEd25519,
+            namespace: "test".to_string(),
+            timestamp: 1,
+            epoch: Epoch::new(1),
+            digest: vec![1u8; 32],
+        };
+
+        let bytes = message.to_vec().unwrap();
+        assert!(bytes.starts_with(HEARTBEAT_DOMAIN_SEPARATOR));
+        assert_ne!(bytes, epoch_message.to_vec().unwrap());
+    }
+}
@@ -0,0 +1,311 @@
+//! Offline integrity scan of a namespace's stored signatures.
+
+use serde::Serialize;
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use crate::{
+    NamespaceInfo, VerifyError,
+    storage::{
+        signatures::{SignatureRepository, SignatureRepositoryError},
+        signing_keys::{VerifyingKeyRepository, VerifyingKeyRepositoryError},
+    },
+};
+
+/// Number of epochs listed per storage query while scanning
+const SCAN_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScanError {
+    #[error("Signature storage error: {0}")]
+    SignatureStorage(#[from] SignatureRepositoryError),
+    #[error("Verifying key repository error: {0}")]
+    VerifyingKeys(#[from] VerifyingKeyRepositoryError),
+}
+
+/// Machine-readable result of [`scan_namespace`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ScanReport {
+    pub namespace: String,
+    pub starting_epoch: u64,
+    pub last_verified_epoch: Option<u64>,
+    /// Number of stored signatures that were read
+    pub scanned_signatures: u64,
+    /// Number of stored signatures that passed every check
+    pub valid_signatures: u64,
+    /// Ranges between `starting_epoch` and `last_verified_epoch` with no stored signature
+    pub gaps: Vec<EpochGap>,
+    /// Signatures that could not be read or failed verification
+    pub bad_signatures: Vec<BadSignature>,
+    /// Signatures made with a key that is not in the verifying key repository
+    pub unknown_key_ids: Vec<UnknownKeyId>,
+    /// Signatures whose namespace or epoch differs from the key they are stored under
+    pub mismatched_signatures: Vec<MismatchedSignature>,
+    /// Stored epochs outside `starting_epoch..=last_verified_epoch`. Not an error by themselves: a
+    /// crash between storing a signature and updating the namespace leaves one past the last verified epoch.
+    pub out_of_range_epochs: Vec<u64>,
+}
+
+impl ScanReport {
+    /// Whether the scan found no gaps, bad signatures, unknown keys or mismatches
+    pub fn is_clean(&self) -> bool {
+        self.gaps.is_empty()
+            && self.bad_signatures.is_empty()
+            && self.unknown_key_ids.is_empty()
+            && self.mismatched_signatures.is_empty()
+    }
+}
+
+/// Inclusive range of missing epochs
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EpochGap {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BadSignature {
+    pub epoch: u64,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UnknownKeyId {
+    pub epoch: u64,
+    pub key_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MismatchedSignature {
+    /// Epoch the signature is stored under
+    pub epoch: u64,
+    /// Namespace and epoch the signature actually covers
+    pub signed_namespace: String,
+    pub signed_epoch: u64,
+}
+
+/// Reads every signature stored for a namespace and checks it against the namespace state.
+///
+/// Each signature is verified with [`EpochSignature::verify`](crate::EpochSignature::verify) and
+/// checked to cover the namespace and epoch it is stored under, and the stored epochs are checked to
+/// be continuous from `starting_epoch` to `last_verified_epoch`. Problems with individual signatures
+/// are collected in the report; only failures to query the storage or key repository are errors.
+#[instrument(skip_all, fields(namespace = namespace.name))]
+pub async fn scan_namespace(
+    namespace: &NamespaceInfo,
+    signatures: &impl SignatureRepository,
+    verifying_keys: &impl VerifyingKeyRepository,
+) -> Result<ScanReport, ScanError> {
+    let starting_epoch = *namespace.starting_epoch.value();
+    let last_verified_epoch = namespace.last_verified_epoch.map(|epoch| *epoch.value());
+    let mut report = ScanReport {
+        namespace: namespace.name.clone(),
+        starting_epoch,
+        last_verified_epoch,
+        ..Default::default()
+    };
+    let expected = |epoch: u64| {
+        epoch >= starting_epoch && last_verified_epoch.is_some_and(|last| epoch <= last)
+    };
+    // Next epoch expected to follow the stored epochs seen so far
+    let mut next_expected = starting_epoch;
+
+    let mut cursor = 0;
+    loop {
+        let epochs = signatures
+            .list_epochs(cursor..=u64::MAX, SCAN_BATCH_SIZE)
+            .await?;
+        let Some(&last_epoch) = epochs.last() else {
+            break;
+        };
+
+        for epoch in epochs {
+            if !expected(epoch) {
+                report.out_of_range_epochs.push(epoch);
+            } else {
+                if epoch > next_expected {
+                    report.gaps.push(EpochGap {
+                        start: next_expected,
+                        end: epoch - 1,
+                    });
+                }
+                next_expected = epoch + 1;
+            }
+            check_signature(namespace, epoch, signatures, verifying_keys, &mut report).await?;
+        }
+
+        match last_epoch.checked_add(1) {
+            Some(epoch) => cursor = epoch,
+            None => break,
+        }
+    }
+
+    if let Some(last) = last_verified_epoch
+        && next_expected <= last
+    {
+        report.gaps.push(EpochGap {
+            start: next_expected,
+            end: last,
+        });
+    }
+
+    debug!(?report, "Scanned namespace signatures");
+    Ok(report)
+}
+
+async fn check_signature(
+    namespace: &NamespaceInfo,
+    epoch: u64,
+    signatures: &impl SignatureRepository,
+    verifying_keys: &impl VerifyingKeyRepository,
+    report: &mut ScanReport,
+) -> Result<(), ScanError> {
+    let signature = match signatures.get_signature(&epoch).await {
+        Ok(Some(signature)) => signature,
+        // Removed between listing and reading
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            report.scanned_signatures += 1;
+            report.bad_signatures.push(BadSignature {
+                epoch,
+                reason: format!("unreadable: {e}"),
+            });
+            return Ok(());
+        }
+    };
+    report.scanned_signatures += 1;
+
+    let mut valid = true;
+    if signature.namespace() != namespace.name || *signature.epoch().value() != epoch {
+        report.mismatched_signatures.push(MismatchedSignature {
+            epoch,
+            signed_namespace: signature.namespace().to_string(),
+            signed_epoch: *signature.epoch().value(),
+        });
+        valid = false;
+    }
+
+    match signature.verify(verifying_keys).await {
+        Ok(()) => {}
+        Err(VerifyError::VerifyingKeyNotFound(key_id)) => {
+            report.unknown_key_ids.push(UnknownKeyId { epoch, key_id });
+            valid = false;
+        }
+        Err(VerifyError::VerifyingKeyRepositoryError(e)) => return Err(e.into()),
+        Err(e) => {
+            report.bad_signatures.push(BadSignature {
+                epoch,
+                reason: e.to_string(),
+            });
+            valid = false;
+        }
+    }
+
+    if valid {
+        report.valid_signatures += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Epoch, EpochSignature,
+        storage::{
+            signatures::InMemorySignatureStorage,
+            signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+        },
+        testing::test_namespace,
+    };
+
+    fn namespace(name: &str, last_verified_epoch: u64) -> NamespaceInfo {
+        test_namespace(name).update_last_verified_epoch(Epoch::new(last_verified_epoch))
+    }
+
+    #[tokio::test]
+    async fn test_scan_reports_problems() {
+        let keys = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = keys.get_current_signing_key().await.unwrap();
+        let other_key = InMemorySigningKeyRepository::new(chrono::Duration::days(1))
+            .get_current_signing_key()
+            .await
+            .unwrap();
+        let info = namespace("test", 8);
+        let sign = |namespace: &NamespaceInfo, epoch: u64, key| {
+            EpochSignature::sign(namespace.clone(), Epoch::new(epoch), [1; 32], key).unwrap()
+        };
+
+        let mut storage = InMemorySignatureStorage::new();
+        for epoch in [1, 2, 5, 9] {
+            storage
+                .set_signature(&epoch, sign(&info, epoch, &key))
+                .await
+                .unwrap();
+        }
+        // Stored under the wrong epoch, for the wrong namespace, and with an unknown key
+        storage
+            .set_signature(&3, sign(&info, 4, &key))
+            .await
+            .unwrap();
+        storage
+            .set_signature(&6, sign(&namespace("other", 8), 6, &key))
+            .await
+            .unwrap();
+        storage
+            .set_signature(&7, sign(&info, 7, &other_key))
+            .await
+            .unwrap();
+
+        let report = scan_namespace(&info, &storage, &keys.verifying_key_repository().unwrap())
+            .await
+            .unwrap();
+
+        assert!(!report.is_clean());
+        assert_eq!(report.scanned_signatures, 7);
+        assert_eq!(report.valid_signatures, 4);
+        assert_eq!(
+            report.gaps,
+            vec![EpochGap { start: 4, end: 4 }, EpochGap { start: 8, end: 8 }]
+        );
+        assert_eq!(
+            report
+                .mismatched_signatures
+                .iter()
+                .map(|m| m.epoch)
+                .collect::<Vec<_>>(),
+            vec![3, 6]
+        );
+        assert_eq!(
+            report.unknown_key_ids,
+            vec![UnknownKeyId {
+                epoch: 7,
+                key_id: other_key.key_id()
+            }]
+        );
+        assert_eq!(report.out_of_range_epochs, vec![9]);
+
+        // The report is machine-readable
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["gaps"][0]["start"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_scan_clean_namespace() {
+        let keys = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = keys.get_current_signing_key().await.unwrap();
+        let info = namespace("test", 3);
+        let mut storage = InMemorySignatureStorage::new();
+        for epoch in 1..=3 {
+            let signature =
+                EpochSignature::sign(info.clone(), Epoch::new(epoch), [1; 32], &key).unwrap();
+            storage.set_signature(&epoch, signature).await.unwrap();
+        }
+
+        let report = scan_namespace(&info, &storage, &keys.verifying_key_repository().unwrap())
+            .await
+            .unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.valid_signatures, 3);
+    }
+}
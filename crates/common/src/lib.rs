@@ -1,13 +1,21 @@
 pub mod akd_configurations;
 pub mod akd_storage_factory;
 mod audit_blob_name;
+pub mod bundle;
 pub mod config;
 pub mod crypto;
 mod epoch_signature;
 mod error;
+pub mod events;
+pub mod evidence;
+mod heartbeat;
+pub mod integrity;
+pub mod metrics;
 mod namespace_info;
+pub mod observability;
 pub(crate) mod proto;
 pub mod storage;
+pub mod transparency_log;
 mod versions;
 
 pub use akd_configurations::BitwardenV1Configuration;
@@ -15,6 +23,8 @@ pub use audit_blob_name::SerializableAuditBlobName;
 use chrono::Duration;
 pub(crate) use epoch_signature::EpochSignedMessage;
 pub use epoch_signature::{EpochSignature, SignError, VerifyError};
+pub use heartbeat::Heartbeat;
+pub(crate) use heartbeat::HeartbeatSignedMessage;
 pub use namespace_info::*;
 use tokio::time::Instant;
 pub use versions::*;
@@ -76,6 +86,10 @@ pub async fn tic_toc<T>(f: impl core::future::Future<Output = T>) -> T {
 ///     // Result-aware logging with just the result value
 ///     let status = timed_event!(with_result(code) INFO, get_status_code();
 ///                               status_code = *code).await;
+///
+///     // Also record the duration in seconds in a metrics histogram
+///     let result = timed_event!(histogram(akd_watch_common::metrics::download_duration("ns"))
+///                               INFO, some_async_function(); "Downloaded").await;
 /// }
 ///
 /// async fn some_async_function() -> i32 { 42 }
@@ -84,6 +98,16 @@ pub async fn tic_toc<T>(f: impl core::future::Future<Output = T>) -> T {
 /// ```
 #[macro_export]
 macro_rules! timed_event {
+    // Histogram: records the duration in `$histogram`, then logs as the remaining arguments specify
+    (histogram($histogram:expr) $($rest:tt)+) => {
+        async {
+            let tic = ::tokio::time::Instant::now();
+            let result = $crate::timed_event!($($rest)+).await;
+            $histogram.record(tic.elapsed().as_secs_f64());
+            result
+        }
+    };
+
     // Basic case: just level and future
     ($level:ident, $future:expr) => {
         async {
@@ -0,0 +1,155 @@
+use std::sync::OnceLock;
+
+use ::metrics::{Histogram, counter, gauge, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use tracing::{error, warn};
+
+use crate::{
+    NamespaceStatus,
+    storage::signing_keys::{VerifyingKeyRepository, VerifyingKeyStorage},
+};
+
+/// Histogram buckets for durations, in seconds
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Returns the handle of the process-wide Prometheus recorder, installing the recorder on first use.
+/// The auditor and the web server share it, so the all-in-one binary serves both roles' metrics.
+fn prometheus_handle() -> &'static PrometheusHandle {
+    PROMETHEUS_HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets(DURATION_BUCKETS)
+            .expect("Duration buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if let Err(e) = ::metrics::set_global_recorder(recorder) {
+            error!(error = %e, "Failed to install the metrics recorder");
+        }
+        handle
+    })
+}
+
+/// Installs the metrics recorder. Metrics recorded before this are dropped, so call it at startup.
+pub fn install() {
+    prometheus_handle();
+}
+
+/// Renders all metrics in the Prometheus text format, refreshing the signing key age first
+pub async fn render(verifying_key_storage: &VerifyingKeyStorage) -> String {
+    match verifying_key_storage.list_keys().await {
+        Ok(keys) => {
+            if let Some(not_before) = keys.iter().map(|key| key.not_before).max() {
+                let age = chrono::Utc::now() - not_before;
+                gauge!("akd_watch_signing_key_age_seconds").set(age.num_seconds() as f64);
+            }
+        }
+        Err(e) => warn!(error = %e, "Failed to list verifying keys for metrics"),
+    }
+    let handle = prometheus_handle();
+    handle.run_upkeep();
+    handle.render()
+}
+
+/// Records the last epoch the auditor verified
+pub fn record_last_verified_epoch(namespace: &str, epoch: u64) {
+    gauge!("akd_watch_last_verified_epoch", "namespace" => namespace.to_string()).set(epoch as f64);
+}
+
+/// Records how many epochs the AKD has published past the namespace's last verified epoch
+pub fn record_epochs_behind_head(namespace: &str, epochs: u64) {
+    gauge!("akd_watch_epochs_behind_head", "namespace" => namespace.to_string()).set(epochs as f64);
+}
+
+/// Sets the namespace's status gauge to 1 for `status` and 0 for every other status
+pub fn record_namespace_status(namespace: &str, status: &NamespaceStatus) {
+    for candidate in NamespaceStatus::ALL {
+        let value = if &candidate == status { 1.0 } else { 0.0 };
+        gauge!(
+            "akd_watch_namespace_status",
+            "namespace" => namespace.to_string(),
+            "status" => format!("{candidate:?}"),
+        )
+        .set(value);
+    }
+}
+
+/// Counts an audit failure by the kind of error that caused it
+pub fn record_audit_failure(namespace: &str, kind: &'static str) {
+    counter!(
+        "akd_watch_audit_failures_total",
+        "namespace" => namespace.to_string(),
+        "kind" => kind,
+    )
+    .increment(1);
+}
+
+/// Time spent downloading audit proofs
+pub fn download_duration(namespace: &str) -> Histogram {
+    histogram!("akd_watch_download_duration_seconds", "namespace" => namespace.to_string())
+}
+
+/// Time spent verifying audit proofs
+pub fn verification_duration(namespace: &str) -> Histogram {
+    histogram!("akd_watch_verification_duration_seconds", "namespace" => namespace.to_string())
+}
+
+/// Counts a served HTTP request and records its latency. `route` is the matched route pattern, so
+/// path parameters do not create new series.
+pub fn record_http_request(method: &str, route: &str, status: u16, seconds: f64) {
+    counter!(
+        "akd_watch_http_requests_total",
+        "method" => method.to_string(),
+        "route" => route.to_string(),
+        "status" => status.to_string(),
+    )
+    .increment(1);
+    histogram!(
+        "akd_watch_http_request_duration_seconds",
+        "method" => method.to_string(),
+        "route" => route.to_string(),
+    )
+    .record(seconds);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_event_records_histogram() {
+        install();
+        let result = crate::timed_event!(histogram(download_duration("timed-namespace")) INFO,
+            async { 7 }; "Downloaded")
+        .await;
+        assert_eq!(result, 7);
+
+        let rendered = prometheus_handle().render();
+        assert!(
+            rendered.contains(
+                "akd_watch_download_duration_seconds_count{namespace=\"timed-namespace\"} 1"
+            ),
+            "{rendered}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespace_status_is_one_hot() {
+        install();
+        record_namespace_status("status-namespace", &NamespaceStatus::Online);
+        record_namespace_status("status-namespace", &NamespaceStatus::SignatureLost);
+
+        let rendered = prometheus_handle().render();
+        assert!(rendered.contains(
+            "akd_watch_namespace_status{namespace=\"status-namespace\",status=\"SignatureLost\"} 1"
+        ));
+        assert!(rendered.contains(
+            "akd_watch_namespace_status{namespace=\"status-namespace\",status=\"Online\"} 0"
+        ));
+    }
+}
@@ -12,9 +12,20 @@ pub enum NamespaceStatus {
     SignatureLost,
     /// Indicates that the auditor has downloaded a proof that failed verification. Future audits are not performed and the AKD should not be trusted.
     SignatureVerificationFailed,
+    /// Indicates that a signature with a different digest was already stored for an audited epoch. Either signature storage was tampered with or another auditor signed a conflicting epoch, so auditing stops until an operator investigates.
+    SignatureConflict,
 }
 
 impl NamespaceStatus {
+    pub const ALL: [NamespaceStatus; 6] = [
+        NamespaceStatus::Online,
+        NamespaceStatus::Initialization,
+        NamespaceStatus::Disabled,
+        NamespaceStatus::SignatureLost,
+        NamespaceStatus::SignatureVerificationFailed,
+        NamespaceStatus::SignatureConflict,
+    ];
+
     pub fn is_active(&self) -> bool {
         matches!(
             self,
@@ -31,6 +42,11 @@ pub struct NamespaceInfo {
     pub last_verified_epoch: Option<Epoch>,
     pub starting_epoch: Epoch,
     pub status: NamespaceStatus,
+    /// Incremented by the namespace repository on every write. Pass the version that was read to
+    /// `compare_and_swap_namespace` to detect concurrent modifications. Namespaces stored before
+    /// versioning was introduced read as version 0.
+    #[serde(default)]
+    pub version: u64,
 }
 
 impl NamespaceInfo {
@@ -48,3 +64,64 @@ impl NamespaceInfo {
         }
     }
 }
+
+/// Who caused a namespace status transition
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum StatusActor {
+    /// The auditor changed the status after an audit failed
+    Auditor,
+    /// The status was set from the auditor configuration at startup
+    Config,
+    /// An operator changed the status by hand
+    Operator,
+}
+
+/// A single entry in a namespace's append-only status history
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct StatusTransition {
+    pub namespace: String,
+    /// `None` when the namespace was first added
+    pub previous_status: Option<NamespaceStatus>,
+    pub new_status: NamespaceStatus,
+    /// The epoch being audited when the transition happened, if any
+    pub epoch: Option<Epoch>,
+    /// Short summary of the error or change that caused the transition
+    pub reason: Option<String>,
+    pub actor: StatusActor,
+    /// Unix timestamp of the transition
+    pub at: i64,
+}
+
+impl StatusTransition {
+    /// Records a transition of `namespace_info` to `new_status` at the current time
+    pub fn new(
+        namespace_info: &NamespaceInfo,
+        new_status: NamespaceStatus,
+        epoch: Option<Epoch>,
+        reason: Option<String>,
+        actor: StatusActor,
+    ) -> Self {
+        StatusTransition {
+            namespace: namespace_info.name.clone(),
+            previous_status: Some(namespace_info.status.clone()),
+            new_status,
+            epoch,
+            reason,
+            actor,
+            at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Records the initial status of a newly added namespace at the current time
+    pub fn added(namespace_info: &NamespaceInfo, actor: StatusActor) -> Self {
+        StatusTransition {
+            namespace: namespace_info.name.clone(),
+            previous_status: None,
+            new_status: namespace_info.status.clone(),
+            epoch: None,
+            reason: Some("namespace added".to_string()),
+            actor,
+            at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use config::ConfigError;
+use opentelemetry::{KeyValue, trace::TracerProvider as _};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{Resource, runtime, trace::TracerProvider};
+use thiserror::Error;
+use tracing::warn;
+use tracing_subscriber::{
+    Layer, fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::config::{LogFormat, ObservabilityConfig};
+
+#[derive(Debug, Error)]
+pub enum ObservabilityError {
+    #[error("Invalid observability configuration: {0}")]
+    Config(#[from] ConfigError),
+    #[error("Failed to build the OTLP span exporter: {0}")]
+    Exporter(#[from] opentelemetry::trace::TraceError),
+    #[error("Failed to install the tracing subscriber: {0}")]
+    Subscriber(#[from] tracing_subscriber::util::TryInitError),
+}
+
+/// Installs the process-wide tracing subscriber: console logs in the configured format and, if
+/// an OTLP endpoint is configured, span export.
+pub struct Observability {
+    config: ObservabilityConfig,
+    service_name: String,
+    stderr: bool,
+}
+
+impl Observability {
+    /// `service_name` is reported with exported spans unless the configuration overrides it
+    pub fn new(config: ObservabilityConfig, service_name: &str) -> Self {
+        Self {
+            config,
+            service_name: service_name.to_string(),
+            stderr: false,
+        }
+    }
+
+    /// Loads the configuration, see [`ObservabilityConfig::load`]
+    pub fn from_config(service_name: &str) -> Result<Self, ObservabilityError> {
+        Ok(Self::new(ObservabilityConfig::load()?, service_name))
+    }
+
+    /// Writes logs to stderr instead of stdout, keeping stdout for command output
+    pub fn with_stderr(mut self) -> Self {
+        self.stderr = true;
+        self
+    }
+
+    /// Installs the subscriber. Keep the returned guard alive until exit: dropping it flushes the
+    /// spans that have not been exported yet.
+    pub fn init(self) -> Result<ObservabilityGuard, ObservabilityError> {
+        let filter = self.config.env_filter()?;
+        let writer = if self.stderr {
+            BoxMakeWriter::new(std::io::stderr)
+        } else {
+            BoxMakeWriter::new(std::io::stdout)
+        };
+        let fmt_layer = match self.config.log_format {
+            LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(writer)
+                .boxed(),
+        };
+
+        let service_name = self
+            .config
+            .service_name
+            .clone()
+            .unwrap_or(self.service_name);
+        let tracer_provider = self
+            .config
+            .otlp_endpoint
+            .as_deref()
+            .map(|endpoint| tracer_provider(endpoint, &service_name))
+            .transpose()?;
+        let otel_layer = tracer_provider.as_ref().map(|provider| {
+            tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.clone()))
+        });
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt_layer)
+            .with(otel_layer)
+            .try_init()?;
+
+        Ok(ObservabilityGuard { tracer_provider })
+    }
+}
+
+/// Builds a provider that batches spans to the OTLP gRPC endpoint
+pub(crate) fn tracer_provider(
+    endpoint: &str,
+    service_name: &str,
+) -> Result<TracerProvider, opentelemetry::trace::TraceError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )]))
+        .build())
+}
+
+/// Flushes and shuts down span export when dropped
+#[must_use = "dropping the guard stops span export"]
+pub struct ObservabilityGuard {
+    tracer_provider: Option<TracerProvider>,
+}
+
+impl Drop for ObservabilityGuard {
+    fn drop(&mut self) {
+        if let Some(tracer_provider) = self.tracer_provider.take() {
+            if let Err(e) = tracer_provider.shutdown() {
+                warn!(error = %e, "Failed to flush exported spans");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry_proto::tonic::collector::trace::v1::{
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+        trace_service_server::{TraceService, TraceServiceServer},
+    };
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    use tonic::transport::server::TcpIncoming;
+
+    use super::*;
+
+    /// Stands in for an OTLP collector, keeping every export request it receives
+    #[derive(Clone, Default)]
+    struct Collector {
+        requests: Arc<Mutex<Vec<ExportTraceServiceRequest>>>,
+    }
+
+    #[tonic::async_trait]
+    impl TraceService for Collector {
+        async fn export(
+            &self,
+            request: tonic::Request<ExportTraceServiceRequest>,
+        ) -> Result<tonic::Response<ExportTraceServiceResponse>, tonic::Status> {
+            self.requests.lock().unwrap().push(request.into_inner());
+            Ok(tonic::Response::new(ExportTraceServiceResponse {
+                partial_success: None,
+            }))
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_spans_are_exported_to_collector() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        let collector = Collector::default();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(TraceServiceServer::new(collector.clone()))
+                .serve_with_incoming(incoming),
+        );
+
+        let provider = tracer_provider(&endpoint, "akd-watch-test").unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("akd-watch-test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let _cycle = tracing::info_span!("run_audit_cycle", namespace = "exported").entered();
+            let _epoch = tracing::info_span!("process_audit_request", epoch = 7).entered();
+        });
+        // Shutting down flushes the batch, blocking until the collector has responded
+        tokio::task::spawn_blocking(move || provider.shutdown())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let requests = collector.requests.lock().unwrap();
+        let resource_spans: Vec<_> = requests
+            .iter()
+            .flat_map(|request| &request.resource_spans)
+            .collect();
+        let service_names: Vec<_> = resource_spans
+            .iter()
+            .filter_map(|spans| spans.resource.as_ref())
+            .flat_map(|resource| &resource.attributes)
+            .filter(|attribute| attribute.key == "service.name")
+            .filter_map(|attribute| attribute.value.as_ref()?.value.as_ref())
+            .collect();
+        assert_eq!(
+            service_names,
+            vec![&Value::StringValue("akd-watch-test".to_string())]
+        );
+        let mut span_names: Vec<_> = resource_spans
+            .iter()
+            .flat_map(|spans| &spans.scope_spans)
+            .flat_map(|scope| &scope.spans)
+            .map(|span| span.name.as_str())
+            .collect();
+        span_names.sort();
+        assert_eq!(span_names, ["process_audit_request", "run_audit_cycle"]);
+    }
+}
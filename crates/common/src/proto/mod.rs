@@ -45,3 +45,77 @@ impl TryFrom<crate::proto::types::SignatureMessage> for crate::EpochSignedMessag
         })
     }
 }
+
+impl From<&crate::HeartbeatSignedMessage> for crate::proto::types::HeartbeatMessage {
+    fn from(input: &crate::HeartbeatSignedMessage) -> Self {
+        Self {
+            ciphersuite: input.ciphersuite.into(),
+            namespace: input.namespace.clone(),
+            observed_at: input.observed_at as u64,
+            epoch: (&input.epoch).into(),
+            digest: input.digest.clone(),
+        }
+    }
+}
+
+impl From<&crate::transparency_log::TreeHeadSignedMessage>
+    for crate::proto::types::TreeHeadMessage
+{
+    fn from(input: &crate::transparency_log::TreeHeadSignedMessage) -> Self {
+        Self {
+            ciphersuite: input.ciphersuite.into(),
+            tree_size: input.tree_size,
+            root_hash: input.root_hash.clone(),
+            timestamp: input.timestamp as u64,
+        }
+    }
+}
+
+impl From<&crate::EpochSignature> for crate::proto::types::LogEntry {
+    fn from(input: &crate::EpochSignature) -> Self {
+        match input {
+            crate::EpochSignature::V1(signature) => Self {
+                ciphersuite: signature.ciphersuite.into(),
+                namespace: signature.namespace.clone(),
+                timestamp: signature.timestamp as u64,
+                epoch: (&signature.epoch).into(),
+                digest: signature.digest.clone(),
+                signature: signature.signature.clone(),
+                key_id: signature.key_id.to_string(),
+            },
+        }
+    }
+}
+
+impl TryFrom<crate::proto::types::LogEntry> for crate::EpochSignature {
+    type Error = ConversionError;
+
+    fn try_from(input: crate::proto::types::LogEntry) -> Result<Self, Self::Error> {
+        Ok(Self::V1(crate::epoch_signature::EpochSignatureV1 {
+            ciphersuite: input.ciphersuite.into(),
+            namespace: input.namespace,
+            timestamp: input.timestamp as i64,
+            epoch: (&input.epoch).into(),
+            digest: input.digest,
+            signature: input.signature,
+            key_id: input.key_id.parse().map_err(|_| ConversionError)?,
+        }))
+    }
+}
+
+impl From<&crate::evidence::EvidenceSignedMessage> for crate::proto::types::EvidenceMessage {
+    fn from(input: &crate::evidence::EvidenceSignedMessage) -> Self {
+        Self {
+            ciphersuite: input.ciphersuite.into(),
+            namespace: input.namespace.clone(),
+            configuration: input.configuration.clone(),
+            epoch: (&input.epoch).into(),
+            blob_name: input.blob_name.clone(),
+            blob_hash: input.blob_hash.clone(),
+            previous_digest: input.previous_digest.clone(),
+            error: input.error.clone(),
+            downloaded_at: input.downloaded_at as u64,
+            detected_at: input.detected_at as u64,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use tracing::{trace, warn};
+
+/// Suffix of the temporary files written before being renamed into place
+const TEMP_FILE_SUFFIX: &str = ".tmp";
+
+/// Files found and cleaned up by a startup recovery step
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecoveryReport {
+    /// Leftover temporary files from interrupted writes, which were removed
+    pub removed_temp_files: Vec<PathBuf>,
+    /// Files that could not be decoded and were renamed aside with a `.corrupt` suffix
+    pub quarantined_files: Vec<PathBuf>,
+    /// Signatures moved from a legacy layout into the current one
+    pub migrated_signatures: u64,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed_temp_files.is_empty()
+            && self.quarantined_files.is_empty()
+            && self.migrated_signatures == 0
+    }
+
+    pub fn merge(&mut self, other: RecoveryReport) {
+        self.removed_temp_files.extend(other.removed_temp_files);
+        self.quarantined_files.extend(other.quarantined_files);
+        self.migrated_signatures += other.migrated_signatures;
+    }
+}
+
+/// Writes `contents` to `path` so that readers and crashes only ever observe the old or the new
+/// contents, never a partial file.
+///
+/// The contents are written to a temporary file in the same directory and fsynced, then renamed over
+/// `path`, and finally the directory is fsynced so the rename itself is durable.
+pub(crate) fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    let temp_path = write_temp_file(path, contents)?;
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    sync_parent_directory(path)
+}
+
+fn write_temp_file(path: &Path, contents: &[u8]) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}{TEMP_FILE_SUFFIX}",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4()
+    ));
+    trace!(path = %path.display(), temp_path = %temp_path.display(), "Writing temporary file");
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok(temp_path)
+}
+
+#[cfg(unix)]
+pub(crate) fn sync_parent_directory(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn sync_parent_directory(_path: &Path) -> io::Result<()> {
+    // Directories cannot be opened for syncing on this platform
+    Ok(())
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(TEMP_FILE_SUFFIX))
+}
+
+/// Removes temporary files left behind by interrupted writes in `directory`, descending into
+/// subdirectories when `recursive` is set. Returns the removed files.
+pub(crate) fn remove_temp_files(
+    directory: impl AsRef<Path>,
+    recursive: bool,
+) -> io::Result<Vec<PathBuf>> {
+    let directory = directory.as_ref();
+    let mut removed = Vec::new();
+    if !directory.is_dir() {
+        return Ok(removed);
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                removed.extend(remove_temp_files(&path, true)?);
+            }
+        } else if is_temp_file(&path) {
+            warn!(path = %path.display(), "Removing temporary file left by an interrupted write");
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// Renames a corrupt file aside so it is no longer read, and returns its new path.
+pub(crate) fn quarantine_file(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    let quarantined = path.with_file_name(format!(
+        "{}.corrupt",
+        path.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    ));
+    warn!(path = %path.display(), quarantined = %quarantined.display(), "Quarantining corrupt file");
+    std::fs::rename(path, &quarantined)?;
+    sync_parent_directory(path)?;
+    Ok(quarantined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_directory() -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let directory = temp_directory();
+        let path = directory.join("state.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_remove_temp_files() {
+        let directory = temp_directory();
+        std::fs::create_dir_all(directory.join("1")).unwrap();
+        std::fs::write(directory.join("1").join("sig"), b"sig").unwrap();
+        std::fs::write(directory.join("1").join(".sig.1234.tmp"), b"partial").unwrap();
+        std::fs::write(directory.join(".state.json.5678.tmp"), b"partial").unwrap();
+
+        assert_eq!(remove_temp_files(&directory, false).unwrap().len(), 1);
+        assert_eq!(remove_temp_files(&directory, true).unwrap().len(), 1);
+        assert!(directory.join("1").join("sig").exists());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
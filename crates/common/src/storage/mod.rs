@@ -1,10 +1,18 @@
+mod atomic_file;
 pub mod namespaces;
 pub mod signatures;
 pub mod signing_keys;
+mod sqlite;
 #[cfg(any(test, feature = "testing"))]
 pub mod test_akd_storage;
 pub mod whatsapp_akd_storage;
 
+pub use atomic_file::RecoveryReport;
+pub(crate) use atomic_file::{
+    quarantine_file, remove_temp_files, sync_parent_directory, write_atomic,
+};
+pub use sqlite::SqliteDatabase;
+
 use std::{
     fmt::{Debug, Display},
     future::Future,
@@ -12,6 +20,7 @@ use std::{
 };
 
 use akd::local_auditing::{AuditBlob, AuditBlobName};
+use quick_xml::{Reader, events::Event};
 
 pub trait AkdStorage: Clone + Display + Debug + Send + Sync {
     fn has_proof(&self, epoch: &u64) -> impl Future<Output = bool> + Send;
@@ -53,3 +62,25 @@ pub enum AkdProofNameError {
     #[error("Proof not found for epoch {0}")]
     ProofNotFound(u64),
 }
+
+/// Collects the text of every `tag` element in an XML document, such as the keys of an object listing.
+pub(crate) fn xml_element_texts(body: &[u8], tag: &[u8]) -> Result<Vec<String>, quick_xml::Error> {
+    let mut reader = Reader::from_reader(body);
+    let mut buf = Vec::new();
+    let mut texts = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.name().as_ref() == tag => {
+                if let Event::Text(e) = reader.read_event_into(&mut buf)? {
+                    texts.push(String::from_utf8_lossy(e.as_ref()).to_string());
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(texts)
+}
@@ -1,22 +1,46 @@
+use fs4::fs_std::FileExt;
+use sha2::{Digest, Sha256};
 use tracing::{debug, instrument, trace};
 
 use crate::{
-    NamespaceInfo,
-    storage::namespaces::{
-        NamespaceRepository, NamespaceRepositoryError, NamespaceRepositoryInitializationError,
-        NamespaceRepositoryPersistenceError,
+    NamespaceInfo, StatusTransition,
+    storage::{
+        RecoveryReport,
+        namespaces::{
+            NamespaceRepository, NamespaceRepositoryError, NamespaceRepositoryInitializationError,
+            NamespaceRepositoryPersistenceError, first_version, next_version, swap_version,
+        },
+        remove_temp_files, write_atomic,
     },
 };
 use std::{
     collections::HashMap,
     fmt::Debug,
-    sync::{Arc, RwLock},
+    fs::{File, OpenOptions},
+    io::ErrorKind,
+    sync::{Arc, Mutex, RwLock},
 };
 
+/// Digest of a file's contents, used to notice when another process rewrote it. Modification times
+/// are too coarse for this, since two writes within the same clock tick can leave a file with the
+/// same time and length.
+type FileDigest = [u8; 32];
+
+/// Namespace repository backed by JSON files in a directory.
+///
+/// The files are cached in memory and re-parsed whenever their contents change, so several
+/// processes (e.g. the auditor and the web server) can share them. Writes hold
+/// an exclusive lock on a sibling lock file from reload to persist, so concurrent writers in
+/// different processes never lose each other's updates.
 #[derive(Clone, Debug)]
 pub struct FileNamespaceRepository {
     file_path: String,
+    lock_path: String,
     namespaces: Arc<RwLock<HashMap<String, NamespaceInfo>>>,
+    namespaces_loaded: Arc<Mutex<Option<FileDigest>>>,
+    status_history_path: String,
+    status_history: Arc<RwLock<HashMap<String, Vec<StatusTransition>>>>,
+    status_history_loaded: Arc<Mutex<Option<FileDigest>>>,
 }
 
 impl FileNamespaceRepository {
@@ -24,41 +48,196 @@ impl FileNamespaceRepository {
         format!("{directory}/namespace_state.json")
     }
 
-    pub fn new(directory_path: &str) -> Self {
+    pub fn status_history_path(directory: &str) -> String {
+        format!("{directory}/namespace_status_history.json")
+    }
+
+    pub fn lock_path(directory: &str) -> String {
+        format!("{directory}/namespace_state.lock")
+    }
+
+    pub fn new(directory_path: &str) -> Result<Self, NamespaceRepositoryInitializationError> {
         // Create the directory if it doesn't exist
-        std::fs::create_dir_all(directory_path).expect("Failed to create namespace directory");
+        std::fs::create_dir_all(directory_path).map_err(|e| {
+            NamespaceRepositoryInitializationError(format!(
+                "Failed to create namespace directory: {e}"
+            ))
+        })?;
 
         // Load existing namespaces from file, if it exists
         let file_path = Self::file_path(directory_path);
-        let namespaces = if std::path::Path::new(&file_path).exists() {
-            Self::load_file(&file_path).expect("Failed to load namespaces from file")
-        } else {
-            HashMap::new()
+        let (namespaces, namespaces_loaded) = match Self::read_file(&file_path)? {
+            Some((content, digest)) => (Self::parse_file(&content)?, Some(digest)),
+            None => (HashMap::new(), None),
         };
 
-        Self {
+        let status_history_path = Self::status_history_path(directory_path);
+        let (status_history, status_history_loaded) = match Self::read_file(&status_history_path)? {
+            Some((content, digest)) => (Self::parse_file(&content)?, Some(digest)),
+            None => (HashMap::new(), None),
+        };
+
+        Ok(Self {
             file_path,
+            lock_path: Self::lock_path(directory_path),
             namespaces: Arc::new(RwLock::new(namespaces)),
+            namespaces_loaded: Arc::new(Mutex::new(namespaces_loaded)),
+            status_history_path,
+            status_history: Arc::new(RwLock::new(status_history)),
+            status_history_loaded: Arc::new(Mutex::new(status_history_loaded)),
+        })
+    }
+
+    /// Startup recovery for the namespace state file.
+    ///
+    /// Removes temporary files left by an interrupted write and checks that the state and status
+    /// history files can be parsed. A corrupt file is reported as an error rather than discarded,
+    /// since it records audit progress and error states that must not be silently reset.
+    pub fn recover(
+        directory_path: &str,
+    ) -> Result<RecoveryReport, NamespaceRepositoryInitializationError> {
+        let removed_temp_files = remove_temp_files(directory_path, false).map_err(|e| {
+            NamespaceRepositoryInitializationError(format!("Failed to remove temporary files: {e}"))
+        })?;
+
+        let file_path = Self::file_path(directory_path);
+        if std::path::Path::new(&file_path).exists() {
+            Self::load_file::<NamespaceInfo>(&file_path).map_err(|e| {
+                NamespaceRepositoryInitializationError(format!(
+                    "Namespace state file {file_path} is corrupt and must be restored from backup: {}",
+                    e.0
+                ))
+            })?;
+        }
+
+        let status_history_path = Self::status_history_path(directory_path);
+        if std::path::Path::new(&status_history_path).exists() {
+            Self::load_file::<Vec<StatusTransition>>(&status_history_path).map_err(|e| {
+                NamespaceRepositoryInitializationError(format!(
+                    "Namespace status history file {status_history_path} is corrupt and must be restored from backup: {}",
+                    e.0
+                ))
+            })?;
         }
+
+        Ok(RecoveryReport {
+            removed_temp_files,
+            ..Default::default()
+        })
     }
 
-    fn load_file(
+    /// Reads a file and the digest of its contents, returning `None` if it does not exist
+    fn read_file(
         file_path: &str,
-    ) -> Result<HashMap<String, NamespaceInfo>, NamespaceRepositoryInitializationError> {
-        // Read file contents
-        let file_content = std::fs::read_to_string(file_path).map_err(|e| {
-            NamespaceRepositoryInitializationError(format!("Failed to read file: {e}"))
+    ) -> Result<Option<(String, FileDigest)>, NamespaceRepositoryInitializationError> {
+        match std::fs::read_to_string(file_path) {
+            Ok(content) => {
+                debug!("Loaded namespace file content");
+                let digest = Sha256::digest(content.as_bytes()).into();
+                Ok(Some((content, digest)))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(NamespaceRepositoryInitializationError(format!(
+                "Failed to read file: {e}"
+            ))),
+        }
+    }
+
+    fn parse_file<T: serde::de::DeserializeOwned>(
+        content: &str,
+    ) -> Result<HashMap<String, T>, NamespaceRepositoryInitializationError> {
+        // Parse file contents into per-namespace entries
+        let namespaces: HashMap<String, T> = serde_json::from_str(content).map_err(|e| {
+            NamespaceRepositoryInitializationError(format!("Failed to parse file: {e}"))
         })?;
-        debug!("Loaded namespace file content");
-        // Parse file contents into NamespaceInfo entries
-        let namespaces: HashMap<String, NamespaceInfo> = serde_json::from_str(&file_content)
-            .map_err(|e| {
-                NamespaceRepositoryInitializationError(format!("Failed to parse file: {e}"))
-            })?;
         debug!("Parsed {} namespaces from file", namespaces.len());
         Ok(namespaces)
     }
 
+    fn load_file<T: serde::de::DeserializeOwned>(
+        file_path: &str,
+    ) -> Result<HashMap<String, T>, NamespaceRepositoryInitializationError> {
+        match Self::read_file(file_path)? {
+            Some((content, _)) => Self::parse_file(&content),
+            None => Err(NamespaceRepositoryInitializationError(format!(
+                "Failed to read file: {file_path} does not exist"
+            ))),
+        }
+    }
+
+    /// Reloads `file_path` into `cache` before a read if its contents changed since it was last
+    /// loaded. The file is always read, but only parsed when it changed.
+    fn refresh<T: serde::de::DeserializeOwned>(
+        file_path: &str,
+        loaded: &Mutex<Option<FileDigest>>,
+        cache: &RwLock<HashMap<String, T>>,
+    ) -> Result<(), NamespaceRepositoryPersistenceError> {
+        let mut loaded = loaded.lock().expect("File digest lock poisoned");
+        let Some((content, digest)) =
+            Self::read_file(file_path).map_err(|e| NamespaceRepositoryPersistenceError(e.0))?
+        else {
+            return Ok(());
+        };
+        if *loaded == Some(digest) {
+            return Ok(());
+        }
+        debug!(file_path, "File changed on disk, reloading");
+        let reloaded =
+            Self::parse_file(&content).map_err(|e| NamespaceRepositoryPersistenceError(e.0))?;
+        *cache.write().expect("Namespace cache lock poisoned") = reloaded;
+        *loaded = Some(digest);
+        Ok(())
+    }
+
+    /// Takes the exclusive inter-process lock on the repository's files. Hold it from the reload
+    /// before a write until the write is persisted. The lock is released when the returned file is
+    /// dropped.
+    fn lock_files(&self) -> Result<File, NamespaceRepositoryPersistenceError> {
+        let lock_file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock_path)
+            .map_err(|e| {
+                NamespaceRepositoryPersistenceError(format!("Failed to open lock file: {e}"))
+            })?;
+        lock_file.lock_exclusive().map_err(|e| {
+            NamespaceRepositoryPersistenceError(format!("Failed to lock namespace files: {e}"))
+        })?;
+        trace!(lock_path = self.lock_path, "Locked namespace files");
+        Ok(lock_file)
+    }
+
+    /// Re-reads `file_path` into `locked` before a write, so that writes made by another process
+    /// sharing the file are neither overwritten nor missed by version checks.
+    fn reload<T: serde::de::DeserializeOwned>(
+        file_path: &str,
+        locked: &mut HashMap<String, T>,
+    ) -> Result<(), NamespaceRepositoryPersistenceError> {
+        if std::path::Path::new(file_path).exists() {
+            *locked =
+                Self::load_file(file_path).map_err(|e| NamespaceRepositoryPersistenceError(e.0))?;
+        }
+        Ok(())
+    }
+
+    fn persist_status_history(
+        &self,
+        locked_status_history: &HashMap<String, Vec<StatusTransition>>,
+    ) -> Result<(), NamespaceRepositoryPersistenceError> {
+        trace!(
+            "Persisting namespace status history to file: {}",
+            self.status_history_path
+        );
+        let serialized = serde_json::to_string(locked_status_history).map_err(|e| {
+            NamespaceRepositoryPersistenceError(format!("Failed to serialize status history: {e}"))
+        })?;
+        write_atomic(&self.status_history_path, serialized.as_bytes()).map_err(|e| {
+            NamespaceRepositoryPersistenceError(format!("Failed to write to file: {e}"))
+        })?;
+        Ok(())
+    }
+
     fn persist(
         &self,
         locked_namespaces: &HashMap<String, NamespaceInfo>,
@@ -68,7 +247,7 @@ impl FileNamespaceRepository {
             NamespaceRepositoryPersistenceError(format!("Failed to serialize namespaces: {e}"))
         })?;
         trace!("Serialized namespaces: {}", serialized);
-        std::fs::write(&self.file_path, serialized).map_err(|e| {
+        write_atomic(&self.file_path, serialized.as_bytes()).map_err(|e| {
             NamespaceRepositoryPersistenceError(format!("Failed to write to file: {e}"))
         })?;
         debug!(
@@ -85,12 +264,14 @@ impl NamespaceRepository for FileNamespaceRepository {
         &self,
         name: &str,
     ) -> Result<Option<NamespaceInfo>, NamespaceRepositoryError> {
+        Self::refresh(&self.file_path, &self.namespaces_loaded, &self.namespaces)?;
         let namespaces = self.namespaces.read().expect("Namespaces lock poisoned");
         Ok(namespaces.get(name).cloned())
     }
 
     #[instrument(level = "debug", skip(self))]
     async fn list_namespaces(&self) -> Result<Vec<NamespaceInfo>, NamespaceRepositoryError> {
+        Self::refresh(&self.file_path, &self.namespaces_loaded, &self.namespaces)?;
         let namespaces = self.namespaces.read().expect("Namespaces lock poisoned");
         Ok(namespaces.values().cloned().collect())
     }
@@ -98,6 +279,9 @@ impl NamespaceRepository for FileNamespaceRepository {
     #[instrument(level = "info", skip(self))]
     async fn add_namespace(&mut self, info: NamespaceInfo) -> Result<(), NamespaceRepositoryError> {
         let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+        let _lock = self.lock_files()?;
+        Self::reload(&self.file_path, &mut namespaces)?;
+        let info = first_version(namespaces.get(&info.name), info)?;
         namespaces.insert(info.name.clone(), info);
         self.persist(&namespaces)?;
         Ok(())
@@ -109,7 +293,10 @@ impl NamespaceRepository for FileNamespaceRepository {
         info: NamespaceInfo,
     ) -> Result<(), NamespaceRepositoryError> {
         let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+        let _lock = self.lock_files()?;
+        Self::reload(&self.file_path, &mut namespaces)?;
         if namespaces.contains_key(&info.name) {
+            let info = next_version(namespaces.get(&info.name), info);
             namespaces.insert(info.name.clone(), info);
             self.persist(&namespaces)?;
             Ok(())
@@ -118,9 +305,25 @@ impl NamespaceRepository for FileNamespaceRepository {
         }
     }
 
+    #[instrument(level = "info", skip(self))]
+    async fn compare_and_swap_namespace(
+        &mut self,
+        info: NamespaceInfo,
+    ) -> Result<NamespaceInfo, NamespaceRepositoryError> {
+        let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+        let _lock = self.lock_files()?;
+        Self::reload(&self.file_path, &mut namespaces)?;
+        let info = swap_version(namespaces.get(&info.name), info)?;
+        namespaces.insert(info.name.clone(), info.clone());
+        self.persist(&namespaces)?;
+        Ok(info)
+    }
+
     #[instrument(level = "info", skip(self))]
     async fn remove_namespace(&mut self, name: &str) -> Result<(), NamespaceRepositoryError> {
         let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+        let _lock = self.lock_files()?;
+        Self::reload(&self.file_path, &mut namespaces)?;
         if namespaces.remove(name).is_some() {
             self.persist(&namespaces)?;
             Ok(())
@@ -130,4 +333,344 @@ impl NamespaceRepository for FileNamespaceRepository {
             ))
         }
     }
+
+    #[instrument(level = "info", skip(self))]
+    async fn record_status_transition(
+        &mut self,
+        transition: StatusTransition,
+    ) -> Result<(), NamespaceRepositoryError> {
+        let mut status_history = self
+            .status_history
+            .write()
+            .expect("Status history lock poisoned");
+        let _lock = self.lock_files()?;
+        Self::reload(&self.status_history_path, &mut status_history)?;
+        status_history
+            .entry(transition.namespace.clone())
+            .or_default()
+            .push(transition);
+        self.persist_status_history(&status_history)?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn status_history(
+        &self,
+        name: &str,
+    ) -> Result<Vec<StatusTransition>, NamespaceRepositoryError> {
+        Self::refresh(
+            &self.status_history_path,
+            &self.status_history_loaded,
+            &self.status_history,
+        )?;
+        let status_history = self
+            .status_history
+            .read()
+            .expect("Status history lock poisoned");
+        Ok(status_history.get(name).cloned().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_directory() -> String {
+        let directory = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        directory.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_corrupt_state_file_is_reported() {
+        let directory = temp_directory();
+        std::fs::create_dir_all(&directory).unwrap();
+        // Simulate a write truncated by a crash
+        std::fs::write(
+            FileNamespaceRepository::file_path(&directory),
+            "{\"test\": {",
+        )
+        .unwrap();
+        std::fs::write(format!("{directory}/.namespace_state.json.1234.tmp"), "{}").unwrap();
+
+        let error = FileNamespaceRepository::recover(&directory).unwrap_err();
+        assert!(error.to_string().contains("is corrupt"));
+        assert!(FileNamespaceRepository::new(&directory).is_err());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_recover_removes_temp_files() {
+        let directory = temp_directory();
+        FileNamespaceRepository::new(&directory).unwrap();
+        std::fs::write(format!("{directory}/.namespace_state.json.1234.tmp"), "{").unwrap();
+
+        let report = FileNamespaceRepository::recover(&directory).unwrap();
+        assert_eq!(report.removed_temp_files.len(), 1);
+        assert!(report.quarantined_files.is_empty());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_history_survives_reopen() {
+        let directory = temp_directory();
+        let info = NamespaceInfo {
+            configuration: crate::akd_configurations::AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: crate::Epoch::new(1),
+            status: crate::NamespaceStatus::Online,
+            version: 0,
+        };
+        let mut repository = FileNamespaceRepository::new(&directory).unwrap();
+        repository.add_namespace(info.clone()).await.unwrap();
+        repository
+            .record_status_transition(StatusTransition::added(&info, crate::StatusActor::Config))
+            .await
+            .unwrap();
+        repository
+            .record_status_transition(StatusTransition::new(
+                &info,
+                crate::NamespaceStatus::SignatureLost,
+                Some(crate::Epoch::new(3)),
+                Some("signature not found".to_string()),
+                crate::StatusActor::Auditor,
+            ))
+            .await
+            .unwrap();
+
+        let reopened = FileNamespaceRepository::new(&directory).unwrap();
+        let history = reopened.status_history("test").await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].previous_status, None);
+        assert_eq!(history[1].new_status, crate::NamespaceStatus::SignatureLost);
+        assert!(reopened.status_history("other").await.unwrap().is_empty());
+        assert!(FileNamespaceRepository::recover(&directory).is_ok());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_add_fails_when_other_instance_added_namespace() {
+        let directory = temp_directory();
+        let info = NamespaceInfo {
+            configuration: crate::akd_configurations::AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: crate::Epoch::new(1),
+            status: crate::NamespaceStatus::Online,
+            version: 0,
+        };
+        let mut auditor = FileNamespaceRepository::new(&directory).unwrap();
+        // A second process sharing the file, which has not read the namespace yet
+        let mut other = FileNamespaceRepository::new(&directory).unwrap();
+        auditor.add_namespace(info.clone()).await.unwrap();
+
+        let error = other
+            .add_namespace(info.update_status(crate::NamespaceStatus::Disabled))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            NamespaceRepositoryError::NamespaceAlreadyExists(name) if name == "test"
+        ));
+        let stored = other.get_namespace_info("test").await.unwrap().unwrap();
+        assert_eq!(stored.status, crate::NamespaceStatus::Online);
+        assert_eq!(stored.version, 1);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_swap_sees_writes_from_other_instances() {
+        let directory = temp_directory();
+        let info = NamespaceInfo {
+            configuration: crate::akd_configurations::AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: crate::Epoch::new(1),
+            status: crate::NamespaceStatus::Online,
+            version: 0,
+        };
+        let mut auditor = FileNamespaceRepository::new(&directory).unwrap();
+        auditor.add_namespace(info).await.unwrap();
+        // A second process sharing the file
+        let mut other = FileNamespaceRepository::new(&directory).unwrap();
+        let read = auditor.get_namespace_info("test").await.unwrap().unwrap();
+
+        other
+            .compare_and_swap_namespace(read.update_status(crate::NamespaceStatus::Disabled))
+            .await
+            .unwrap();
+        let error = auditor
+            .compare_and_swap_namespace(read.update_last_verified_epoch(crate::Epoch::new(2)))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            NamespaceRepositoryError::VersionConflict {
+                expected: 1,
+                actual: 2,
+                ..
+            }
+        ));
+
+        // The conflict reloaded the other instance's write, so retrying from a fresh read keeps it
+        let fresh = auditor.get_namespace_info("test").await.unwrap().unwrap();
+        auditor
+            .compare_and_swap_namespace(fresh.update_last_verified_epoch(crate::Epoch::new(2)))
+            .await
+            .unwrap();
+        let stored = FileNamespaceRepository::new(&directory)
+            .unwrap()
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, crate::NamespaceStatus::Disabled);
+        assert_eq!(stored.last_verified_epoch, Some(crate::Epoch::new(2)));
+        assert_eq!(stored.version, 3);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_compare_and_swap_from_other_instances_loses_no_update() {
+        const WRITERS: u64 = 4;
+        const INCREMENTS: u64 = 20;
+        let directory = temp_directory();
+        let info = NamespaceInfo {
+            configuration: crate::akd_configurations::AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: crate::Epoch::new(1),
+            status: crate::NamespaceStatus::Online,
+            version: 0,
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(
+                FileNamespaceRepository::new(&directory)
+                    .unwrap()
+                    .add_namespace(info),
+            )
+            .unwrap();
+
+        // Each thread stands in for a separate process with its own instance and in-process locks
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|_| {
+                let directory = directory.clone();
+                std::thread::spawn(move || {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .build()
+                        .unwrap();
+                    let mut repository = FileNamespaceRepository::new(&directory).unwrap();
+                    runtime.block_on(async {
+                        for _ in 0..INCREMENTS {
+                            loop {
+                                let current = repository
+                                    .get_namespace_info("test")
+                                    .await
+                                    .unwrap()
+                                    .unwrap();
+                                let next = current
+                                    .last_verified_epoch
+                                    .map_or(1, |epoch| *epoch.value() + 1);
+                                match repository
+                                    .compare_and_swap_namespace(
+                                        current.update_last_verified_epoch(crate::Epoch::new(next)),
+                                    )
+                                    .await
+                                {
+                                    Ok(_) => break,
+                                    Err(NamespaceRepositoryError::VersionConflict { .. }) => {}
+                                    Err(e) => panic!("Unexpected error: {e}"),
+                                }
+                            }
+                        }
+                    });
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let stored = runtime
+            .block_on(
+                FileNamespaceRepository::new(&directory)
+                    .unwrap()
+                    .get_namespace_info("test"),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored.last_verified_epoch,
+            Some(crate::Epoch::new(WRITERS * INCREMENTS))
+        );
+        assert_eq!(stored.version, WRITERS * INCREMENTS + 1);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reads_see_writes_from_other_instances() {
+        let directory = temp_directory();
+        let info = NamespaceInfo {
+            configuration: crate::akd_configurations::AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: crate::Epoch::new(1),
+            status: crate::NamespaceStatus::Online,
+            version: 0,
+        };
+        // The web server opens the repository before the auditor has written anything
+        let web = FileNamespaceRepository::new(&directory).unwrap();
+        assert!(web.list_namespaces().await.unwrap().is_empty());
+
+        let mut auditor = FileNamespaceRepository::new(&directory).unwrap();
+        auditor.add_namespace(info.clone()).await.unwrap();
+        assert_eq!(web.list_namespaces().await.unwrap().len(), 1);
+
+        auditor
+            .update_namespace(info.update_last_verified_epoch(crate::Epoch::new(7)))
+            .await
+            .unwrap();
+        auditor
+            .record_status_transition(StatusTransition::added(&info, crate::StatusActor::Config))
+            .await
+            .unwrap();
+        let seen = web.get_namespace_info("test").await.unwrap().unwrap();
+        assert_eq!(seen.last_verified_epoch, Some(crate::Epoch::new(7)));
+        assert_eq!(web.status_history("test").await.unwrap().len(), 1);
+
+        // A rewrite of the same length within the same clock tick leaves the modification time and
+        // length unchanged, but is still picked up
+        let file_path = FileNamespaceRepository::file_path(&directory);
+        let modified = std::fs::metadata(&file_path).unwrap().modified().unwrap();
+        let length = std::fs::metadata(&file_path).unwrap().len();
+        auditor
+            .update_namespace(seen.update_last_verified_epoch(crate::Epoch::new(8)))
+            .await
+            .unwrap();
+        File::options()
+            .write(true)
+            .open(&file_path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(std::fs::metadata(&file_path).unwrap().len(), length);
+        let seen = web.get_namespace_info("test").await.unwrap().unwrap();
+        assert_eq!(seen.last_verified_epoch, Some(crate::Epoch::new(8)));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
 }
@@ -1,6 +1,8 @@
 use crate::{
-    NamespaceInfo,
-    storage::namespaces::{NamespaceRepository, NamespaceRepositoryError},
+    NamespaceInfo, StatusTransition,
+    storage::namespaces::{
+        NamespaceRepository, NamespaceRepositoryError, first_version, next_version, swap_version,
+    },
 };
 use std::{
     collections::HashMap,
@@ -13,6 +15,7 @@ type Result<T> = std::result::Result<T, NamespaceRepositoryError>;
 #[derive(Clone, Debug)]
 pub struct InMemoryNamespaceRepository {
     namespaces: Arc<RwLock<HashMap<String, NamespaceInfo>>>,
+    status_history: Arc<RwLock<HashMap<String, Vec<StatusTransition>>>>,
 }
 
 impl Default for InMemoryNamespaceRepository {
@@ -25,6 +28,7 @@ impl InMemoryNamespaceRepository {
     pub fn new() -> Self {
         Self {
             namespaces: Arc::new(RwLock::new(HashMap::new())),
+            status_history: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -53,6 +57,7 @@ impl NamespaceRepository for InMemoryNamespaceRepository {
         let namespaces = self.namespaces.clone();
         async move {
             let mut namespaces = namespaces.write().expect("Namespaces lock poisoned");
+            let info = first_version(namespaces.get(&info.name), info)?;
             namespaces.insert(info.name.clone(), info);
             Ok(())
         }
@@ -63,6 +68,7 @@ impl NamespaceRepository for InMemoryNamespaceRepository {
         async move {
             let mut namespaces = namespaces.write().expect("Namespaces lock poisoned");
             if namespaces.contains_key(&info.name) {
+                let info = next_version(namespaces.get(&info.name), info);
                 namespaces.insert(info.name.clone(), info);
                 Ok(())
             } else {
@@ -71,6 +77,19 @@ impl NamespaceRepository for InMemoryNamespaceRepository {
         }
     }
 
+    fn compare_and_swap_namespace(
+        &mut self,
+        info: NamespaceInfo,
+    ) -> impl Future<Output = Result<NamespaceInfo>> + Send {
+        let namespaces = self.namespaces.clone();
+        async move {
+            let mut namespaces = namespaces.write().expect("Namespaces lock poisoned");
+            let info = swap_version(namespaces.get(&info.name), info)?;
+            namespaces.insert(info.name.clone(), info.clone());
+            Ok(info)
+        }
+    }
+
     fn remove_namespace(&mut self, name: &str) -> impl Future<Output = Result<()>> + Send {
         let namespaces = self.namespaces.clone();
         async move {
@@ -84,4 +103,32 @@ impl NamespaceRepository for InMemoryNamespaceRepository {
             }
         }
     }
+
+    fn record_status_transition(
+        &mut self,
+        transition: StatusTransition,
+    ) -> impl Future<Output = Result<()>> + Send {
+        let status_history = self.status_history.clone();
+        async move {
+            let mut status_history = status_history
+                .write()
+                .expect("Status history lock poisoned");
+            status_history
+                .entry(transition.namespace.clone())
+                .or_default()
+                .push(transition);
+            Ok(())
+        }
+    }
+
+    fn status_history(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Vec<StatusTransition>>> + Send {
+        let status_history = self.status_history.clone();
+        async move {
+            let status_history = status_history.read().expect("Status history lock poisoned");
+            Ok(status_history.get(name).cloned().unwrap_or_default())
+        }
+    }
 }
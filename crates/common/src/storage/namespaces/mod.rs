@@ -1,19 +1,32 @@
 mod file_namespace_repository;
 mod in_memory_namespace_repository;
+mod sqlite_namespace_repository;
 
 pub use file_namespace_repository::FileNamespaceRepository;
 pub use in_memory_namespace_repository::InMemoryNamespaceRepository;
+pub use sqlite_namespace_repository::SqliteNamespaceRepository;
+pub(crate) use sqlite_namespace_repository::{parse_namespace_info, serialize_namespace_info};
 
 use std::future::Future;
 use thiserror::Error;
 
-use crate::NamespaceInfo;
+use crate::{NamespaceInfo, StatusTransition};
 use std::fmt::Debug;
 
 #[derive(Debug, Error)]
 pub enum NamespaceRepositoryError {
     #[error("Namespace not found: {0}")]
     NamespaceNotFound(String),
+    #[error("Namespace already exists: {0}")]
+    NamespaceAlreadyExists(String),
+    #[error(
+        "Namespace {name} was modified concurrently: expected version {expected}, found {actual}"
+    )]
+    VersionConflict {
+        name: String,
+        expected: u64,
+        actual: u64,
+    },
     #[error("{0}")]
     PersistenceError(#[from] NamespaceRepositoryPersistenceError),
 }
@@ -28,25 +41,92 @@ pub struct NamespaceRepositoryPersistenceError(String);
 
 type Result<T> = std::result::Result<T, NamespaceRepositoryError>;
 
+/// Checks a compare-and-swap of `info` against the stored namespace and returns `info` with the
+/// version it should be stored under.
+pub(crate) fn swap_version(
+    stored: Option<&NamespaceInfo>,
+    info: NamespaceInfo,
+) -> Result<NamespaceInfo> {
+    let Some(stored) = stored else {
+        return Err(NamespaceRepositoryError::NamespaceNotFound(info.name));
+    };
+    if stored.version != info.version {
+        return Err(NamespaceRepositoryError::VersionConflict {
+            name: info.name,
+            expected: info.version,
+            actual: stored.version,
+        });
+    }
+    Ok(NamespaceInfo {
+        version: stored.version + 1,
+        ..info
+    })
+}
+
+/// Returns `info` as a new namespace at version 1, unless the namespace is already stored
+pub(crate) fn first_version(
+    stored: Option<&NamespaceInfo>,
+    info: NamespaceInfo,
+) -> Result<NamespaceInfo> {
+    if stored.is_some() {
+        return Err(NamespaceRepositoryError::NamespaceAlreadyExists(info.name));
+    }
+    Ok(NamespaceInfo { version: 1, ..info })
+}
+
+/// Returns `info` with the version that follows the stored namespace's, for unconditional writes
+pub(crate) fn next_version(stored: Option<&NamespaceInfo>, info: NamespaceInfo) -> NamespaceInfo {
+    NamespaceInfo {
+        version: stored.map_or(0, |stored| stored.version) + 1,
+        ..info
+    }
+}
+
 pub trait NamespaceRepository: Clone + Send + Sync {
     fn get_namespace_info(
         &self,
         name: &str,
     ) -> impl Future<Output = Result<Option<NamespaceInfo>>> + Send;
     fn list_namespaces(&self) -> impl Future<Output = Result<Vec<NamespaceInfo>>> + Send;
+    /// Stores a new namespace at version 1. Fails with
+    /// [`NamespaceRepositoryError::NamespaceAlreadyExists`] if the namespace exists, so of two
+    /// writers adding the same namespace only one succeeds.
     fn add_namespace(&mut self, info: NamespaceInfo) -> impl Future<Output = Result<()>> + Send;
+    /// Stores `info` regardless of the stored version, advancing the version. Prefer
+    /// [`compare_and_swap_namespace`](Self::compare_and_swap_namespace) when `info` was derived
+    /// from a previous read.
     fn update_namespace(&mut self, info: NamespaceInfo) -> impl Future<Output = Result<()>> + Send;
+    /// Stores `info` only if the stored version still equals `info.version`, and returns the
+    /// stored info with its advanced version. Fails with
+    /// [`NamespaceRepositoryError::VersionConflict`] if another writer updated the namespace since
+    /// `info` was read.
+    fn compare_and_swap_namespace(
+        &mut self,
+        info: NamespaceInfo,
+    ) -> impl Future<Output = Result<NamespaceInfo>> + Send;
     fn remove_namespace(&mut self, name: &str) -> impl Future<Output = Result<()>> + Send;
+    /// Appends a transition to the namespace's status history. History is never rewritten and is
+    /// kept when the namespace is removed.
+    fn record_status_transition(
+        &mut self,
+        transition: StatusTransition,
+    ) -> impl Future<Output = Result<()>> + Send;
+    /// Returns the namespace's status transitions, oldest first
+    fn status_history(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Vec<StatusTransition>>> + Send;
 }
 
 /// Enum wrapper to support different namespace repository implementations
 ///
 /// This enum allows applications to work with different storage backends
-/// for namespace information (File-based or InMemory) based on configuration.
+/// for namespace information (File-based, SQLite or InMemory) based on configuration.
 #[derive(Clone, Debug)]
 pub enum NamespaceStorage {
     File(FileNamespaceRepository),
     InMemory(InMemoryNamespaceRepository),
+    Sqlite(SqliteNamespaceRepository),
 }
 
 impl NamespaceRepository for NamespaceStorage {
@@ -54,6 +134,7 @@ impl NamespaceRepository for NamespaceStorage {
         match self {
             NamespaceStorage::File(repo) => repo.get_namespace_info(name).await,
             NamespaceStorage::InMemory(repo) => repo.get_namespace_info(name).await,
+            NamespaceStorage::Sqlite(repo) => repo.get_namespace_info(name).await,
         }
     }
 
@@ -61,6 +142,7 @@ impl NamespaceRepository for NamespaceStorage {
         match self {
             NamespaceStorage::File(repo) => repo.list_namespaces().await,
             NamespaceStorage::InMemory(repo) => repo.list_namespaces().await,
+            NamespaceStorage::Sqlite(repo) => repo.list_namespaces().await,
         }
     }
 
@@ -68,6 +150,7 @@ impl NamespaceRepository for NamespaceStorage {
         match self {
             NamespaceStorage::File(repo) => repo.add_namespace(info).await,
             NamespaceStorage::InMemory(repo) => repo.add_namespace(info).await,
+            NamespaceStorage::Sqlite(repo) => repo.add_namespace(info).await,
         }
     }
 
@@ -75,6 +158,18 @@ impl NamespaceRepository for NamespaceStorage {
         match self {
             NamespaceStorage::File(repo) => repo.update_namespace(info).await,
             NamespaceStorage::InMemory(repo) => repo.update_namespace(info).await,
+            NamespaceStorage::Sqlite(repo) => repo.update_namespace(info).await,
+        }
+    }
+
+    async fn compare_and_swap_namespace(
+        &mut self,
+        info: crate::NamespaceInfo,
+    ) -> Result<crate::NamespaceInfo> {
+        match self {
+            NamespaceStorage::File(repo) => repo.compare_and_swap_namespace(info).await,
+            NamespaceStorage::InMemory(repo) => repo.compare_and_swap_namespace(info).await,
+            NamespaceStorage::Sqlite(repo) => repo.compare_and_swap_namespace(info).await,
         }
     }
 
@@ -82,6 +177,23 @@ impl NamespaceRepository for NamespaceStorage {
         match self {
             NamespaceStorage::File(repo) => repo.remove_namespace(name).await,
             NamespaceStorage::InMemory(repo) => repo.remove_namespace(name).await,
+            NamespaceStorage::Sqlite(repo) => repo.remove_namespace(name).await,
+        }
+    }
+
+    async fn record_status_transition(&mut self, transition: StatusTransition) -> Result<()> {
+        match self {
+            NamespaceStorage::File(repo) => repo.record_status_transition(transition).await,
+            NamespaceStorage::InMemory(repo) => repo.record_status_transition(transition).await,
+            NamespaceStorage::Sqlite(repo) => repo.record_status_transition(transition).await,
+        }
+    }
+
+    async fn status_history(&self, name: &str) -> Result<Vec<StatusTransition>> {
+        match self {
+            NamespaceStorage::File(repo) => repo.status_history(name).await,
+            NamespaceStorage::InMemory(repo) => repo.status_history(name).await,
+            NamespaceStorage::Sqlite(repo) => repo.status_history(name).await,
         }
     }
 }
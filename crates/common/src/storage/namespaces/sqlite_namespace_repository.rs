@@ -0,0 +1,307 @@
+use rusqlite::{OptionalExtension, params};
+use tracing::instrument;
+
+use crate::{
+    NamespaceInfo, StatusTransition,
+    storage::{
+        namespaces::{
+            NamespaceRepository, NamespaceRepositoryError, NamespaceRepositoryPersistenceError,
+        },
+        sqlite::SqliteDatabase,
+    },
+};
+
+type Result<T> = std::result::Result<T, NamespaceRepositoryError>;
+
+/// Namespace repository backed by the shared SQLite database.
+///
+/// Namespace info is stored as JSON, keyed by namespace name. Versions are advanced inside the
+/// same statement that writes the info, so concurrent writers (including other processes sharing
+/// the database) are serialized by SQLite. Status transitions are appended as
+/// JSON rows and returned in insertion order.
+#[derive(Clone, Debug)]
+pub struct SqliteNamespaceRepository {
+    database: SqliteDatabase,
+}
+
+impl SqliteNamespaceRepository {
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+}
+
+fn persistence_error(context: &str, e: impl std::fmt::Display) -> NamespaceRepositoryError {
+    NamespaceRepositoryPersistenceError(format!("{context}: {e}")).into()
+}
+
+pub(crate) fn parse_namespace_info(info: &str) -> Result<NamespaceInfo> {
+    serde_json::from_str(info).map_err(|e| persistence_error("Failed to parse namespace", e))
+}
+
+pub(crate) fn serialize_namespace_info(info: &NamespaceInfo) -> Result<String> {
+    serde_json::to_string(info).map_err(|e| persistence_error("Failed to serialize namespace", e))
+}
+
+impl NamespaceRepository for SqliteNamespaceRepository {
+    #[instrument(level = "debug", skip(self))]
+    async fn get_namespace_info(&self, name: &str) -> Result<Option<NamespaceInfo>> {
+        let info: Option<String> = self
+            .database
+            .connection()
+            .query_row(
+                "SELECT info FROM namespaces WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| persistence_error("Failed to read namespace", e))?;
+        info.as_deref().map(parse_namespace_info).transpose()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn list_namespaces(&self) -> Result<Vec<NamespaceInfo>> {
+        let connection = self.database.connection();
+        let mut statement = connection
+            .prepare("SELECT info FROM namespaces ORDER BY name")
+            .map_err(|e| persistence_error("Failed to list namespaces", e))?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| persistence_error("Failed to list namespaces", e))?;
+
+        let mut namespaces = Vec::new();
+        for info in rows {
+            let info = info.map_err(|e| persistence_error("Failed to list namespaces", e))?;
+            namespaces.push(parse_namespace_info(&info)?);
+        }
+        Ok(namespaces)
+    }
+
+    #[instrument(level = "info", skip(self))]
+    async fn add_namespace(&mut self, info: NamespaceInfo) -> Result<()> {
+        let info = NamespaceInfo { version: 1, ..info };
+        let serialized = serialize_namespace_info(&info)?;
+        let inserted = self
+            .database
+            .connection()
+            .execute(
+                "INSERT INTO namespaces (name, info) VALUES (?1, ?2) ON CONFLICT (name) DO NOTHING",
+                params![info.name, serialized],
+            )
+            .map_err(|e| persistence_error("Failed to add namespace", e))?;
+        if inserted == 0 {
+            return Err(NamespaceRepositoryError::NamespaceAlreadyExists(info.name));
+        }
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip(self))]
+    async fn update_namespace(&mut self, info: NamespaceInfo) -> Result<()> {
+        let serialized = serialize_namespace_info(&info)?;
+        let updated = self
+            .database
+            .connection()
+            .execute(
+                "UPDATE namespaces SET info = json_set(?2, '$.version',
+                     coalesce(json_extract(info, '$.version'), 0) + 1)
+                 WHERE name = ?1",
+                params![info.name, serialized],
+            )
+            .map_err(|e| persistence_error("Failed to update namespace", e))?;
+        if updated == 0 {
+            return Err(NamespaceRepositoryError::NamespaceNotFound(info.name));
+        }
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip(self))]
+    async fn compare_and_swap_namespace(&mut self, info: NamespaceInfo) -> Result<NamespaceInfo> {
+        let expected = i64::try_from(info.version)
+            .map_err(|e| persistence_error("Namespace version out of range", e))?;
+        let swapped = NamespaceInfo {
+            version: info.version + 1,
+            ..info
+        };
+        let serialized = serialize_namespace_info(&swapped)?;
+        let connection = self.database.connection();
+        let updated = connection
+            .execute(
+                "UPDATE namespaces SET info = ?2
+                 WHERE name = ?1 AND coalesce(json_extract(info, '$.version'), 0) = ?3",
+                params![swapped.name, serialized, expected],
+            )
+            .map_err(|e| persistence_error("Failed to update namespace", e))?;
+        if updated == 1 {
+            return Ok(swapped);
+        }
+
+        // Nothing was written: report why
+        let actual: Option<i64> = connection
+            .query_row(
+                "SELECT coalesce(json_extract(info, '$.version'), 0) FROM namespaces WHERE name = ?1",
+                params![swapped.name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| persistence_error("Failed to read namespace", e))?;
+        match actual {
+            Some(actual) => Err(NamespaceRepositoryError::VersionConflict {
+                name: swapped.name,
+                expected: info.version,
+                actual: actual.try_into().unwrap_or_default(),
+            }),
+            None => Err(NamespaceRepositoryError::NamespaceNotFound(swapped.name)),
+        }
+    }
+
+    #[instrument(level = "info", skip(self))]
+    async fn remove_namespace(&mut self, name: &str) -> Result<()> {
+        let removed = self
+            .database
+            .connection()
+            .execute("DELETE FROM namespaces WHERE name = ?1", params![name])
+            .map_err(|e| persistence_error("Failed to remove namespace", e))?;
+        if removed == 0 {
+            return Err(NamespaceRepositoryError::NamespaceNotFound(
+                name.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip(self))]
+    async fn record_status_transition(&mut self, transition: StatusTransition) -> Result<()> {
+        let serialized = serde_json::to_string(&transition)
+            .map_err(|e| persistence_error("Failed to serialize status transition", e))?;
+        self.database
+            .connection()
+            .execute(
+                "INSERT INTO namespace_status_history (namespace, transition) VALUES (?1, ?2)",
+                params![transition.namespace, serialized],
+            )
+            .map_err(|e| persistence_error("Failed to record status transition", e))?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn status_history(&self, name: &str) -> Result<Vec<StatusTransition>> {
+        let connection = self.database.connection();
+        let mut statement = connection
+            .prepare(
+                "SELECT transition FROM namespace_status_history WHERE namespace = ?1 ORDER BY id",
+            )
+            .map_err(|e| persistence_error("Failed to read status history", e))?;
+        let rows = statement
+            .query_map(params![name], |row| row.get::<_, String>(0))
+            .map_err(|e| persistence_error("Failed to read status history", e))?;
+
+        let mut history = Vec::new();
+        for transition in rows {
+            let transition =
+                transition.map_err(|e| persistence_error("Failed to read status history", e))?;
+            history.push(
+                serde_json::from_str(&transition)
+                    .map_err(|e| persistence_error("Failed to parse status transition", e))?,
+            );
+        }
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Epoch, NamespaceStatus, akd_configurations::AkdConfiguration};
+
+    fn test_namespace() -> NamespaceInfo {
+        NamespaceInfo {
+            configuration: AkdConfiguration::BitwardenV1Configuration,
+            name: "test".to_string(),
+            log_directory: "logs".to_string(),
+            last_verified_epoch: None,
+            starting_epoch: Epoch::new(1),
+            status: NamespaceStatus::Online,
+            version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_existing_namespace_fails() {
+        let mut repository =
+            SqliteNamespaceRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        repository.add_namespace(test_namespace()).await.unwrap();
+
+        let error = repository
+            .add_namespace(test_namespace().update_status(NamespaceStatus::Disabled))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            NamespaceRepositoryError::NamespaceAlreadyExists(name) if name == "test"
+        ));
+        let stored = repository
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, NamespaceStatus::Online);
+        assert_eq!(stored.version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_swap_detects_concurrent_update() {
+        let mut repository =
+            SqliteNamespaceRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        repository.add_namespace(test_namespace()).await.unwrap();
+        let read = repository
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.version, 1);
+
+        // Another writer advances the namespace after it was read
+        repository
+            .update_namespace(read.update_status(NamespaceStatus::Disabled))
+            .await
+            .unwrap();
+
+        let error = repository
+            .compare_and_swap_namespace(read.update_last_verified_epoch(Epoch::new(2)))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            NamespaceRepositoryError::VersionConflict {
+                expected: 1,
+                actual: 2,
+                ..
+            }
+        ));
+
+        let fresh = repository
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fresh.status, NamespaceStatus::Disabled);
+        let swapped = repository
+            .compare_and_swap_namespace(fresh.update_last_verified_epoch(Epoch::new(2)))
+            .await
+            .unwrap();
+        assert_eq!(swapped.version, 3);
+        let stored = repository
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.version, swapped.version);
+        assert_eq!(stored.last_verified_epoch, Some(Epoch::new(2)));
+
+        let mut missing = test_namespace();
+        missing.name = "missing".to_string();
+        assert!(matches!(
+            repository.compare_and_swap_namespace(missing).await,
+            Err(NamespaceRepositoryError::NamespaceNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,967 @@
+use std::{
+    collections::BTreeMap,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use hmac::{Hmac, Mac};
+use reqwest::{
+    Method, Request, StatusCode,
+    header::{AUTHORIZATION, CONTENT_TYPE, IF_NONE_MATCH},
+};
+use sha2::Sha256;
+use tracing::{debug, instrument, trace};
+
+use crate::{
+    BINCODE_CONFIG, Heartbeat,
+    epoch_signature::EpochSignature,
+    storage::{
+        signatures::{
+            SignatureRepository, SignatureRepositoryError, ensure_same_digest,
+            epoch_names::{
+                LegacyEpochs, PADDED_EPOCH_PREFIX, is_legacy_epoch_name, merge_legacy_epochs,
+                padded_epoch_name, parse_epoch_name,
+            },
+            http::{Secret, UnexpectedStatusError, header_value, unexpected_status},
+        },
+        xml_element_texts,
+    },
+};
+
+/// Azure Storage REST API version used for all requests
+const AZURE_STORAGE_API_VERSION: &str = "2021-12-02";
+/// Environment variable consulted when no connection string is configured
+pub const AZURE_STORAGE_CONNECTION_STRING_ENV: &str = "AZURE_STORAGE_CONNECTION_STRING";
+
+/// Well-known account and key of the Azurite storage emulator
+const DEVELOPMENT_ACCOUNT_NAME: &str = "devstoreaccount1";
+const DEVELOPMENT_ACCOUNT_KEY: &str =
+    "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==";
+const DEVELOPMENT_BLOB_ENDPOINT: &str = "http://127.0.0.1:10000/devstoreaccount1";
+
+const HEARTBEAT_BLOB_NAME: &str = "heartbeat";
+/// Largest page of blob names requested at once
+const MAX_RESULTS_PER_PAGE: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AzureStorageError {
+    #[error("Invalid Azure connection string: {0}")]
+    InvalidConnectionString(String),
+    #[error("Azure request error: {0}")]
+    RequestError(#[from] reqwest::Error),
+    #[error("Azure returned unexpected status {status}: {body}")]
+    UnexpectedStatus { status: StatusCode, body: String },
+    #[error("Blob already exists: {0}")]
+    BlobAlreadyExists(String),
+    #[error("Failed to parse Azure listing: {0}")]
+    ListingParseError(#[from] quick_xml::Error),
+}
+
+impl UnexpectedStatusError for AzureStorageError {
+    fn unexpected_status(status: StatusCode, body: String) -> Self {
+        AzureStorageError::UnexpectedStatus { status, body }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum AzureCredential {
+    SharedKey(Secret<Vec<u8>>),
+    SharedAccessSignature(Secret<String>),
+}
+
+/// The parts of an Azure Storage connection string needed to talk to the Blob service
+#[derive(Clone, Debug)]
+pub struct AzureConnectionString {
+    account_name: String,
+    credential: AzureCredential,
+    blob_endpoint: String,
+}
+
+impl AzureConnectionString {
+    /// Parses a connection string of the form `Key1=Value1;Key2=Value2`.
+    ///
+    /// `default_account_name` is used when the connection string does not contain an `AccountName`.
+    /// Supports `AccountKey` and `SharedAccessSignature` credentials, as well as
+    /// `UseDevelopmentStorage=true` for the Azurite emulator.
+    pub fn parse(
+        connection_string: &str,
+        default_account_name: &str,
+    ) -> Result<Self, AzureStorageError> {
+        let mut account_name = None;
+        let mut account_key = None;
+        let mut sas_token = None;
+        let mut blob_endpoint = None;
+        let mut protocol = "https".to_string();
+        let mut endpoint_suffix = "core.windows.net".to_string();
+        let mut use_development_storage = false;
+
+        for part in connection_string
+            .split(';')
+            .filter(|p| !p.trim().is_empty())
+        {
+            let (key, value) = part.trim().split_once('=').ok_or_else(|| {
+                AzureStorageError::InvalidConnectionString(format!("malformed segment '{part}'"))
+            })?;
+            match key {
+                "AccountName" => account_name = Some(value.to_string()),
+                "AccountKey" => account_key = Some(value.to_string()),
+                "SharedAccessSignature" => sas_token = Some(value.to_string()),
+                "BlobEndpoint" => blob_endpoint = Some(value.trim_end_matches('/').to_string()),
+                "DefaultEndpointsProtocol" => protocol = value.to_string(),
+                "EndpointSuffix" => endpoint_suffix = value.to_string(),
+                "UseDevelopmentStorage" => use_development_storage = value == "true",
+                _ => trace!(key, "Ignoring unsupported connection string segment"),
+            }
+        }
+
+        if use_development_storage {
+            account_name.get_or_insert_with(|| DEVELOPMENT_ACCOUNT_NAME.to_string());
+            account_key.get_or_insert_with(|| DEVELOPMENT_ACCOUNT_KEY.to_string());
+            blob_endpoint.get_or_insert_with(|| DEVELOPMENT_BLOB_ENDPOINT.to_string());
+        }
+
+        let account_name = account_name.unwrap_or_else(|| default_account_name.to_string());
+        if account_name.is_empty() {
+            return Err(AzureStorageError::InvalidConnectionString(
+                "missing AccountName".to_string(),
+            ));
+        }
+
+        let credential = match (account_key, sas_token) {
+            (Some(key), _) => {
+                AzureCredential::SharedKey(Secret(BASE64.decode(key).map_err(|e| {
+                    AzureStorageError::InvalidConnectionString(format!(
+                        "AccountKey is not base64: {e}"
+                    ))
+                })?))
+            }
+            (None, Some(token)) => AzureCredential::SharedAccessSignature(Secret(
+                token.trim_start_matches('?').to_string(),
+            )),
+            (None, None) => {
+                return Err(AzureStorageError::InvalidConnectionString(
+                    "missing AccountKey or SharedAccessSignature".to_string(),
+                ));
+            }
+        };
+
+        let blob_endpoint = blob_endpoint
+            .unwrap_or_else(|| format!("{protocol}://{account_name}.blob.{endpoint_suffix}"));
+
+        Ok(Self {
+            account_name,
+            credential,
+            blob_endpoint,
+        })
+    }
+}
+
+/// Minimal client for the Azure Blob service REST API, scoped to a single container
+#[derive(Clone, Debug)]
+pub struct AzureBlobClient {
+    connection: AzureConnectionString,
+    container_name: String,
+    http: reqwest::Client,
+}
+
+impl AzureBlobClient {
+    pub fn new(connection: AzureConnectionString, container_name: &str) -> Self {
+        Self {
+            connection,
+            container_name: container_name.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn url(&self, blob_name: Option<&str>) -> String {
+        match blob_name {
+            Some(blob_name) => format!(
+                "{}/{}/{}",
+                self.connection.blob_endpoint, self.container_name, blob_name
+            ),
+            None => format!("{}/{}", self.connection.blob_endpoint, self.container_name),
+        }
+    }
+
+    /// Creates the container if it does not exist yet
+    #[instrument(level = "debug", skip(self), fields(container = self.container_name))]
+    pub async fn create_container_if_not_exists(&self) -> Result<(), AzureStorageError> {
+        let request = self
+            .http
+            .request(Method::PUT, self.url(None))
+            .query(&[("restype", "container")])
+            .build()?;
+        let response = self.execute(request).await?;
+        match response.status() {
+            StatusCode::CREATED => {
+                debug!(container = self.container_name, "Created Azure container");
+                Ok(())
+            }
+            StatusCode::CONFLICT => Ok(()),
+            status => Err(unexpected_status(status, response).await),
+        }
+    }
+
+    /// Downloads a blob, returning `None` if it does not exist
+    #[instrument(level = "trace", skip(self))]
+    pub async fn get_blob(&self, blob_name: &str) -> Result<Option<Vec<u8>>, AzureStorageError> {
+        let request = self
+            .http
+            .request(Method::GET, self.url(Some(blob_name)))
+            .build()?;
+        let response = self.execute(request).await?;
+        match response.status() {
+            StatusCode::OK => Ok(Some(response.bytes().await?.to_vec())),
+            StatusCode::NOT_FOUND => Ok(None),
+            status => Err(unexpected_status(status, response).await),
+        }
+    }
+
+    /// Checks whether a blob exists without downloading it
+    #[instrument(level = "trace", skip(self))]
+    pub async fn blob_exists(&self, blob_name: &str) -> Result<bool, AzureStorageError> {
+        let request = self
+            .http
+            .request(Method::HEAD, self.url(Some(blob_name)))
+            .build()?;
+        let response = self.execute(request).await?;
+        match response.status() {
+            StatusCode::OK => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            status => Err(unexpected_status(status, response).await),
+        }
+    }
+
+    /// Lists the names of all blobs starting with `prefix`, following continuation markers
+    #[instrument(level = "trace", skip(self))]
+    pub async fn list_blob_names(&self, prefix: &str) -> Result<Vec<String>, AzureStorageError> {
+        let mut names = Vec::new();
+        let mut marker: Option<String> = None;
+        loop {
+            let (page, next_marker) = self
+                .list_blob_names_page(prefix, marker.as_deref(), MAX_RESULTS_PER_PAGE)
+                .await?;
+            names.extend(page);
+            match next_marker {
+                Some(next) => marker = Some(next),
+                None => break,
+            }
+        }
+        Ok(names)
+    }
+
+    /// Lists one page of up to `max_results` names of blobs starting with `prefix`, resuming from
+    /// `marker`. Also returns the marker of the next page, if there is one.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn list_blob_names_page(
+        &self,
+        prefix: &str,
+        marker: Option<&str>,
+        max_results: usize,
+    ) -> Result<(Vec<String>, Option<String>), AzureStorageError> {
+        let max_results = max_results.to_string();
+        let mut query = vec![
+            ("restype", "container"),
+            ("comp", "list"),
+            ("prefix", prefix),
+            ("maxresults", &max_results),
+        ];
+        if let Some(marker) = marker {
+            query.push(("marker", marker));
+        }
+        let request = self
+            .http
+            .request(Method::GET, self.url(None))
+            .query(&query)
+            .build()?;
+        let response = self.execute(request).await?;
+        let body = match response.status() {
+            StatusCode::OK => response.bytes().await?,
+            status => return Err(unexpected_status(status, response).await),
+        };
+
+        let next_marker = xml_element_texts(&body, b"NextMarker")?
+            .pop()
+            .filter(|next| !next.is_empty());
+        Ok((xml_element_texts(&body, b"Name")?, next_marker))
+    }
+
+    /// Uploads a block blob.
+    ///
+    /// When `overwrite` is false the upload is conditional on the blob not existing yet, and
+    /// [`AzureStorageError::BlobAlreadyExists`] is returned if it does.
+    #[instrument(level = "trace", skip(self, content))]
+    pub async fn put_blob(
+        &self,
+        blob_name: &str,
+        content: Vec<u8>,
+        overwrite: bool,
+    ) -> Result<(), AzureStorageError> {
+        let mut builder = self
+            .http
+            .request(Method::PUT, self.url(Some(blob_name)))
+            .header("x-ms-blob-type", "BlockBlob")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .body(content);
+        if !overwrite {
+            builder = builder.header(IF_NONE_MATCH, "*");
+        }
+        let response = self.execute(builder.build()?).await?;
+        match response.status() {
+            StatusCode::CREATED => Ok(()),
+            // Azure reports an existing blob as 409 BlobAlreadyExists, some emulators as 412
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED if !overwrite => {
+                Err(AzureStorageError::BlobAlreadyExists(blob_name.to_string()))
+            }
+            status => Err(unexpected_status(status, response).await),
+        }
+    }
+
+    async fn execute(&self, mut request: Request) -> Result<reqwest::Response, AzureStorageError> {
+        let headers = request.headers_mut();
+        headers.insert(
+            "x-ms-date",
+            header_value(
+                &chrono::Utc::now()
+                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                    .to_string(),
+            ),
+        );
+        headers.insert("x-ms-version", header_value(AZURE_STORAGE_API_VERSION));
+
+        match &self.connection.credential {
+            AzureCredential::SharedKey(Secret(key)) => {
+                let string_to_sign = string_to_sign(&self.connection.account_name, &request);
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+                mac.update(string_to_sign.as_bytes());
+                let signature = BASE64.encode(mac.finalize().into_bytes());
+                request.headers_mut().insert(
+                    AUTHORIZATION,
+                    header_value(&format!(
+                        "SharedKey {}:{}",
+                        self.connection.account_name, signature
+                    )),
+                );
+            }
+            AzureCredential::SharedAccessSignature(Secret(token)) => {
+                let url = request.url_mut();
+                let query = match url.query() {
+                    Some(query) => format!("{query}&{token}"),
+                    None => token.clone(),
+                };
+                url.set_query(Some(&query));
+            }
+        }
+
+        trace!(method = %request.method(), url = %request.url(), "Sending Azure request");
+        Ok(self.http.execute(request).await?)
+    }
+}
+
+/// Builds the Shared Key string-to-sign for a request, as described in
+/// <https://learn.microsoft.com/en-us/rest/api/storageservices/authorize-with-shared-key>
+fn string_to_sign(account_name: &str, request: &Request) -> String {
+    let headers = request.headers();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let content_length = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|body| body.len())
+        .filter(|length| *length > 0)
+        .map(|length| length.to_string())
+        .unwrap_or_default();
+
+    let mut canonicalized_headers: Vec<(String, String)> = headers
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+        .map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                value.to_str().unwrap_or_default().trim().to_string(),
+            )
+        })
+        .collect();
+    canonicalized_headers.sort();
+
+    let mut query: Vec<(String, String)> = request
+        .url()
+        .query_pairs()
+        .map(|(key, value)| (key.to_lowercase(), value.to_string()))
+        .collect();
+    query.sort();
+
+    let mut result = [
+        request.method().as_str().to_string(),
+        header("content-encoding"),
+        header("content-language"),
+        content_length,
+        header("content-md5"),
+        header("content-type"),
+        // Date is empty because x-ms-date is always set
+        String::new(),
+        header("if-modified-since"),
+        header("if-match"),
+        header("if-none-match"),
+        header("if-unmodified-since"),
+        header("range"),
+    ]
+    .join("\n");
+    result.push('\n');
+    for (name, value) in canonicalized_headers {
+        result.push_str(&format!("{name}:{value}\n"));
+    }
+    result.push_str(&format!("/{}{}", account_name, request.url().path()));
+    for (key, value) in query {
+        result.push_str(&format!("\n{key}:{value}"));
+    }
+    result
+}
+
+/// Signature storage for a single namespace in an Azure Blob container.
+///
+/// Signatures are stored as blobs named `<namespace>/<epoch>`, with the epoch zero-padded to 21
+/// digits, and the latest heartbeat as `<namespace>/heartbeat`. Signature blobs are written with
+/// `If-None-Match: *`, so an existing signature is never overwritten. Signatures stored by earlier
+/// versions under unpadded names are still read.
+#[derive(Clone, Debug)]
+pub struct AzureSignatureStorage {
+    client: AzureBlobClient,
+    namespace: String,
+    legacy_epochs: LegacyEpochs,
+    /// Markers of the listing pages read so far, keyed by the first epoch of each page. Blob listings
+    /// cannot start at a given name, so listings resume from the closest page before it instead.
+    page_markers: Arc<Mutex<BTreeMap<u64, String>>>,
+}
+
+impl AzureSignatureStorage {
+    pub fn new(client: AzureBlobClient, namespace: &str) -> Self {
+        Self {
+            client,
+            namespace: namespace.to_string(),
+            legacy_epochs: LegacyEpochs::default(),
+            page_markers: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn epoch_blob_name(&self, epoch: &u64) -> String {
+        format!("{}/{}", self.namespace, padded_epoch_name(*epoch))
+    }
+
+    /// Name of a signature blob stored by an earlier version
+    pub fn legacy_epoch_blob_name(&self, epoch: &u64) -> String {
+        format!("{}/{}", self.namespace, epoch)
+    }
+
+    pub fn heartbeat_blob_name(&self) -> String {
+        format!("{}/{}", self.namespace, HEARTBEAT_BLOB_NAME)
+    }
+
+    fn parse_epoch_blob_name(&self, name: &str) -> Option<u64> {
+        parse_epoch_name(name.strip_prefix(&self.namespace)?.strip_prefix('/')?)
+    }
+
+    /// Returns the marker of the last page read that starts at or before `epoch`
+    fn page_marker_before(&self, epoch: u64) -> Option<(u64, String)> {
+        self.page_markers
+            .lock()
+            .expect("Page markers lock poisoned")
+            .range(..=epoch)
+            .next_back()
+            .map(|(first, marker)| (*first, marker.clone()))
+    }
+
+    /// Lists the epochs stored under padded names in `range` in ascending order, at most `limit`.
+    /// The listing resumes from the closest known page before the range and stops at its end.
+    async fn list_padded_epochs(
+        &self,
+        range: &RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, AzureStorageError> {
+        let prefix = format!("{}/{}", self.namespace, PADDED_EPOCH_PREFIX);
+        let mut marker = self
+            .page_marker_before(*range.start())
+            .map(|(_, marker)| marker);
+        let mut epochs = Vec::new();
+        while epochs.len() < limit {
+            let (names, next_marker) = self
+                .client
+                .list_blob_names_page(&prefix, marker.as_deref(), MAX_RESULTS_PER_PAGE)
+                .await?;
+            let page: Vec<u64> = names
+                .iter()
+                .filter_map(|name| self.parse_epoch_blob_name(name))
+                .collect();
+            if let (Some(marker), Some(first)) = (marker, page.first()) {
+                self.page_markers
+                    .lock()
+                    .expect("Page markers lock poisoned")
+                    .insert(*first, marker);
+            }
+            for epoch in page {
+                if epoch > *range.end() {
+                    return Ok(epochs);
+                }
+                if range.contains(&epoch) {
+                    epochs.push(epoch);
+                    if epochs.len() == limit {
+                        return Ok(epochs);
+                    }
+                }
+            }
+            match next_marker {
+                Some(next) => marker = Some(next),
+                None => break,
+            }
+        }
+        Ok(epochs)
+    }
+
+    /// Returns the epochs stored under unpadded names, which never start with `0`
+    async fn legacy_epochs(&self) -> Result<&[u64], AzureStorageError> {
+        self.legacy_epochs
+            .get_or_list(async || {
+                let mut epochs = Vec::new();
+                for digit in 1..=9 {
+                    let prefix = format!("{}/{digit}", self.namespace);
+                    for name in self.client.list_blob_names(&prefix).await? {
+                        if let Some(name) = name.strip_prefix(&format!("{}/", self.namespace))
+                            && is_legacy_epoch_name(name)
+                        {
+                            epochs.extend(parse_epoch_name(name));
+                        }
+                    }
+                }
+                Ok(epochs)
+            })
+            .await
+    }
+
+    /// Returns the name `epoch` is stored under if it was stored by an earlier version
+    async fn existing_legacy_blob_name(
+        &self,
+        epoch: &u64,
+    ) -> Result<Option<String>, AzureStorageError> {
+        Ok(self
+            .legacy_epochs()
+            .await?
+            .binary_search(epoch)
+            .is_ok()
+            .then(|| self.legacy_epoch_blob_name(epoch)))
+    }
+}
+
+impl SignatureRepository for AzureSignatureStorage {
+    async fn has_signature(&self, epoch: &u64) -> Result<bool, SignatureRepositoryError> {
+        if self
+            .client
+            .blob_exists(&self.epoch_blob_name(epoch))
+            .await?
+        {
+            return Ok(true);
+        }
+        Ok(self.existing_legacy_blob_name(epoch).await?.is_some())
+    }
+
+    async fn get_signature(
+        &self,
+        epoch: &u64,
+    ) -> Result<Option<EpochSignature>, SignatureRepositoryError> {
+        let mut bytes = self.client.get_blob(&self.epoch_blob_name(epoch)).await?;
+        if bytes.is_none()
+            && let Some(legacy_name) = self.existing_legacy_blob_name(epoch).await?
+        {
+            bytes = self.client.get_blob(&legacy_name).await?;
+        }
+        match bytes {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_signature(
+        &mut self,
+        epoch: &u64,
+        signature: EpochSignature,
+    ) -> Result<(), SignatureRepositoryError> {
+        let result = match self.existing_legacy_blob_name(epoch).await? {
+            Some(legacy_name) => Err(AzureStorageError::BlobAlreadyExists(legacy_name)),
+            None => {
+                let content = bincode::encode_to_vec(&signature, BINCODE_CONFIG)?;
+                self.client
+                    .put_blob(&self.epoch_blob_name(epoch), content, false)
+                    .await
+            }
+        };
+        match result {
+            Err(AzureStorageError::BlobAlreadyExists(blob_name)) => {
+                let existing = self
+                    .get_signature(epoch)
+                    .await?
+                    .ok_or(AzureStorageError::BlobAlreadyExists(blob_name))?;
+                ensure_same_digest(epoch, &existing, &signature)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    async fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, SignatureRepositoryError> {
+        let epochs = self.list_padded_epochs(&range, limit).await?;
+        let legacy = self.legacy_epochs().await?;
+        Ok(merge_legacy_epochs(epochs, legacy, &range, limit))
+    }
+
+    /// Lists from the last page read so far, so only the first call lists the whole namespace
+    async fn latest_epoch(&self) -> Result<Option<u64>, SignatureRepositoryError> {
+        let last_page = self
+            .page_marker_before(u64::MAX)
+            .map_or(0, |(first, _)| first);
+        let latest = self
+            .list_padded_epochs(&(last_page..=u64::MAX), usize::MAX)
+            .await?
+            .last()
+            .copied();
+        let legacy = self.legacy_epochs().await?.last().copied();
+        Ok(latest.max(legacy))
+    }
+
+    /// Lists the whole namespace
+    async fn count_signatures(&self) -> Result<u64, SignatureRepositoryError> {
+        let padded = self
+            .list_padded_epochs(&(0..=u64::MAX), usize::MAX)
+            .await?
+            .len();
+        let legacy = self.legacy_epochs().await?.len();
+        Ok((padded + legacy) as u64)
+    }
+
+    async fn get_heartbeat(&self) -> Result<Option<Heartbeat>, SignatureRepositoryError> {
+        match self.client.get_blob(&self.heartbeat_blob_name()).await? {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<(), SignatureRepositoryError> {
+        let content = bincode::encode_to_vec(heartbeat, BINCODE_CONFIG)?;
+        self.client
+            .put_blob(&self.heartbeat_blob_name(), content, true)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_CONNECTION_STRING: &str = "DefaultEndpointsProtocol=https;AccountName=account;AccountKey=a2V5;EndpointSuffix=core.windows.net";
+
+    fn mock_client(server: &mockito::Server) -> AzureBlobClient {
+        let connection = AzureConnectionString::parse(
+            &format!(
+                "AccountName=account;AccountKey=a2V5;BlobEndpoint={}/account",
+                server.url()
+            ),
+            "",
+        )
+        .unwrap();
+        AzureBlobClient::new(connection, "signatures")
+    }
+
+    #[test]
+    fn test_parse_connection_string() {
+        let connection = AzureConnectionString::parse(TEST_CONNECTION_STRING, "ignored").unwrap();
+        assert_eq!(connection.account_name, "account");
+        assert_eq!(
+            connection.blob_endpoint,
+            "https://account.blob.core.windows.net"
+        );
+        assert!(matches!(
+            connection.credential,
+            AzureCredential::SharedKey(Secret(ref key)) if key == b"key"
+        ));
+    }
+
+    #[test]
+    fn test_parse_development_storage_connection_string() {
+        let connection = AzureConnectionString::parse("UseDevelopmentStorage=true", "").unwrap();
+        assert_eq!(connection.account_name, DEVELOPMENT_ACCOUNT_NAME);
+        assert_eq!(connection.blob_endpoint, DEVELOPMENT_BLOB_ENDPOINT);
+    }
+
+    #[test]
+    fn test_parse_connection_string_requires_credential() {
+        let result = AzureConnectionString::parse("AccountName=account", "");
+        assert!(matches!(
+            result,
+            Err(AzureStorageError::InvalidConnectionString(_))
+        ));
+    }
+
+    #[test]
+    fn test_connection_string_debug_hides_key() {
+        let connection = AzureConnectionString::parse(TEST_CONNECTION_STRING, "").unwrap();
+        assert!(!format!("{connection:?}").contains("a2V5"));
+    }
+
+    #[test]
+    fn test_string_to_sign() {
+        let request = reqwest::Client::new()
+            .put("https://account.blob.core.windows.net/container/ns/1?restype=container")
+            .header("x-ms-version", AZURE_STORAGE_API_VERSION)
+            .header("x-ms-date", "Mon, 01 Jan 2024 00:00:00 GMT")
+            .header("x-ms-blob-type", "BlockBlob")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(IF_NONE_MATCH, "*")
+            .body(vec![1u8, 2, 3])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            string_to_sign("account", &request),
+            "PUT\n\n\n3\n\napplication/octet-stream\n\n\n\n*\n\n\n\
+             x-ms-blob-type:BlockBlob\n\
+             x-ms-date:Mon, 01 Jan 2024 00:00:00 GMT\n\
+             x-ms-version:2021-12-02\n\
+             /account/container/ns/1\n\
+             restype:container"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_blob_is_conditional() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("PUT", "/account/signatures/ns/1")
+            .match_header("if-none-match", "*")
+            .match_header("x-ms-blob-type", "BlockBlob")
+            .match_header(
+                "authorization",
+                mockito::Matcher::Regex("^SharedKey account:".to_string()),
+            )
+            .with_status(201)
+            .create_async()
+            .await;
+
+        mock_client(&server)
+            .put_blob("ns/1", vec![1, 2, 3], false)
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_put_blob_reports_existing_blob() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("PUT", "/account/signatures/ns/1")
+            .with_status(409)
+            .create_async()
+            .await;
+
+        let result = mock_client(&server)
+            .put_blob("ns/1", vec![1, 2, 3], false)
+            .await;
+        assert!(matches!(
+            result,
+            Err(AzureStorageError::BlobAlreadyExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_blob_returns_none() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/account/signatures/ns/1")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        assert!(
+            mock_client(&server)
+                .get_blob("ns/1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    fn listing_body(names: &[String], next_marker: &str) -> String {
+        let blobs: String = names
+            .iter()
+            .map(|name| format!("<Blob><Name>{name}</Name></Blob>"))
+            .collect();
+        format!(
+            "<EnumerationResults><Blobs>{blobs}</Blobs><NextMarker>{next_marker}</NextMarker></EnumerationResults>"
+        )
+    }
+
+    #[tokio::test]
+    async fn test_list_epochs_resumes_from_page_markers() {
+        let mut server = mockito::Server::new_async().await;
+        let storage = AzureSignatureStorage::new(mock_client(&server), "ns");
+        let names = |epochs: &[u64]| {
+            epochs
+                .iter()
+                .map(|e| storage.epoch_blob_name(e))
+                .collect::<Vec<_>>()
+        };
+        let first_page = server
+            .mock("GET", "/account/signatures")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("prefix".into(), "ns/0".into()),
+                // The marker is appended last, so this only matches the first page
+                mockito::Matcher::Regex("maxresults=1000$".into()),
+            ]))
+            .with_status(200)
+            .with_body(listing_body(&names(&[2, 3]), "page2"))
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/account/signatures")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("prefix".into(), "ns/0".into()),
+                mockito::Matcher::UrlEncoded("marker".into(), "page2".into()),
+            ]))
+            .with_status(200)
+            .with_body(listing_body(&names(&[10, 12]), ""))
+            .create_async()
+            .await;
+        // Unpadded names of earlier versions are listed by their first digit
+        server
+            .mock("GET", "/account/signatures")
+            .match_query(mockito::Matcher::UrlEncoded("prefix".into(), "ns/5".into()))
+            .with_status(200)
+            .with_body(listing_body(&["ns/5".to_string()], ""))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/account/signatures")
+            .match_query(mockito::Matcher::Regex("prefix=ns%2F[1-46-9]&".into()))
+            .with_status(200)
+            .with_body(listing_body(&[], ""))
+            .create_async()
+            .await;
+
+        assert_eq!(storage.count_signatures().await.unwrap(), 5);
+        // Listings after the first page resume from its marker
+        assert_eq!(
+            storage.list_epochs(11..=u64::MAX, 10).await.unwrap(),
+            vec![12]
+        );
+        assert_eq!(storage.latest_epoch().await.unwrap(), Some(12));
+        assert_eq!(storage.list_epochs(3..=10, 2).await.unwrap(), vec![3, 5]);
+        first_page.assert_async().await;
+    }
+
+    #[test]
+    fn test_blob_names() {
+        let storage = AzureSignatureStorage::new(
+            AzureBlobClient::new(
+                AzureConnectionString::parse("UseDevelopmentStorage=true", "").unwrap(),
+                "signatures",
+            ),
+            "ns",
+        );
+        assert_eq!(storage.epoch_blob_name(&7), "ns/000000000000000000007");
+        assert_eq!(storage.legacy_epoch_blob_name(&7), "ns/7");
+        assert_eq!(storage.parse_epoch_blob_name("ns/7"), Some(7));
+        assert_eq!(storage.parse_epoch_blob_name("ns/heartbeat"), None);
+    }
+
+    mod azurite {
+        //! Integration tests against the Azurite emulator. Start it with
+        //! `docker run -p 10000:10000 mcr.microsoft.com/azure-storage/azurite azurite-blob --blobHost 0.0.0.0`
+        //! and run `cargo test -p akd_watch_common -- --ignored azurite`.
+        use super::*;
+        use crate::{
+            Epoch,
+            storage::signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+            testing::sign_test_epoch,
+        };
+
+        async fn azurite_storage(namespace: &str) -> AzureSignatureStorage {
+            let connection =
+                AzureConnectionString::parse("UseDevelopmentStorage=true", "").unwrap();
+            let client = AzureBlobClient::new(connection, "akd-watch-test");
+            client.create_container_if_not_exists().await.unwrap();
+            AzureSignatureStorage::new(client, namespace)
+        }
+
+        #[tokio::test]
+        #[ignore = "requires the Azurite emulator"]
+        async fn test_set_and_get_signature() {
+            let namespace = uuid::Uuid::new_v4().to_string();
+            let mut storage = azurite_storage(&namespace).await;
+
+            assert!(!storage.has_signature(&1).await.unwrap());
+            assert!(storage.get_signature(&1).await.unwrap().is_none());
+            storage
+                .set_signature(&1, sign_test_epoch(1, 1).await)
+                .await
+                .unwrap();
+
+            assert!(storage.has_signature(&1).await.unwrap());
+            let signature = storage.get_signature(&1).await.unwrap().unwrap();
+            assert_eq!(signature.epoch(), Epoch::new(1));
+        }
+
+        #[tokio::test]
+        #[ignore = "requires the Azurite emulator"]
+        async fn test_signature_is_never_overwritten() {
+            let namespace = uuid::Uuid::new_v4().to_string();
+            let mut storage = azurite_storage(&namespace).await;
+
+            storage
+                .set_signature(&1, sign_test_epoch(1, 1).await)
+                .await
+                .unwrap();
+            // Re-storing the same digest is a no-op
+            storage
+                .set_signature(&1, sign_test_epoch(1, 1).await)
+                .await
+                .unwrap();
+            let result = storage.set_signature(&1, sign_test_epoch(1, 2).await).await;
+            assert!(matches!(
+                result,
+                Err(SignatureRepositoryError::SignatureConflict { epoch: 1, .. })
+            ));
+        }
+
+        #[tokio::test]
+        #[ignore = "requires the Azurite emulator"]
+        async fn test_heartbeat_is_replaced() {
+            let namespace = uuid::Uuid::new_v4().to_string();
+            let mut storage = azurite_storage(&namespace).await;
+            let key = InMemorySigningKeyRepository::new(chrono::Duration::days(1))
+                .get_current_signing_key()
+                .await
+                .unwrap();
+
+            assert!(storage.get_heartbeat().await.unwrap().is_none());
+            let first = Heartbeat::sign(&namespace, &sign_test_epoch(1, 1).await, &key).unwrap();
+            storage.set_heartbeat(first).await.unwrap();
+            let second = Heartbeat::sign(&namespace, &sign_test_epoch(2, 1).await, &key).unwrap();
+            storage.set_heartbeat(second).await.unwrap();
+
+            let heartbeat = storage.get_heartbeat().await.unwrap().unwrap();
+            assert_eq!(heartbeat.epoch(), Epoch::new(2));
+        }
+    }
+}
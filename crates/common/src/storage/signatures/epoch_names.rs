@@ -0,0 +1,112 @@
+//! Object names of epoch signatures in the S3 and Azure backends.
+//!
+//! Object listings are sorted by name, so epochs are zero-padded to a fixed width to make the listing
+//! order numeric and let a listing start at, and stop after, a given epoch. The width is one digit
+//! more than the longest `u64`, so every padded name starts with `0` and sorts before the unpadded
+//! names written by earlier versions, which are still read.
+
+use std::{ops::RangeInclusive, sync::Arc};
+
+use tokio::sync::OnceCell;
+
+/// Width of a padded epoch name
+const PADDED_EPOCH_WIDTH: usize = 21;
+
+/// Prefix shared by all padded epoch names, relative to the namespace
+pub(super) const PADDED_EPOCH_PREFIX: &str = "0";
+
+/// Pads `epoch` to [`PADDED_EPOCH_WIDTH`] digits
+pub(super) fn padded_epoch_name(epoch: u64) -> String {
+    format!("{epoch:0PADDED_EPOCH_WIDTH$}")
+}
+
+/// Parses an epoch name relative to the namespace, padded or not
+pub(super) fn parse_epoch_name(name: &str) -> Option<u64> {
+    if name.is_empty() || !name.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    name.parse().ok()
+}
+
+/// Whether `name` is an unpadded epoch name written by an earlier version. The unpadded name of
+/// epoch 0 starts with `0` and is listed with the padded names instead.
+pub(super) fn is_legacy_epoch_name(name: &str) -> bool {
+    !name.starts_with(PADDED_EPOCH_PREFIX) && parse_epoch_name(name).is_some()
+}
+
+/// Epochs stored under unpadded names by earlier versions.
+///
+/// Only the current version writes signatures, and always under padded names, so the unpadded ones
+/// are listed once and then kept for the lifetime of the storage.
+#[derive(Clone, Debug, Default)]
+pub(super) struct LegacyEpochs(Arc<OnceCell<Vec<u64>>>);
+
+impl LegacyEpochs {
+    /// Returns the legacy epochs in ascending order, listing them with `list` on first use
+    pub(super) async fn get_or_list<E, F>(&self, list: F) -> Result<&[u64], E>
+    where
+        F: AsyncFnOnce() -> Result<Vec<u64>, E>,
+    {
+        let epochs = self
+            .0
+            .get_or_try_init(async || {
+                let mut epochs = list().await?;
+                epochs.sort_unstable();
+                Ok(epochs)
+            })
+            .await?;
+        Ok(epochs)
+    }
+}
+
+/// Merges the padded epochs listed from `range` with the legacy epochs in it, keeping the first `limit`
+pub(super) fn merge_legacy_epochs(
+    mut epochs: Vec<u64>,
+    legacy: &[u64],
+    range: &RangeInclusive<u64>,
+    limit: usize,
+) -> Vec<u64> {
+    epochs.extend(legacy.iter().filter(|epoch| range.contains(epoch)));
+    epochs.sort_unstable();
+    epochs.dedup();
+    epochs.truncate(limit);
+    epochs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_names_sort_numerically_before_legacy_names() {
+        let mut names = vec![
+            padded_epoch_name(u64::MAX),
+            padded_epoch_name(10),
+            "2".to_string(),
+            padded_epoch_name(2),
+        ];
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "000000000000000000002",
+                "000000000000000000010",
+                "018446744073709551615",
+                "2"
+            ]
+        );
+        assert!(names.iter().all(|name| parse_epoch_name(name).is_some()));
+        assert!(is_legacy_epoch_name("2"));
+        assert!(!is_legacy_epoch_name(&padded_epoch_name(2)));
+        assert!(!is_legacy_epoch_name("heartbeat"));
+        assert_eq!(parse_epoch_name("+2"), None);
+    }
+
+    #[test]
+    fn test_merge_legacy_epochs() {
+        assert_eq!(
+            merge_legacy_epochs(vec![4, 6], &[1, 3, 5, 9], &(2..=8), 3),
+            vec![3, 4, 5]
+        );
+    }
+}
@@ -1,39 +1,180 @@
-use tracing::{instrument, trace};
+use std::{collections::BTreeSet, ops::RangeInclusive};
+
+use tracing::{info, instrument, trace, warn};
 
 use crate::{
-    BINCODE_CONFIG,
+    BINCODE_CONFIG, Heartbeat,
     epoch_signature::EpochSignature,
-    storage::signatures::{
-        SignatureRepository, SignatureRepositoryError, SignatureStorageFileError,
+    storage::{
+        RecoveryReport, quarantine_file, remove_temp_files,
+        signatures::{
+            SignatureRepository, SignatureRepositoryError, SignatureStorageFileError,
+            ensure_same_digest, segment_store::SegmentStore,
+        },
+        write_atomic,
     },
 };
 
+/// Stores a namespace's signatures in packed segment files under `<root>/segments`.
+///
+/// Signatures written by earlier versions, one `<root>/<epoch>/sig` file per epoch, are still read
+/// and are moved into segments by [`FilesystemSignatureStorage::recover`].
 #[derive(Clone, Debug)]
 pub struct FilesystemSignatureStorage {
     root_path: String,
+    segments: SegmentStore,
 }
 
 const SIG_FILE_NAME: &str = "sig";
+const HEARTBEAT_FILE_NAME: &str = "heartbeat";
 
 impl FilesystemSignatureStorage {
-    pub fn new(root_path: &str) -> Self {
+    pub fn new(root_path: &str) -> Result<Self, SignatureRepositoryError> {
         // Create the root directory if it doesn't exist
-        std::fs::create_dir_all(root_path)
-            .expect("Failed to create signature storage root directory");
+        std::fs::create_dir_all(root_path).map_err(SignatureStorageFileError::IoError)?;
 
-        FilesystemSignatureStorage {
+        Ok(FilesystemSignatureStorage {
             root_path: root_path.into(),
+            segments: SegmentStore::new(root_path),
+        })
+    }
+
+    /// Startup recovery for a namespace's signature directory.
+    ///
+    /// Removes temporary files left by interrupted writes, and quarantines legacy signature files and
+    /// heartbeat files that cannot be decoded (e.g. truncated by a crash before writes were atomic) by
+    /// renaming them with a `.corrupt` suffix. A quarantined signature is then reported missing, so the
+    /// auditor flags the gap instead of failing on every read.
+    ///
+    /// Remaining legacy `<epoch>/sig` files are then migrated into segment files. Each legacy file is
+    /// only removed once its signature is durably stored in a segment, so an interrupted migration
+    /// simply resumes on the next startup.
+    pub fn recover(root_path: &str) -> Result<RecoveryReport, SignatureRepositoryError> {
+        let mut report = RecoveryReport {
+            removed_temp_files: remove_temp_files(root_path, true)
+                .map_err(SignatureStorageFileError::IoError)?,
+            ..Default::default()
+        };
+        let root = std::path::Path::new(root_path);
+        if !root.is_dir() {
+            return Ok(report);
+        }
+
+        let storage = Self::new(root_path)?;
+        report
+            .removed_temp_files
+            .extend(storage.segments.remove_incomplete_files()?);
+        // Refuse to touch segments written by an unknown format version
+        storage.segments.check_headers()?;
+
+        for epoch in storage.legacy_epochs()? {
+            let sig_path = std::path::PathBuf::from(storage.epoch_sig_path(&epoch));
+            if !Self::decodes::<EpochSignature>(&sig_path) {
+                warn!(path = %sig_path.display(), "Signature file is corrupt");
+                report
+                    .quarantined_files
+                    .push(quarantine_file(&sig_path).map_err(SignatureStorageFileError::IoError)?);
+                continue;
+            }
+            storage.migrate_legacy_signature(epoch)?;
+            report.migrated_signatures += 1;
+        }
+        if report.migrated_signatures > 0 {
+            info!(
+                root_path,
+                migrated = report.migrated_signatures,
+                "Migrated legacy signature files into segments"
+            );
+        }
+
+        let heartbeat_path = root.join(HEARTBEAT_FILE_NAME);
+        if heartbeat_path.is_file() && !Self::decodes::<Heartbeat>(&heartbeat_path) {
+            warn!(path = %heartbeat_path.display(), "Heartbeat file is corrupt");
+            report.quarantined_files.push(
+                quarantine_file(&heartbeat_path).map_err(SignatureStorageFileError::IoError)?,
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Moves one legacy signature file into its segment, then removes the legacy file
+    fn migrate_legacy_signature(&self, epoch: u64) -> Result<(), SignatureRepositoryError> {
+        let sig_path = self.epoch_sig_path(&epoch);
+        let bytes = std::fs::read(&sig_path).map_err(SignatureStorageFileError::IoError)?;
+        match self.segments.insert_new(epoch, &bytes) {
+            Ok(()) => {}
+            // A previous migration stored the signature but was interrupted before removing the file
+            Err(SignatureStorageFileError::IoError(e))
+                if e.kind() == std::io::ErrorKind::AlreadyExists =>
+            {
+                let legacy: EpochSignature = bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0;
+                let packed = self
+                    .packed_signature(epoch)?
+                    .ok_or(SignatureStorageFileError::IoError(e))?;
+                ensure_same_digest(&epoch, &packed, &legacy)?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        trace!(epoch, sig_path, "Removing migrated legacy signature file");
+        std::fs::remove_file(&sig_path).map_err(SignatureStorageFileError::IoError)?;
+        // The directory may still hold quarantined files, which are kept for inspection
+        let _ = std::fs::remove_dir(self.epoch_path(&epoch));
+        Ok(())
+    }
+
+    /// Lists the epochs stored in the legacy `<epoch>/sig` layout, in ascending order
+    fn legacy_epochs(&self) -> Result<Vec<u64>, SignatureRepositoryError> {
+        let mut epochs = Vec::new();
+        for entry in
+            std::fs::read_dir(&self.root_path).map_err(SignatureStorageFileError::IoError)?
+        {
+            let entry = entry.map_err(SignatureStorageFileError::IoError)?;
+            if let Some(epoch) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u64>().ok())
+                && self.get_existing_signature_path(&epoch).is_some()
+            {
+                epochs.push(epoch);
+            }
+        }
+        epochs.sort_unstable();
+        Ok(epochs)
+    }
+
+    fn decodes<T: bincode::Decode<()>>(path: &std::path::Path) -> bool {
+        std::fs::read(path)
+            .ok()
+            .is_some_and(|bytes| bincode::decode_from_slice::<T, _>(&bytes, BINCODE_CONFIG).is_ok())
+    }
+
+    fn packed_signature(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<EpochSignature>, SignatureRepositoryError> {
+        match self.segments.get(epoch)? {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0)),
+            None => Ok(None),
         }
     }
 
+    /// Directory of an epoch in the legacy layout
     pub fn epoch_path(&self, epoch: &u64) -> String {
         format!("{}/{}", self.root_path, epoch)
     }
 
+    /// Signature file of an epoch in the legacy layout
     pub fn epoch_sig_path(&self, epoch: &u64) -> String {
         format!("{}/{}/{}", self.root_path, epoch, SIG_FILE_NAME)
     }
 
+    pub fn heartbeat_path(&self) -> String {
+        format!("{}/{}", self.root_path, HEARTBEAT_FILE_NAME)
+    }
+
+    /// Returns the legacy signature file of an epoch, if one exists
     #[instrument(skip_all, fields(epoch))]
     pub fn get_existing_signature_path(&self, epoch: &u64) -> Option<String> {
         let sig_file_path = self.epoch_sig_path(epoch);
@@ -54,39 +195,28 @@ impl FilesystemSignatureStorage {
 
 impl SignatureRepository for FilesystemSignatureStorage {
     async fn has_signature(&self, epoch: &u64) -> Result<bool, SignatureRepositoryError> {
-        let signature_path = self.get_existing_signature_path(epoch);
-        match signature_path {
-            Some(_) => Ok(true),
-            None => Ok(false),
-        }
+        Ok(self.segments.contains(*epoch)? || self.get_existing_signature_path(epoch).is_some())
     }
 
     async fn get_signature(
         &self,
         epoch: &u64,
     ) -> Result<Option<EpochSignature>, SignatureRepositoryError> {
-        let signature_path = self.get_existing_signature_path(epoch);
-        trace!(
-            epoch,
-            signature_path, "Checking for existing signature file"
-        );
+        if let Some(signature) = self.packed_signature(*epoch)? {
+            trace!(epoch, "Decoded signature from segment");
+            return Ok(Some(signature));
+        }
 
+        // Fall back to the legacy layout for signatures that have not been migrated yet
+        let signature_path = self.get_existing_signature_path(epoch);
         if let Some(path) = signature_path {
-            trace!(epoch, path, "Found signature file, reading it");
-            // Read the signature file to bytes
+            trace!(epoch, path, "Found legacy signature file, reading it");
             let bytes = std::fs::read(&path).map_err(SignatureStorageFileError::IoError)?;
-            trace!(
-                epoch,
-                path,
-                "Read {} bytes from signature file",
-                bytes.len()
-            );
-
             let signature: EpochSignature = bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0;
             trace!(epoch, path, "Decoded signature from file");
             Ok(Some(signature))
         } else {
-            trace!(epoch, "No signature file found for epoch");
+            trace!(epoch, "No signature found for epoch");
             Ok(None)
         }
     }
@@ -96,16 +226,262 @@ impl SignatureRepository for FilesystemSignatureStorage {
         epoch: &u64,
         signature: EpochSignature,
     ) -> Result<(), SignatureRepositoryError> {
-        let epoch_dir = self.epoch_path(epoch);
+        // A legacy signature that has not been migrated yet is just as final as a packed one
+        if let Some(existing) = self.get_signature(epoch).await? {
+            return ensure_same_digest(epoch, &existing, &signature);
+        }
+
+        let content = bincode::encode_to_vec(&signature, BINCODE_CONFIG)?;
+        match self.segments.insert_new(*epoch, &content) {
+            Ok(()) => Ok(()),
+            Err(SignatureStorageFileError::IoError(e))
+                if e.kind() == std::io::ErrorKind::AlreadyExists =>
+            {
+                let existing = self
+                    .packed_signature(*epoch)?
+                    .ok_or(SignatureStorageFileError::IoError(e))?;
+                ensure_same_digest(epoch, &existing, &signature)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        // ensure the epoch directory is created
-        std::fs::create_dir_all(&epoch_dir).map_err(SignatureStorageFileError::IoError)?;
+    #[instrument(skip(self))]
+    async fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, SignatureRepositoryError> {
+        let mut epochs: BTreeSet<u64> = self
+            .segments
+            .list_epochs(&range, limit)?
+            .into_iter()
+            .collect();
+        epochs.extend(
+            self.legacy_epochs()?
+                .into_iter()
+                .filter(|epoch| range.contains(epoch))
+                .take(limit),
+        );
+        Ok(epochs.into_iter().take(limit).collect())
+    }
 
-        // Write the signature to a file in the epoch directory
-        let signature_path = self.epoch_sig_path(epoch);
-        let content = bincode::encode_to_vec(signature, BINCODE_CONFIG)?;
-        std::fs::write(&signature_path, content).map_err(SignatureStorageFileError::IoError)?;
+    async fn latest_epoch(&self) -> Result<Option<u64>, SignatureRepositoryError> {
+        let packed = self.segments.latest_epoch()?;
+        let legacy = self.legacy_epochs()?.last().copied();
+        Ok(packed.max(legacy))
+    }
 
+    async fn count_signatures(&self) -> Result<u64, SignatureRepositoryError> {
+        let mut count = self.segments.count()?;
+        // Skip legacy files whose migration was interrupted after the signature was packed
+        for epoch in self.legacy_epochs()? {
+            if !self.segments.contains(epoch)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    async fn get_heartbeat(&self) -> Result<Option<Heartbeat>, SignatureRepositoryError> {
+        let heartbeat_path = self.heartbeat_path();
+        if !std::path::Path::new(&heartbeat_path).is_file() {
+            trace!(heartbeat_path, "No heartbeat file found");
+            return Ok(None);
+        }
+
+        let bytes = std::fs::read(&heartbeat_path).map_err(SignatureStorageFileError::IoError)?;
+        let heartbeat: Heartbeat = bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0;
+        trace!(heartbeat_path, "Decoded heartbeat from file");
+        Ok(Some(heartbeat))
+    }
+
+    async fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<(), SignatureRepositoryError> {
+        let content = bincode::encode_to_vec(heartbeat, BINCODE_CONFIG)?;
+        write_atomic(self.heartbeat_path(), &content)
+            .map_err(SignatureStorageFileError::IoError)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Epoch, testing::sign_test_epoch};
+
+    #[tokio::test]
+    async fn test_set_signature_is_write_once() {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        let mut storage = FilesystemSignatureStorage::new(&root.to_string_lossy()).unwrap();
+
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+        // Re-storing the same digest is idempotent
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+
+        let result = storage.set_signature(&1, sign_test_epoch(1, 2).await).await;
+        assert!(matches!(
+            result,
+            Err(SignatureRepositoryError::SignatureConflict { epoch: 1, .. })
+        ));
+        let stored = storage.get_signature(&1).await.unwrap().unwrap();
+        assert_eq!(stored.digest(), vec![1u8; 32]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Writes a signature in the legacy one-directory-per-epoch layout
+    async fn write_legacy(storage: &FilesystemSignatureStorage, epoch: u64, digest: u8) -> Vec<u8> {
+        let bytes =
+            bincode::encode_to_vec(sign_test_epoch(epoch, digest).await, BINCODE_CONFIG).unwrap();
+        std::fs::create_dir_all(storage.epoch_path(&epoch)).unwrap();
+        std::fs::write(storage.epoch_sig_path(&epoch), &bytes).unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_recover_quarantines_truncated_signature() {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        let root_path = root.to_string_lossy().to_string();
+        let mut storage = FilesystemSignatureStorage::new(&root_path).unwrap();
+        write_legacy(&storage, 1, 1).await;
+        let bytes = write_legacy(&storage, 2, 1).await;
+
+        // Simulate a signature truncated by a crash and a leftover temporary file
+        std::fs::write(storage.epoch_sig_path(&2), &bytes[..bytes.len() / 2]).unwrap();
+        std::fs::write(root.join("1").join(".sig.1234.tmp"), &bytes).unwrap();
+
+        let report = FilesystemSignatureStorage::recover(&root_path).unwrap();
+        assert_eq!(report.removed_temp_files.len(), 1);
+        assert_eq!(report.quarantined_files.len(), 1);
+        assert_eq!(report.migrated_signatures, 1);
+
+        assert!(storage.has_signature(&1).await.unwrap());
+        assert!(!storage.has_signature(&2).await.unwrap());
+        // The epoch can be signed again after quarantine
+        storage
+            .set_signature(&2, sign_test_epoch(2, 1).await)
+            .await
+            .unwrap();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recover_removes_segment_file_without_header() {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        let root_path = root.to_string_lossy().to_string();
+        let mut storage = FilesystemSignatureStorage::new(&root_path).unwrap();
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+
+        // Simulate a crash right after creating the files of a new segment
+        let segments = root.join("segments");
+        let next_segment = crate::storage::signatures::segment_store::EPOCHS_PER_SEGMENT;
+        std::fs::write(segments.join("0000000001.idx"), b"").unwrap();
+        std::fs::write(segments.join("0000000001.dat"), b"AKDW").unwrap();
+
+        let report = FilesystemSignatureStorage::recover(&root_path).unwrap();
+        assert_eq!(report.removed_temp_files.len(), 2);
+        assert!(storage.has_signature(&1).await.unwrap());
+        storage
+            .set_signature(&next_segment, sign_test_epoch(next_segment, 1).await)
+            .await
+            .unwrap();
+        assert!(storage.has_signature(&next_segment).await.unwrap());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_legacy_layout_is_read_and_migrated() {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        let root_path = root.to_string_lossy().to_string();
+        let mut storage = FilesystemSignatureStorage::new(&root_path).unwrap();
+        for epoch in [1, 2, 3] {
+            write_legacy(&storage, epoch, 1).await;
+        }
+        storage
+            .set_signature(&4, sign_test_epoch(4, 1).await)
+            .await
+            .unwrap();
+
+        // Legacy signatures are readable and stay write-once before migration
+        assert!(storage.has_signature(&2).await.unwrap());
+        assert_eq!(storage.count_signatures().await.unwrap(), 4);
+        assert!(matches!(
+            storage.set_signature(&2, sign_test_epoch(2, 2).await).await,
+            Err(SignatureRepositoryError::SignatureConflict { epoch: 2, .. })
+        ));
+
+        // Simulate a migration interrupted after packing epoch 1 but before removing its file
+        storage
+            .segments
+            .insert_new(1, &std::fs::read(storage.epoch_sig_path(&1)).unwrap())
+            .unwrap();
+        assert_eq!(storage.count_signatures().await.unwrap(), 4);
+
+        let report = FilesystemSignatureStorage::recover(&root_path).unwrap();
+        assert_eq!(report.migrated_signatures, 3);
+        for epoch in [1, 2, 3] {
+            assert!(!root.join(epoch.to_string()).exists());
+        }
+        assert_eq!(
+            storage.list_epochs(0..=u64::MAX, 10).await.unwrap(),
+            vec![1, 2, 3, 4]
+        );
+        let stored = storage.get_signature(&3).await.unwrap().unwrap();
+        assert_eq!(stored.epoch(), Epoch::new(3));
+
+        // Recovery is idempotent once everything is migrated
+        let report = FilesystemSignatureStorage::recover(&root_path).unwrap();
+        assert!(report.is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_range_queries() {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        let mut storage = FilesystemSignatureStorage::new(&root.to_string_lossy()).unwrap();
+        assert_eq!(storage.latest_epoch().await.unwrap(), None);
+
+        for epoch in [1, 2, 10, 11] {
+            storage
+                .set_signature(&epoch, sign_test_epoch(epoch, 1).await)
+                .await
+                .unwrap();
+        }
+        // An epoch directory without a signature is ignored
+        std::fs::create_dir_all(storage.epoch_path(&12)).unwrap();
+        write_legacy(&storage, 5, 1).await;
+
+        assert_eq!(storage.latest_epoch().await.unwrap(), Some(11));
+        assert_eq!(storage.count_signatures().await.unwrap(), 5);
+        // Packed and legacy epochs are merged in numeric order
+        assert_eq!(
+            storage.list_epochs(0..=u64::MAX, 3).await.unwrap(),
+            vec![1, 2, 5]
+        );
+        assert_eq!(
+            storage.list_epochs(3..=u64::MAX, 3).await.unwrap(),
+            vec![5, 10, 11]
+        );
+
+        let signatures = storage.get_signatures(2..=10, 10).await.unwrap();
+        let epochs: Vec<_> = signatures.iter().map(|s| s.epoch()).collect();
+        assert_eq!(epochs, vec![Epoch::new(2), Epoch::new(5), Epoch::new(10)]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
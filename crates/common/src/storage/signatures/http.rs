@@ -0,0 +1,46 @@
+//! Helpers shared by the clients of the object storage REST APIs
+
+use std::fmt::Debug;
+
+use reqwest::{StatusCode, header::HeaderValue};
+
+/// A credential that `Debug` never prints
+#[derive(Clone, PartialEq)]
+pub struct Secret<T>(pub T);
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Errors of a client that can report a response with a status it did not expect
+pub(super) trait UnexpectedStatusError {
+    fn unexpected_status(status: StatusCode, body: String) -> Self;
+}
+
+/// Builds a header value the client computed itself, which is always visible ASCII
+pub(super) fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).expect("Object storage header values are always valid ASCII")
+}
+
+/// Reads the body of an unexpected response into the client's error, so the reason is logged
+pub(super) async fn unexpected_status<E: UnexpectedStatusError>(
+    status: StatusCode,
+    response: reqwest::Response,
+) -> E {
+    let body = response.text().await.unwrap_or_default();
+    E::unexpected_status(status, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_is_not_printed() {
+        let secret = Secret("account-key".to_string());
+        assert_eq!(format!("{secret:?}"), "<redacted>");
+        assert_eq!(format!("{:?}", Some(secret)), "Some(<redacted>)");
+    }
+}
@@ -1,16 +1,19 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
+    ops::RangeInclusive,
     sync::{Arc, RwLock},
 };
 
 use crate::{
+    Heartbeat,
     epoch_signature::EpochSignature,
-    storage::signatures::{SignatureRepository, SignatureRepositoryError},
+    storage::signatures::{SignatureRepository, SignatureRepositoryError, ensure_same_digest},
 };
 
 #[derive(Clone, Debug)]
 pub struct InMemorySignatureStorage {
-    signatures: Arc<RwLock<HashMap<u64, EpochSignature>>>,
+    signatures: Arc<RwLock<BTreeMap<u64, EpochSignature>>>,
+    heartbeat: Arc<RwLock<Option<Heartbeat>>>,
 }
 
 impl Default for InMemorySignatureStorage {
@@ -22,7 +25,8 @@ impl Default for InMemorySignatureStorage {
 impl InMemorySignatureStorage {
     pub fn new() -> Self {
         InMemorySignatureStorage {
-            signatures: Arc::new(RwLock::new(HashMap::new())),
+            signatures: Arc::new(RwLock::new(BTreeMap::new())),
+            heartbeat: Arc::new(RwLock::new(None)),
         }
     }
 }
@@ -50,10 +54,65 @@ impl SignatureRepository for InMemorySignatureStorage {
         epoch: &u64,
         signature: EpochSignature,
     ) -> Result<(), SignatureRepositoryError> {
-        self.signatures
-            .write()
+        let mut signatures = self.signatures.write().expect("Poisoned signature storage");
+        match signatures.get(epoch) {
+            Some(existing) => ensure_same_digest(epoch, existing, &signature),
+            None => {
+                signatures.insert(*epoch, signature);
+                Ok(())
+            }
+        }
+    }
+
+    async fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, SignatureRepositoryError> {
+        let signatures = self.signatures.read().expect("Poisoned signature storage");
+        Ok(signatures
+            .range(range)
+            .map(|(epoch, _)| *epoch)
+            .take(limit)
+            .collect())
+    }
+
+    async fn get_signatures(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<EpochSignature>, SignatureRepositoryError> {
+        let signatures = self.signatures.read().expect("Poisoned signature storage");
+        Ok(signatures
+            .range(range)
+            .map(|(_, signature)| signature.clone())
+            .take(limit)
+            .collect())
+    }
+
+    async fn latest_epoch(&self) -> Result<Option<u64>, SignatureRepositoryError> {
+        let signatures = self.signatures.read().expect("Poisoned signature storage");
+        Ok(signatures.last_key_value().map(|(epoch, _)| *epoch))
+    }
+
+    async fn count_signatures(&self) -> Result<u64, SignatureRepositoryError> {
+        let signatures = self.signatures.read().expect("Poisoned signature storage");
+        Ok(signatures.len() as u64)
+    }
+
+    async fn get_heartbeat(&self) -> Result<Option<Heartbeat>, SignatureRepositoryError> {
+        Ok(self
+            .heartbeat
+            .read()
             .expect("Poisoned signature storage")
-            .insert(*epoch, signature);
+            .clone())
+    }
+
+    async fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<(), SignatureRepositoryError> {
+        *self.heartbeat.write().expect("Poisoned signature storage") = Some(heartbeat);
         Ok(())
     }
 }
@@ -0,0 +1,642 @@
+use std::{collections::BTreeSet, future::Future, ops::RangeInclusive, pin::Pin, time::Duration};
+
+use futures_util::future;
+use tracing::{debug, instrument, trace, warn};
+
+use crate::{
+    EpochSignature, Heartbeat,
+    storage::signatures::{
+        SignatureRepository, SignatureRepositoryError, SignatureStorage, ensure_same_digest,
+    },
+};
+
+/// Number of epochs compared per batch by [`MirroredSignatureStorage::reconcile`]
+const RECONCILE_BATCH_SIZE: usize = 1000;
+
+type ReplicaFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, SignatureRepositoryError>> + Send + 'a>>;
+
+/// Boxes a replica call. Replicas may themselves be [`SignatureStorage`], so their futures are
+/// boxed to keep the future types finite.
+fn boxed<'a, T>(
+    future: impl Future<Output = Result<T, SignatureRepositoryError>> + Send + 'a,
+) -> ReplicaFuture<'a, T> {
+    Box::pin(future)
+}
+
+/// Writes every signature to several replicas and reads from the first replica that has it.
+///
+/// Writes succeed once `write_quorum` replicas have stored the signature. Replicas that failed are
+/// left for [`MirroredSignatureStorage::reconcile`] to repair. A conflicting signature on any replica
+/// fails the write regardless of the quorum, since signatures are write-once. The existing copies
+/// are read before writing, so a conflict found then leaves every replica untouched.
+#[derive(Clone, Debug)]
+pub struct MirroredSignatureStorage<S = SignatureStorage> {
+    /// Replicas in read fallback order
+    replicas: Vec<S>,
+    write_quorum: usize,
+    reconcile_interval: Option<Duration>,
+    /// Highest epoch compared by the last complete [`MirroredSignatureStorage::reconcile`] pass
+    reconciled_through: Option<u64>,
+    /// Epochs whose last comparison left a disagreement, an unreadable copy or a failed repair
+    unresolved_epochs: BTreeSet<u64>,
+}
+
+/// Outcome of a [`MirroredSignatureStorage::reconcile`] pass
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReconcileReport {
+    /// Epochs stored on at least one replica
+    pub checked_epochs: u64,
+    /// Signatures copied to replicas that were missing them
+    pub repaired_copies: u64,
+    /// Copies that could not be read, and so were neither compared nor repaired
+    pub unreadable_copies: u64,
+    /// Whether the latest heartbeat was copied to replicas with a missing or older one
+    pub repaired_heartbeat: bool,
+    /// Epochs for which replicas store signatures over different digests
+    pub disagreements: Vec<DigestDisagreement>,
+}
+
+/// Replicas storing signatures over different digests for the same epoch. Never repaired
+/// automatically, because signatures are write-once and it is unknown which copy is genuine.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DigestDisagreement {
+    pub epoch: u64,
+    /// Hex digest stored by each replica, in replica order. `None` if the replica has no readable copy.
+    pub digests: Vec<Option<String>>,
+}
+
+impl<S: SignatureRepository> MirroredSignatureStorage<S> {
+    pub fn new(
+        replicas: Vec<S>,
+        write_quorum: usize,
+        reconcile_interval: Option<Duration>,
+    ) -> Result<Self, SignatureRepositoryError> {
+        if replicas.is_empty() || write_quorum == 0 || write_quorum > replicas.len() {
+            return Err(SignatureRepositoryError::InvalidReplicaConfiguration(
+                format!(
+                    "write quorum must be between 1 and the number of replicas ({}), got {write_quorum}",
+                    replicas.len()
+                ),
+            ));
+        }
+        Ok(Self {
+            replicas,
+            write_quorum,
+            reconcile_interval,
+            reconciled_through: None,
+            unresolved_epochs: BTreeSet::new(),
+        })
+    }
+
+    pub fn replicas(&self) -> &[S] {
+        &self.replicas
+    }
+
+    pub fn write_quorum(&self) -> usize {
+        self.write_quorum
+    }
+
+    /// How often the auditor should run [`MirroredSignatureStorage::reconcile`], if at all
+    pub fn reconcile_interval(&self) -> Option<Duration> {
+        self.reconcile_interval
+    }
+
+    /// Checks that at least `write_quorum` replicas succeeded, surfacing conflicts first
+    fn check_quorum(
+        &self,
+        epoch: Option<u64>,
+        results: Vec<Result<(), SignatureRepositoryError>>,
+    ) -> Result<(), SignatureRepositoryError> {
+        let mut accepted = Vec::new();
+        let mut conflict = None;
+        let mut errors = Vec::new();
+        for (replica, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(()) => accepted.push(replica),
+                Err(e @ SignatureRepositoryError::SignatureConflict { .. }) => {
+                    conflict.get_or_insert((replica, e));
+                }
+                Err(e) => {
+                    warn!(replica, epoch, error = %e, "Replica write failed");
+                    errors.push(format!("replica {replica}: {e}"));
+                }
+            }
+        }
+        if let Some((replica, e)) = conflict {
+            // Only a write racing this one gets here, since existing copies are checked first
+            warn!(
+                replica,
+                epoch,
+                ?accepted,
+                "Replica holds a conflicting signature that other replicas accepted"
+            );
+            return Err(e);
+        }
+
+        let succeeded = self.replicas.len() - errors.len();
+        if succeeded < self.write_quorum {
+            return Err(SignatureRepositoryError::WriteQuorumNotReached {
+                succeeded,
+                required: self.write_quorum,
+                errors: errors.join("; "),
+            });
+        }
+        Ok(())
+    }
+
+    /// Merges a read across replicas: returns the first successful answer accepted by `found`,
+    /// otherwise the last successful answer, or the first error if no replica answered.
+    async fn read_with_fallback<'a, T: 'a>(
+        &'a self,
+        read: impl Fn(&'a S) -> ReplicaFuture<'a, T>,
+        found: impl Fn(&T) -> bool,
+    ) -> Result<T, SignatureRepositoryError> {
+        let mut first_error = None;
+        let mut fallback = None;
+        for (replica, storage) in self.replicas.iter().enumerate() {
+            match read(storage).await {
+                Ok(value) if found(&value) => return Ok(value),
+                Ok(value) => {
+                    trace!(
+                        replica,
+                        "Replica has no value, falling back to the next one"
+                    );
+                    fallback = Some(value);
+                }
+                Err(e) => {
+                    warn!(replica, error = %e, "Replica read failed, falling back to the next one");
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        // A missing value is only trusted if every replica answered
+        match first_error {
+            Some(e) => Err(e),
+            None => fallback.ok_or_else(|| {
+                SignatureRepositoryError::InvalidReplicaConfiguration("no replicas".to_string())
+            }),
+        }
+    }
+
+    /// Reads from all replicas, failing only if none of them answered
+    async fn read_all<'a, T: 'a>(
+        &'a self,
+        read: impl Fn(&'a S) -> ReplicaFuture<'a, T>,
+    ) -> Result<Vec<T>, SignatureRepositoryError> {
+        let results = future::join_all(self.replicas.iter().map(read)).await;
+        let mut values = Vec::new();
+        let mut first_error = None;
+        for (replica, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(value) => values.push(value),
+                Err(e) => {
+                    warn!(replica, error = %e, "Replica read failed");
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) if values.is_empty() => Err(e),
+            _ => Ok(values),
+        }
+    }
+
+    /// Compares the replicas and copies signatures to replicas that are missing them, as well as
+    /// the newest heartbeat to replicas with a missing or older one.
+    ///
+    /// Only the epoch listings are compared for epochs already compared by an earlier pass. Their
+    /// copies are read again only if a replica no longer lists them, or if the earlier comparison
+    /// left them unresolved. Digest disagreements and unreadable copies are reported but left
+    /// untouched.
+    #[instrument(skip_all)]
+    pub async fn reconcile(&mut self) -> Result<ReconcileReport, SignatureRepositoryError> {
+        let mut report = ReconcileReport::default();
+        let mut next_epoch = 0;
+        let mut last_epoch = None;
+        loop {
+            let listings = self.replica_listings(next_epoch..=u64::MAX).await?;
+            let epochs: BTreeSet<u64> = listings.iter().flatten().flatten().copied().collect();
+            let epochs: Vec<u64> = epochs.into_iter().take(RECONCILE_BATCH_SIZE).collect();
+            let Some(&batch_end) = epochs.last() else {
+                break;
+            };
+            for epoch in epochs {
+                report.checked_epochs += 1;
+                let listed_everywhere = listings.iter().all(|listing| {
+                    listing
+                        .as_ref()
+                        .is_some_and(|epochs| epochs.contains(&epoch))
+                });
+                let compared = self
+                    .reconciled_through
+                    .is_some_and(|through| epoch <= through);
+                if listed_everywhere && compared && !self.unresolved_epochs.contains(&epoch) {
+                    continue;
+                }
+                if self.reconcile_epoch(epoch, &mut report).await {
+                    self.unresolved_epochs.remove(&epoch);
+                } else {
+                    self.unresolved_epochs.insert(epoch);
+                }
+            }
+            last_epoch = Some(batch_end);
+            match batch_end.checked_add(1) {
+                Some(epoch) => next_epoch = epoch,
+                None => break,
+            }
+        }
+        self.reconciled_through = last_epoch;
+        report.repaired_heartbeat = self.reconcile_heartbeat().await?;
+        debug!(?report, "Reconciled replicas");
+        Ok(report)
+    }
+
+    /// Lists a batch of epochs of every replica, with `None` for replicas whose listing failed.
+    /// Fails only if every listing failed.
+    ///
+    /// Each listing holds a full batch or reaches the end of the replica, so the first batch of
+    /// their union is covered by every listing.
+    async fn replica_listings(
+        &self,
+        range: RangeInclusive<u64>,
+    ) -> Result<Vec<Option<BTreeSet<u64>>>, SignatureRepositoryError> {
+        let results = future::join_all(
+            self.replicas
+                .iter()
+                .map(|replica| boxed(replica.list_epochs(range.clone(), RECONCILE_BATCH_SIZE))),
+        )
+        .await;
+        let mut listings = Vec::with_capacity(results.len());
+        let mut first_error = None;
+        for (replica, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(epochs) => listings.push(Some(epochs.into_iter().collect())),
+                Err(e) => {
+                    warn!(replica, error = %e, "Replica listing failed");
+                    first_error.get_or_insert(e);
+                    listings.push(None);
+                }
+            }
+        }
+        match first_error {
+            Some(e) if listings.iter().all(Option::is_none) => Err(e),
+            _ => Ok(listings),
+        }
+    }
+
+    /// Compares the copies of `epoch` and repairs missing ones. Returns whether every replica now
+    /// holds the same signature.
+    async fn reconcile_epoch(&mut self, epoch: u64, report: &mut ReconcileReport) -> bool {
+        let copies = future::join_all(
+            self.replicas
+                .iter()
+                .map(|replica| boxed(replica.get_signature(&epoch))),
+        )
+        .await;
+
+        let mut source = None;
+        let mut resolved = true;
+        let mut digests = Vec::with_capacity(copies.len());
+        for (replica, copy) in copies.iter().enumerate() {
+            match copy {
+                Ok(Some(signature)) => {
+                    digests.push(Some(signature.digest_hex()));
+                    source.get_or_insert(signature);
+                }
+                Ok(None) => digests.push(None),
+                Err(e) => {
+                    warn!(replica, epoch, error = %e, "Replica copy is unreadable");
+                    report.unreadable_copies += 1;
+                    resolved = false;
+                    digests.push(None);
+                }
+            }
+        }
+
+        let distinct: BTreeSet<_> = digests.iter().flatten().collect();
+        if distinct.len() > 1 {
+            report
+                .disagreements
+                .push(DigestDisagreement { epoch, digests });
+            return false;
+        }
+        let Some(source) = source.cloned() else {
+            return false;
+        };
+
+        for (replica, copy) in copies.into_iter().enumerate() {
+            if !matches!(copy, Ok(None)) {
+                continue;
+            }
+            match self.replicas[replica]
+                .set_signature(&epoch, source.clone())
+                .await
+            {
+                Ok(()) => {
+                    debug!(replica, epoch, "Repaired missing signature copy");
+                    report.repaired_copies += 1;
+                }
+                Err(e) => {
+                    warn!(replica, epoch, error = %e, "Failed to repair signature copy");
+                    resolved = false;
+                }
+            }
+        }
+        resolved
+    }
+
+    /// Copies the newest heartbeat to replicas whose heartbeat is missing or older
+    async fn reconcile_heartbeat(&mut self) -> Result<bool, SignatureRepositoryError> {
+        let heartbeats = future::join_all(
+            self.replicas
+                .iter()
+                .map(|replica| boxed(replica.get_heartbeat())),
+        )
+        .await;
+        let Some(newest) = heartbeats
+            .iter()
+            .filter_map(|heartbeat| heartbeat.as_ref().ok().cloned().flatten())
+            .max_by_key(Heartbeat::observed_at)
+        else {
+            return Ok(false);
+        };
+
+        let mut repaired = false;
+        for (replica, heartbeat) in heartbeats.into_iter().enumerate() {
+            let stale = match heartbeat {
+                Ok(Some(heartbeat)) => heartbeat.observed_at() < newest.observed_at(),
+                Ok(None) => true,
+                Err(_) => false,
+            };
+            if stale {
+                self.replicas[replica].set_heartbeat(newest.clone()).await?;
+                repaired = true;
+            }
+        }
+        Ok(repaired)
+    }
+}
+
+impl<S: SignatureRepository> SignatureRepository for MirroredSignatureStorage<S> {
+    async fn has_signature(&self, epoch: &u64) -> Result<bool, SignatureRepositoryError> {
+        self.read_with_fallback(
+            |replica| boxed(replica.has_signature(epoch)),
+            |found| *found,
+        )
+        .await
+    }
+
+    async fn get_signature(
+        &self,
+        epoch: &u64,
+    ) -> Result<Option<EpochSignature>, SignatureRepositoryError> {
+        self.read_with_fallback(
+            |replica| boxed(replica.get_signature(epoch)),
+            Option::is_some,
+        )
+        .await
+    }
+
+    /// Reads the existing copies first and fails on a conflicting one before writing anywhere.
+    /// Replicas that already hold the signature count towards the quorum without being written.
+    /// A replica whose copy cannot be read is written anyway, and reports a conflict itself.
+    async fn set_signature(
+        &mut self,
+        epoch: &u64,
+        signature: EpochSignature,
+    ) -> Result<(), SignatureRepositoryError> {
+        let existing = future::join_all(
+            self.replicas
+                .iter()
+                .map(|replica| boxed(replica.get_signature(epoch))),
+        )
+        .await;
+        let mut stored = Vec::with_capacity(existing.len());
+        for (replica, copy) in existing.into_iter().enumerate() {
+            match copy {
+                Ok(Some(existing)) => {
+                    ensure_same_digest(epoch, &existing, &signature)?;
+                    stored.push(true);
+                }
+                Ok(None) => stored.push(false),
+                Err(e) => {
+                    warn!(replica, epoch, error = %e, "Replica copy is unreadable, writing it anyway");
+                    stored.push(false);
+                }
+            }
+        }
+
+        let results = future::join_all(self.replicas.iter_mut().zip(&stored).map(
+            |(replica, &stored)| {
+                if stored {
+                    boxed(future::ok(()))
+                } else {
+                    boxed(replica.set_signature(epoch, signature.clone()))
+                }
+            },
+        ))
+        .await;
+        self.check_quorum(Some(*epoch), results)
+    }
+
+    async fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, SignatureRepositoryError> {
+        let listings = self
+            .read_all(|replica| boxed(replica.list_epochs(range.clone(), limit)))
+            .await?;
+        let epochs: BTreeSet<u64> = listings.into_iter().flatten().collect();
+        Ok(epochs.into_iter().take(limit).collect())
+    }
+
+    async fn latest_epoch(&self) -> Result<Option<u64>, SignatureRepositoryError> {
+        let latest = self
+            .read_all(|replica| boxed(replica.latest_epoch()))
+            .await?;
+        Ok(latest.into_iter().flatten().max())
+    }
+
+    /// Returns the highest count reported by any replica, since counting the union of all replicas
+    /// would require listing every epoch.
+    async fn count_signatures(&self) -> Result<u64, SignatureRepositoryError> {
+        let counts = self
+            .read_all(|replica| boxed(replica.count_signatures()))
+            .await?;
+        Ok(counts.into_iter().max().unwrap_or_default())
+    }
+
+    async fn get_heartbeat(&self) -> Result<Option<Heartbeat>, SignatureRepositoryError> {
+        let heartbeats = self
+            .read_all(|replica| boxed(replica.get_heartbeat()))
+            .await?;
+        Ok(heartbeats
+            .into_iter()
+            .flatten()
+            .max_by_key(Heartbeat::observed_at))
+    }
+
+    async fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<(), SignatureRepositoryError> {
+        let results = future::join_all(
+            self.replicas
+                .iter_mut()
+                .map(|replica| boxed(replica.set_heartbeat(heartbeat.clone()))),
+        )
+        .await;
+        self.check_quorum(None, results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::signatures::{FilesystemSignatureStorage, InMemorySignatureStorage},
+        testing::{MockSignatureStorage, sign_test_epoch},
+    };
+
+    #[test]
+    fn test_new_rejects_invalid_quorum() {
+        let replicas = || {
+            vec![
+                InMemorySignatureStorage::new(),
+                InMemorySignatureStorage::new(),
+            ]
+        };
+        assert!(MirroredSignatureStorage::new(replicas(), 0, None).is_err());
+        assert!(MirroredSignatureStorage::new(replicas(), 3, None).is_err());
+        assert!(
+            MirroredSignatureStorage::<InMemorySignatureStorage>::new(vec![], 1, None).is_err()
+        );
+        assert!(MirroredSignatureStorage::new(replicas(), 2, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_write_quorum() {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        let filesystem = FilesystemSignatureStorage::new(&root.to_string_lossy()).unwrap();
+        // Make every write to the filesystem replica fail
+        std::fs::remove_dir_all(&root).unwrap();
+        std::fs::write(&root, b"not a directory").unwrap();
+        let replicas = || {
+            vec![
+                SignatureStorage::InMemory(InMemorySignatureStorage::new()),
+                SignatureStorage::Filesystem(filesystem.clone()),
+            ]
+        };
+
+        let mut storage = MirroredSignatureStorage::new(replicas(), 1, None).unwrap();
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+        assert!(storage.has_signature(&1).await.unwrap());
+
+        let mut storage = MirroredSignatureStorage::new(replicas(), 2, None).unwrap();
+        let result = storage.set_signature(&1, sign_test_epoch(1, 1).await).await;
+        assert!(matches!(
+            result,
+            Err(SignatureRepositoryError::WriteQuorumNotReached {
+                succeeded: 1,
+                required: 2,
+                ..
+            })
+        ));
+
+        std::fs::remove_file(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conflict_fails_regardless_of_quorum() {
+        let first = InMemorySignatureStorage::new();
+        let mut second = InMemorySignatureStorage::new();
+        second
+            .set_signature(&1, sign_test_epoch(1, 2).await)
+            .await
+            .unwrap();
+
+        let mut storage =
+            MirroredSignatureStorage::new(vec![first.clone(), second], 1, None).unwrap();
+        let result = storage.set_signature(&1, sign_test_epoch(1, 1).await).await;
+        assert!(matches!(
+            result,
+            Err(SignatureRepositoryError::SignatureConflict { epoch: 1, .. })
+        ));
+        // The conflict is found before writing, so no replica stores the rejected signature
+        assert!(!first.has_signature(&1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_reads_fall_back_in_order() {
+        let mut primary = MockSignatureStorage::new();
+        let mut secondary = MockSignatureStorage::new();
+        primary.add_test_signature(1, sign_test_epoch(1, 1).await);
+        secondary.add_test_signature(1, sign_test_epoch(1, 2).await);
+        secondary.add_test_signature(2, sign_test_epoch(2, 1).await);
+
+        let storage = MirroredSignatureStorage::new(vec![primary, secondary], 2, None).unwrap();
+        // The primary's copy wins, and missing epochs are read from the secondary
+        let signature = storage.get_signature(&1).await.unwrap().unwrap();
+        assert_eq!(signature.digest(), vec![1u8; 32]);
+        assert!(storage.get_signature(&2).await.unwrap().is_some());
+        assert!(storage.get_signature(&3).await.unwrap().is_none());
+        assert_eq!(storage.list_epochs(0..=10, 10).await.unwrap(), vec![1, 2]);
+        assert_eq!(storage.latest_epoch().await.unwrap(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_repairs_and_reports() {
+        let mut first = MockSignatureStorage::new();
+        let mut second = MockSignatureStorage::new();
+        first.add_test_signature(1, sign_test_epoch(1, 1).await);
+        second.add_test_signature(1, sign_test_epoch(1, 1).await);
+        first.add_test_signature(2, sign_test_epoch(2, 1).await);
+        second.add_test_signature(3, sign_test_epoch(3, 1).await);
+        first.add_test_signature(4, sign_test_epoch(4, 1).await);
+        second.add_test_signature(4, sign_test_epoch(4, 2).await);
+
+        let mut storage =
+            MirroredSignatureStorage::new(vec![first.clone(), second.clone()], 2, None).unwrap();
+        let report = storage.reconcile().await.unwrap();
+
+        assert_eq!(report.checked_epochs, 4);
+        assert_eq!(report.repaired_copies, 2);
+        assert_eq!(
+            report.disagreements,
+            vec![DigestDisagreement {
+                epoch: 4,
+                digests: vec![Some(hex::encode([1u8; 32])), Some(hex::encode([2u8; 32]))],
+            }]
+        );
+        assert_eq!(first.get_stored_epochs(), vec![1, 2, 3, 4]);
+        assert_eq!(second.get_stored_epochs(), vec![1, 2, 3, 4]);
+
+        // A second pass has nothing left to repair
+        let report = storage.reconcile().await.unwrap();
+        assert_eq!(report.repaired_copies, 0);
+        assert_eq!(report.disagreements.len(), 1);
+
+        // Later passes read only the copies of unresolved epochs and of epochs a replica no longer
+        // lists, so the replaced copy of epoch 1 goes unread
+        second.remove_test_signature(1);
+        second.add_test_signature(1, sign_test_epoch(1, 2).await);
+        second.remove_test_signature(2);
+        let report = storage.reconcile().await.unwrap();
+        assert_eq!(report.checked_epochs, 4);
+        assert_eq!(report.repaired_copies, 1);
+        assert_eq!(
+            report
+                .disagreements
+                .iter()
+                .map(|disagreement| disagreement.epoch)
+                .collect::<Vec<_>>(),
+            vec![4]
+        );
+        assert_eq!(second.get_stored_epochs(), vec![1, 2, 3, 4]);
+    }
+}
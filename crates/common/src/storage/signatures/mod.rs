@@ -1,12 +1,32 @@
+mod azure_signature_storage;
+mod epoch_names;
 mod filesystem_signature_storage;
+mod http;
 mod in_memory_signature_storage;
+mod mirrored_signature_storage;
+mod s3_signature_storage;
+mod segment_store;
+mod sqlite_signature_storage;
 
+pub use azure_signature_storage::{
+    AZURE_STORAGE_CONNECTION_STRING_ENV, AzureBlobClient, AzureConnectionString,
+    AzureSignatureStorage, AzureStorageError,
+};
 pub use filesystem_signature_storage::FilesystemSignatureStorage;
+pub use http::Secret;
 pub use in_memory_signature_storage::InMemorySignatureStorage;
+pub use mirrored_signature_storage::{
+    DigestDisagreement, MirroredSignatureStorage, ReconcileReport,
+};
+pub use s3_signature_storage::{
+    AWS_ACCESS_KEY_ID_ENV, AWS_SECRET_ACCESS_KEY_ENV, AWS_SESSION_TOKEN_ENV, S3Client,
+    S3Credentials, S3SignatureStorage, S3StorageError,
+};
+pub use sqlite_signature_storage::SqliteSignatureStorage;
 use tracing::instrument;
 
-use crate::EpochSignature;
-use std::{fmt::Debug, future::Future};
+use crate::{EpochSignature, Heartbeat};
+use std::{fmt::Debug, future::Future, ops::RangeInclusive};
 
 pub trait SignatureRepository: Clone + Debug + Send + Sync {
     fn has_signature(
@@ -17,11 +37,55 @@ pub trait SignatureRepository: Clone + Debug + Send + Sync {
         &self,
         epoch: &u64,
     ) -> impl Future<Output = Result<Option<EpochSignature>, SignatureRepositoryError>> + Send;
+    /// Stores the signature for an epoch. Signatures are write-once: storing a signature with the same
+    /// digest as the existing one is a no-op, and storing one with a different digest fails with
+    /// [`SignatureRepositoryError::SignatureConflict`].
     fn set_signature(
         &mut self,
         epoch: &u64,
         signature: EpochSignature,
     ) -> impl Future<Output = Result<(), SignatureRepositoryError>> + Send;
+    /// Lists the epochs with a stored signature within `range` in ascending order, returning at most
+    /// `limit` epochs. To page through all epochs, start the next range after the last epoch returned.
+    fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<u64>, SignatureRepositoryError>> + Send;
+    /// Retrieves the stored signatures within `range` in ascending epoch order, returning at most
+    /// `limit` signatures.
+    fn get_signatures(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<EpochSignature>, SignatureRepositoryError>> + Send {
+        async move {
+            let mut signatures = Vec::new();
+            for epoch in self.list_epochs(range, limit).await? {
+                if let Some(signature) = self.get_signature(&epoch).await? {
+                    signatures.push(signature);
+                }
+            }
+            Ok(signatures)
+        }
+    }
+    /// Returns the highest epoch with a stored signature, if any.
+    fn latest_epoch(
+        &self,
+    ) -> impl Future<Output = Result<Option<u64>, SignatureRepositoryError>> + Send;
+    /// Counts the stored signatures.
+    fn count_signatures(
+        &self,
+    ) -> impl Future<Output = Result<u64, SignatureRepositoryError>> + Send;
+    /// Retrieves the most recently stored heartbeat for this namespace, if any.
+    fn get_heartbeat(
+        &self,
+    ) -> impl Future<Output = Result<Option<Heartbeat>, SignatureRepositoryError>> + Send;
+    /// Stores a heartbeat, replacing any previously stored heartbeat for this namespace.
+    fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> impl Future<Output = Result<(), SignatureRepositoryError>> + Send;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -32,31 +96,87 @@ pub enum SignatureRepositoryError {
     BincodeError(#[from] bincode::error::EncodeError),
     #[error("Bincode deserialization error: {0}")]
     BincodeDecodeError(#[from] bincode::error::DecodeError),
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("Namespace state error: {0}")]
+    NamespaceStateError(String),
+    #[error(
+        "Conflicting signature for epoch {epoch}: stored digest {existing_digest}, new digest {new_digest}"
+    )]
+    SignatureConflict {
+        epoch: u64,
+        existing_digest: String,
+        new_digest: String,
+    },
+    #[error("{0}")]
+    AzureError(#[from] AzureStorageError),
+    #[error("{0}")]
+    S3Error(#[from] S3StorageError),
+    #[error(
+        "Write quorum not reached: {succeeded} of {required} required replicas succeeded ({errors})"
+    )]
+    WriteQuorumNotReached {
+        succeeded: usize,
+        required: usize,
+        errors: String,
+    },
+    #[error("Invalid replica configuration: {0}")]
+    InvalidReplicaConfiguration(String),
+}
+
+/// Checks a signature that is about to be stored against the one already stored for the epoch.
+///
+/// Returns `Ok(())` if both sign the same digest, so that re-storing a signature is idempotent.
+pub(crate) fn ensure_same_digest(
+    epoch: &u64,
+    existing: &EpochSignature,
+    new: &EpochSignature,
+) -> Result<(), SignatureRepositoryError> {
+    if existing.digest() == new.digest() {
+        return Ok(());
+    }
+    Err(SignatureRepositoryError::SignatureConflict {
+        epoch: *epoch,
+        existing_digest: existing.digest_hex(),
+        new_digest: new.digest_hex(),
+    })
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum SignatureStorageFileError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Corrupt signature segment: {0}")]
+    CorruptSegment(String),
+    #[error("Unsupported signature segment format version {version} in {path}")]
+    UnsupportedFormatVersion { path: String, version: u32 },
 }
 
 /// Enum wrapper to support different signature storage implementations
 ///
 /// This enum allows applications to work with different storage backends
-/// for epoch signatures (Filesystem, InMemory, or future Azure support)
-/// based on configuration.
+/// for epoch signatures (Filesystem, SQLite, Azure Blob, S3, or InMemory)
+/// based on configuration, or mirror signatures across several of them.
 #[derive(Clone, Debug)]
 pub enum SignatureStorage {
+    Azure(AzureSignatureStorage),
     Filesystem(FilesystemSignatureStorage),
     InMemory(InMemorySignatureStorage),
+    Mirrored(MirroredSignatureStorage),
+    S3(S3SignatureStorage),
+    Sqlite(SqliteSignatureStorage),
 }
 
 impl SignatureRepository for SignatureStorage {
     #[instrument(skip_all, fields(epoch))]
     async fn has_signature(&self, epoch: &u64) -> Result<bool, SignatureRepositoryError> {
         match self {
+            SignatureStorage::Azure(storage) => storage.has_signature(epoch).await,
             SignatureStorage::Filesystem(storage) => storage.has_signature(epoch).await,
             SignatureStorage::InMemory(storage) => storage.has_signature(epoch).await,
+            SignatureStorage::Mirrored(storage) => storage.has_signature(epoch).await,
+            SignatureStorage::S3(storage) => storage.has_signature(epoch).await,
+            SignatureStorage::Sqlite(storage) => storage.has_signature(epoch).await,
         }
     }
 
@@ -66,8 +186,12 @@ impl SignatureRepository for SignatureStorage {
         epoch: &u64,
     ) -> Result<Option<crate::EpochSignature>, SignatureRepositoryError> {
         match self {
+            SignatureStorage::Azure(storage) => storage.get_signature(epoch).await,
             SignatureStorage::Filesystem(storage) => storage.get_signature(epoch).await,
             SignatureStorage::InMemory(storage) => storage.get_signature(epoch).await,
+            SignatureStorage::Mirrored(storage) => storage.get_signature(epoch).await,
+            SignatureStorage::S3(storage) => storage.get_signature(epoch).await,
+            SignatureStorage::Sqlite(storage) => storage.get_signature(epoch).await,
         }
     }
 
@@ -78,8 +202,95 @@ impl SignatureRepository for SignatureStorage {
         signature: crate::EpochSignature,
     ) -> Result<(), SignatureRepositoryError> {
         match self {
+            SignatureStorage::Azure(storage) => storage.set_signature(epoch, signature).await,
             SignatureStorage::Filesystem(storage) => storage.set_signature(epoch, signature).await,
             SignatureStorage::InMemory(storage) => storage.set_signature(epoch, signature).await,
+            SignatureStorage::Mirrored(storage) => storage.set_signature(epoch, signature).await,
+            SignatureStorage::S3(storage) => storage.set_signature(epoch, signature).await,
+            SignatureStorage::Sqlite(storage) => storage.set_signature(epoch, signature).await,
+        }
+    }
+
+    #[instrument(skip_all, fields(start = range.start(), end = range.end(), limit))]
+    async fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, SignatureRepositoryError> {
+        match self {
+            SignatureStorage::Azure(storage) => storage.list_epochs(range, limit).await,
+            SignatureStorage::Filesystem(storage) => storage.list_epochs(range, limit).await,
+            SignatureStorage::InMemory(storage) => storage.list_epochs(range, limit).await,
+            SignatureStorage::Mirrored(storage) => storage.list_epochs(range, limit).await,
+            SignatureStorage::S3(storage) => storage.list_epochs(range, limit).await,
+            SignatureStorage::Sqlite(storage) => storage.list_epochs(range, limit).await,
+        }
+    }
+
+    #[instrument(skip_all, fields(start = range.start(), end = range.end(), limit))]
+    async fn get_signatures(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<EpochSignature>, SignatureRepositoryError> {
+        match self {
+            SignatureStorage::Azure(storage) => storage.get_signatures(range, limit).await,
+            SignatureStorage::Filesystem(storage) => storage.get_signatures(range, limit).await,
+            SignatureStorage::InMemory(storage) => storage.get_signatures(range, limit).await,
+            SignatureStorage::Mirrored(storage) => storage.get_signatures(range, limit).await,
+            SignatureStorage::S3(storage) => storage.get_signatures(range, limit).await,
+            SignatureStorage::Sqlite(storage) => storage.get_signatures(range, limit).await,
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn latest_epoch(&self) -> Result<Option<u64>, SignatureRepositoryError> {
+        match self {
+            SignatureStorage::Azure(storage) => storage.latest_epoch().await,
+            SignatureStorage::Filesystem(storage) => storage.latest_epoch().await,
+            SignatureStorage::InMemory(storage) => storage.latest_epoch().await,
+            SignatureStorage::Mirrored(storage) => storage.latest_epoch().await,
+            SignatureStorage::S3(storage) => storage.latest_epoch().await,
+            SignatureStorage::Sqlite(storage) => storage.latest_epoch().await,
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn count_signatures(&self) -> Result<u64, SignatureRepositoryError> {
+        match self {
+            SignatureStorage::Azure(storage) => storage.count_signatures().await,
+            SignatureStorage::Filesystem(storage) => storage.count_signatures().await,
+            SignatureStorage::InMemory(storage) => storage.count_signatures().await,
+            SignatureStorage::Mirrored(storage) => storage.count_signatures().await,
+            SignatureStorage::S3(storage) => storage.count_signatures().await,
+            SignatureStorage::Sqlite(storage) => storage.count_signatures().await,
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn get_heartbeat(&self) -> Result<Option<Heartbeat>, SignatureRepositoryError> {
+        match self {
+            SignatureStorage::Azure(storage) => storage.get_heartbeat().await,
+            SignatureStorage::Filesystem(storage) => storage.get_heartbeat().await,
+            SignatureStorage::InMemory(storage) => storage.get_heartbeat().await,
+            SignatureStorage::Mirrored(storage) => storage.get_heartbeat().await,
+            SignatureStorage::S3(storage) => storage.get_heartbeat().await,
+            SignatureStorage::Sqlite(storage) => storage.get_heartbeat().await,
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<(), SignatureRepositoryError> {
+        match self {
+            SignatureStorage::Azure(storage) => storage.set_heartbeat(heartbeat).await,
+            SignatureStorage::Filesystem(storage) => storage.set_heartbeat(heartbeat).await,
+            SignatureStorage::InMemory(storage) => storage.set_heartbeat(heartbeat).await,
+            SignatureStorage::Mirrored(storage) => storage.set_heartbeat(heartbeat).await,
+            SignatureStorage::S3(storage) => storage.set_heartbeat(heartbeat).await,
+            SignatureStorage::Sqlite(storage) => storage.set_heartbeat(heartbeat).await,
         }
     }
 }
@@ -0,0 +1,465 @@
+//! Packed on-disk layout for [`FilesystemSignatureStorage`](super::FilesystemSignatureStorage).
+//!
+//! Epochs are grouped into segments of [`EPOCHS_PER_SEGMENT`] consecutive epochs. Each segment has
+//! two files in the `segments` directory:
+//!
+//! - `<segment>.dat`: a header followed by append-only records of `epoch (u64 LE) | length (u32 LE) | payload`
+//! - `<segment>.idx`: a header followed by one fixed-size slot per epoch of `offset (u64 LE) | length (u32 LE)`,
+//!   pointing into the data file. An all-zero slot means the epoch is not stored.
+//!
+//! Looking up an epoch is a single seek into the index and one into the data file. A record is appended
+//! and synced before its slot is written, so a crash can leave at most an unreferenced record behind,
+//! never a slot pointing at missing data.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use tracing::{debug, trace, warn};
+
+use crate::storage::{signatures::SignatureStorageFileError, sync_parent_directory, write_atomic};
+
+/// Number of consecutive epochs stored in one segment
+pub(crate) const EPOCHS_PER_SEGMENT: u64 = 4096;
+/// Current version of the segment file format
+pub(crate) const SEGMENT_FORMAT_VERSION: u32 = 1;
+
+const SEGMENTS_DIRECTORY: &str = "segments";
+const INDEX_MAGIC: &[u8; 8] = b"AKDWIDX\0";
+const DATA_MAGIC: &[u8; 8] = b"AKDWDAT\0";
+/// Magic, format version and epochs per segment
+const HEADER_LEN: u64 = 16;
+const SLOT_LEN: u64 = 12;
+const RECORD_HEADER_LEN: u64 = 12;
+
+type Result<T> = std::result::Result<T, SignatureStorageFileError>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Slot {
+    offset: u64,
+    length: u32,
+}
+
+impl Slot {
+    fn from_bytes(bytes: [u8; SLOT_LEN as usize]) -> Option<Self> {
+        let offset = u64::from_le_bytes(bytes[..8].try_into().expect("slot offset is 8 bytes"));
+        let length = u32::from_le_bytes(bytes[8..].try_into().expect("slot length is 4 bytes"));
+        (length != 0).then_some(Slot { offset, length })
+    }
+
+    fn to_bytes(self) -> [u8; SLOT_LEN as usize] {
+        let mut bytes = [0u8; SLOT_LEN as usize];
+        bytes[..8].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.length.to_le_bytes());
+        bytes
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SegmentStore {
+    directory: PathBuf,
+    /// Serializes appends so two writers in this process never race for the same offset
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl SegmentStore {
+    pub(crate) fn new(root_path: &str) -> Self {
+        Self {
+            directory: Path::new(root_path).join(SEGMENTS_DIRECTORY),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn index_path(&self, segment: u64) -> PathBuf {
+        self.directory.join(format!("{segment:010}.idx"))
+    }
+
+    fn data_path(&self, segment: u64) -> PathBuf {
+        self.directory.join(format!("{segment:010}.dat"))
+    }
+
+    fn slot_position(epoch: u64) -> (u64, u64) {
+        let segment = epoch / EPOCHS_PER_SEGMENT;
+        let slot_offset = HEADER_LEN + (epoch % EPOCHS_PER_SEGMENT) * SLOT_LEN;
+        (segment, slot_offset)
+    }
+
+    /// Lists the segments present on disk, in ascending order
+    fn segments(&self) -> Result<Vec<u64>> {
+        let mut segments = Vec::new();
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(segments),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "idx") {
+                if let Some(segment) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<u64>().ok())
+                {
+                    segments.push(segment);
+                }
+            }
+        }
+        segments.sort_unstable();
+        Ok(segments)
+    }
+
+    fn open_existing(path: &Path, magic: &[u8; 8]) -> Result<Option<File>> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Self::check_header(&mut file, path, magic)?;
+        Ok(Some(file))
+    }
+
+    fn check_header(file: &mut File, path: &Path, magic: &[u8; 8]) -> Result<()> {
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header).map_err(|e| {
+            SignatureStorageFileError::CorruptSegment(format!(
+                "{}: failed to read header: {e}",
+                path.display()
+            ))
+        })?;
+        if &header[..8] != magic {
+            return Err(SignatureStorageFileError::CorruptSegment(format!(
+                "{}: bad magic",
+                path.display()
+            )));
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().expect("version is 4 bytes"));
+        let epochs_per_segment =
+            u32::from_le_bytes(header[12..].try_into().expect("segment size is 4 bytes"));
+        if version != SEGMENT_FORMAT_VERSION || u64::from(epochs_per_segment) != EPOCHS_PER_SEGMENT
+        {
+            return Err(SignatureStorageFileError::UnsupportedFormatVersion {
+                path: path.display().to_string(),
+                version,
+            });
+        }
+        Ok(())
+    }
+
+    /// Opens a segment file for writing, creating it with a header if it does not exist yet
+    fn open_for_write(path: &Path, magic: &[u8; 8]) -> Result<File> {
+        match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(mut file) => {
+                Self::check_header(&mut file, path, magic)?;
+                return Ok(file);
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        std::fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(magic);
+        header.extend_from_slice(&SEGMENT_FORMAT_VERSION.to_le_bytes());
+        header.extend_from_slice(&(EPOCHS_PER_SEGMENT as u32).to_le_bytes());
+        // Write the header atomically so a crash never leaves a segment file without one
+        write_atomic(path, &header)?;
+        debug!(path = %path.display(), "Created signature segment file");
+        Ok(OpenOptions::new().read(true).write(true).open(path)?)
+    }
+
+    fn read_slot(&self, epoch: u64) -> Result<Option<Slot>> {
+        let (segment, slot_offset) = Self::slot_position(epoch);
+        let Some(mut index) = Self::open_existing(&self.index_path(segment), INDEX_MAGIC)? else {
+            return Ok(None);
+        };
+        index.seek(SeekFrom::Start(slot_offset))?;
+        let mut bytes = [0u8; SLOT_LEN as usize];
+        match index.read_exact(&mut bytes) {
+            Ok(()) => Ok(Slot::from_bytes(bytes)),
+            // The index only extends as far as the highest stored slot
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn contains(&self, epoch: u64) -> Result<bool> {
+        Ok(self.read_slot(epoch)?.is_some())
+    }
+
+    /// Reads the payload stored for an epoch
+    pub(crate) fn get(&self, epoch: u64) -> Result<Option<Vec<u8>>> {
+        let Some(slot) = self.read_slot(epoch)? else {
+            return Ok(None);
+        };
+        let segment = epoch / EPOCHS_PER_SEGMENT;
+        let data_path = self.data_path(segment);
+        let corrupt = |reason: String| {
+            SignatureStorageFileError::CorruptSegment(format!(
+                "{}: epoch {epoch}: {reason}",
+                data_path.display()
+            ))
+        };
+        let mut data = Self::open_existing(&data_path, DATA_MAGIC)?
+            .ok_or_else(|| corrupt("index refers to a missing data file".to_string()))?;
+
+        data.seek(SeekFrom::Start(slot.offset))?;
+        let mut record_header = [0u8; RECORD_HEADER_LEN as usize];
+        data.read_exact(&mut record_header)
+            .map_err(|e| corrupt(format!("failed to read record header: {e}")))?;
+        let record_epoch = u64::from_le_bytes(
+            record_header[..8]
+                .try_into()
+                .expect("record epoch is 8 bytes"),
+        );
+        let record_length = u32::from_le_bytes(
+            record_header[8..]
+                .try_into()
+                .expect("record length is 4 bytes"),
+        );
+        if record_epoch != epoch || record_length != slot.length {
+            return Err(corrupt(format!(
+                "index slot does not match record for epoch {record_epoch}"
+            )));
+        }
+
+        let mut payload = vec![0u8; slot.length as usize];
+        data.read_exact(&mut payload)
+            .map_err(|e| corrupt(format!("failed to read record: {e}")))?;
+        trace!(
+            epoch,
+            segment,
+            offset = slot.offset,
+            "Read signature record"
+        );
+        Ok(Some(payload))
+    }
+
+    /// Appends the payload for an epoch. Fails with [`ErrorKind::AlreadyExists`] if the epoch is
+    /// already stored.
+    pub(crate) fn insert_new(&self, epoch: u64, payload: &[u8]) -> Result<()> {
+        let _guard = self.write_lock.lock().expect("Segment write lock poisoned");
+        if self.contains(epoch)? {
+            return Err(std::io::Error::from(ErrorKind::AlreadyExists).into());
+        }
+        let length = u32::try_from(payload.len())
+            .map_err(|_| std::io::Error::new(ErrorKind::InvalidInput, "record too large"))?;
+        let (segment, slot_offset) = Self::slot_position(epoch);
+
+        // Append and sync the record before referencing it from the index
+        let mut data = Self::open_for_write(&self.data_path(segment), DATA_MAGIC)?;
+        let offset = data.seek(SeekFrom::End(0))?;
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN as usize + payload.len());
+        record.extend_from_slice(&epoch.to_le_bytes());
+        record.extend_from_slice(&length.to_le_bytes());
+        record.extend_from_slice(payload);
+        data.write_all(&record)?;
+        data.sync_data()?;
+
+        let mut index = Self::open_for_write(&self.index_path(segment), INDEX_MAGIC)?;
+        index.seek(SeekFrom::Start(slot_offset))?;
+        index.write_all(&Slot { offset, length }.to_bytes())?;
+        index.sync_data()?;
+        trace!(epoch, segment, offset, "Appended signature record");
+        Ok(())
+    }
+
+    /// Reads every slot of a segment index, returning the stored epochs in ascending order
+    fn segment_epochs(&self, segment: u64) -> Result<Vec<u64>> {
+        let Some(mut index) = Self::open_existing(&self.index_path(segment), INDEX_MAGIC)? else {
+            return Ok(Vec::new());
+        };
+        let mut slots = Vec::new();
+        index.read_to_end(&mut slots)?;
+        Ok(slots
+            .chunks_exact(SLOT_LEN as usize)
+            .enumerate()
+            .filter_map(|(position, bytes)| {
+                Slot::from_bytes(bytes.try_into().expect("chunk is a slot"))
+                    .map(|_| segment * EPOCHS_PER_SEGMENT + position as u64)
+            })
+            .collect())
+    }
+
+    /// Lists stored epochs within `range` in ascending order, reading only the segments that overlap it
+    pub(crate) fn list_epochs(
+        &self,
+        range: &RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>> {
+        let first_segment = range.start() / EPOCHS_PER_SEGMENT;
+        let last_segment = range.end() / EPOCHS_PER_SEGMENT;
+        let mut epochs = Vec::new();
+        for segment in self.segments()? {
+            if segment < first_segment || segment > last_segment {
+                continue;
+            }
+            epochs.extend(
+                self.segment_epochs(segment)?
+                    .into_iter()
+                    .filter(|epoch| range.contains(epoch)),
+            );
+            if epochs.len() >= limit {
+                break;
+            }
+        }
+        epochs.truncate(limit);
+        Ok(epochs)
+    }
+
+    pub(crate) fn latest_epoch(&self) -> Result<Option<u64>> {
+        for segment in self.segments()?.into_iter().rev() {
+            if let Some(epoch) = self.segment_epochs(segment)?.last() {
+                return Ok(Some(*epoch));
+            }
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn count(&self) -> Result<u64> {
+        let mut count = 0;
+        for segment in self.segments()? {
+            count += self.segment_epochs(segment)?.len() as u64;
+        }
+        Ok(count)
+    }
+
+    /// Removes segment files too short to hold a header. Headers are written atomically, so such a
+    /// file only appears if it was truncated or copied incompletely outside the auditor. It cannot
+    /// hold a slot or a record, so removing it loses nothing, and keeps it from failing every read of
+    /// the store. Returns the removed files.
+    pub(crate) fn remove_incomplete_files(&self) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(removed),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            let is_segment_file = path
+                .extension()
+                .is_some_and(|extension| extension == "idx" || extension == "dat");
+            if is_segment_file && entry.metadata()?.len() < HEADER_LEN {
+                warn!(path = %path.display(), "Removing segment file left without a header");
+                std::fs::remove_file(&path)?;
+                removed.push(path);
+            }
+        }
+        if let Some(path) = removed.first() {
+            sync_parent_directory(path)?;
+        }
+        Ok(removed)
+    }
+
+    /// Checks the header of every segment file, failing on corrupt or unsupported files
+    pub(crate) fn check_headers(&self) -> Result<()> {
+        for segment in self.segments()? {
+            Self::open_existing(&self.index_path(segment), INDEX_MAGIC)?;
+            Self::open_existing(&self.data_path(segment), DATA_MAGIC)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> (SegmentStore, PathBuf) {
+        let root = std::env::temp_dir().join(format!("akd-watch-{}", uuid::Uuid::new_v4()));
+        (SegmentStore::new(&root.to_string_lossy()), root)
+    }
+
+    #[test]
+    fn test_insert_and_get_across_segments() {
+        let (store, root) = temp_store();
+        let epochs = [
+            0,
+            1,
+            EPOCHS_PER_SEGMENT - 1,
+            EPOCHS_PER_SEGMENT,
+            3 * EPOCHS_PER_SEGMENT + 7,
+        ];
+        for epoch in epochs {
+            store.insert_new(epoch, &epoch.to_le_bytes()).unwrap();
+        }
+
+        for epoch in epochs {
+            assert_eq!(
+                store.get(epoch).unwrap(),
+                Some(epoch.to_le_bytes().to_vec())
+            );
+        }
+        assert_eq!(store.get(2).unwrap(), None);
+        assert_eq!(store.get(10 * EPOCHS_PER_SEGMENT).unwrap(), None);
+        assert_eq!(store.count().unwrap(), epochs.len() as u64);
+        assert_eq!(
+            store.latest_epoch().unwrap(),
+            Some(3 * EPOCHS_PER_SEGMENT + 7)
+        );
+        assert_eq!(
+            store.list_epochs(&(1..=EPOCHS_PER_SEGMENT), 10).unwrap(),
+            vec![1, EPOCHS_PER_SEGMENT - 1, EPOCHS_PER_SEGMENT]
+        );
+        // Two segments with data, plus their index files
+        assert_eq!(std::fs::read_dir(&store.directory).unwrap().count(), 6);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_insert_is_write_once() {
+        let (store, root) = temp_store();
+        store.insert_new(5, b"first").unwrap();
+
+        let error = store.insert_new(5, b"second").unwrap_err();
+        assert!(matches!(
+            error,
+            SignatureStorageFileError::IoError(ref e) if e.kind() == ErrorKind::AlreadyExists
+        ));
+        assert_eq!(store.get(5).unwrap(), Some(b"first".to_vec()));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unreferenced_record_is_ignored() {
+        let (store, root) = temp_store();
+        store.insert_new(1, b"first").unwrap();
+        // Simulate a crash after appending a record but before writing its slot
+        let mut data = OpenOptions::new()
+            .append(true)
+            .open(store.data_path(0))
+            .unwrap();
+        data.write_all(&[2, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0]).unwrap();
+
+        assert_eq!(store.get(2).unwrap(), None);
+        store.insert_new(2, b"second").unwrap();
+        assert_eq!(store.get(2).unwrap(), Some(b"second".to_vec()));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unsupported_version_is_rejected() {
+        let (store, root) = temp_store();
+        store.insert_new(1, b"first").unwrap();
+        let index_path = store.index_path(0);
+        let mut index = std::fs::read(&index_path).unwrap();
+        index[8..12].copy_from_slice(&2u32.to_le_bytes());
+        std::fs::write(&index_path, index).unwrap();
+
+        assert!(matches!(
+            store.get(1),
+            Err(SignatureStorageFileError::UnsupportedFormatVersion { version: 2, .. })
+        ));
+        assert!(store.check_headers().is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
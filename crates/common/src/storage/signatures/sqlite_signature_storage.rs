@@ -0,0 +1,380 @@
+use std::ops::RangeInclusive;
+
+use rusqlite::{OptionalExtension, params};
+use tracing::{instrument, trace};
+
+use crate::{
+    BINCODE_CONFIG, Heartbeat, NamespaceInfo,
+    epoch_signature::EpochSignature,
+    storage::{
+        namespaces::{parse_namespace_info, serialize_namespace_info},
+        signatures::{SignatureRepository, SignatureRepositoryError, ensure_same_digest},
+        sqlite::{SqliteDatabase, epoch_range_to_sql, epoch_to_sql},
+    },
+};
+
+/// Signature storage for a single namespace, backed by the shared SQLite database.
+///
+/// Storing a signature also advances the namespace's `last_verified_epoch` in the same transaction,
+/// so the two can never disagree after a crash.
+#[derive(Clone, Debug)]
+pub struct SqliteSignatureStorage {
+    database: SqliteDatabase,
+    namespace: String,
+}
+
+impl SqliteSignatureStorage {
+    pub fn new(database: SqliteDatabase, namespace: &str) -> Self {
+        Self {
+            database,
+            namespace: namespace.to_string(),
+        }
+    }
+}
+
+impl SignatureRepository for SqliteSignatureStorage {
+    async fn has_signature(&self, epoch: &u64) -> Result<bool, SignatureRepositoryError> {
+        let exists = self
+            .database
+            .connection()
+            .query_row(
+                "SELECT 1 FROM signatures WHERE namespace = ?1 AND epoch = ?2",
+                params![self.namespace, epoch_to_sql(*epoch)?],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(exists)
+    }
+
+    async fn get_signature(
+        &self,
+        epoch: &u64,
+    ) -> Result<Option<EpochSignature>, SignatureRepositoryError> {
+        let bytes: Option<Vec<u8>> = self
+            .database
+            .connection()
+            .query_row(
+                "SELECT signature FROM signatures WHERE namespace = ?1 AND epoch = ?2",
+                params![self.namespace, epoch_to_sql(*epoch)?],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match bytes {
+            Some(bytes) => {
+                let signature: EpochSignature =
+                    bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0;
+                trace!(
+                    epoch,
+                    namespace = self.namespace,
+                    "Decoded signature from SQLite"
+                );
+                Ok(Some(signature))
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[instrument(skip_all, fields(namespace = self.namespace, epoch))]
+    async fn set_signature(
+        &mut self,
+        epoch: &u64,
+        signature: EpochSignature,
+    ) -> Result<(), SignatureRepositoryError> {
+        let content = bincode::encode_to_vec(&signature, BINCODE_CONFIG)?;
+
+        let mut connection = self.database.connection();
+        let transaction = connection.transaction()?;
+        let existing: Option<Vec<u8>> = transaction
+            .query_row(
+                "SELECT signature FROM signatures WHERE namespace = ?1 AND epoch = ?2",
+                params![self.namespace, epoch_to_sql(*epoch)?],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match existing {
+            Some(existing) => {
+                let existing: EpochSignature =
+                    bincode::decode_from_slice(&existing, BINCODE_CONFIG)?.0;
+                ensure_same_digest(epoch, &existing, &signature)?;
+            }
+            None => {
+                transaction.execute(
+                    "INSERT INTO signatures (namespace, epoch, signature) VALUES (?1, ?2, ?3)",
+                    params![self.namespace, epoch_to_sql(*epoch)?, content],
+                )?;
+            }
+        }
+
+        // Advance last_verified_epoch in the same transaction, if the namespace is stored here too
+        let info: Option<String> = transaction
+            .query_row(
+                "SELECT info FROM namespaces WHERE name = ?1",
+                params![self.namespace],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(info) = info {
+            let info = parse_namespace_info(&info)
+                .map_err(|e| SignatureRepositoryError::NamespaceStateError(e.to_string()))?;
+            if info
+                .last_verified_epoch
+                .is_none_or(|last_verified| last_verified.value() < epoch)
+            {
+                let updated = NamespaceInfo {
+                    version: info.version + 1,
+                    ..info.update_last_verified_epoch((*epoch).into())
+                };
+                let updated = serialize_namespace_info(&updated)
+                    .map_err(|e| SignatureRepositoryError::NamespaceStateError(e.to_string()))?;
+                transaction.execute(
+                    "UPDATE namespaces SET info = ?2 WHERE name = ?1",
+                    params![self.namespace, updated],
+                )?;
+                trace!(epoch, "Advanced last verified epoch with signature");
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<u64>, SignatureRepositoryError> {
+        let Some((start, end)) = epoch_range_to_sql(&range) else {
+            return Ok(Vec::new());
+        };
+        let connection = self.database.connection();
+        let mut statement = connection.prepare(
+            "SELECT epoch FROM signatures WHERE namespace = ?1 AND epoch BETWEEN ?2 AND ?3 ORDER BY epoch LIMIT ?4",
+        )?;
+        let epochs = statement
+            .query_map(
+                params![self.namespace, start, end, limit_to_sql(limit)],
+                |row| row.get::<_, i64>(0),
+            )?
+            .map(|epoch| Ok(epoch?.cast_unsigned()))
+            .collect::<Result<Vec<u64>, rusqlite::Error>>()?;
+        Ok(epochs)
+    }
+
+    async fn get_signatures(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> Result<Vec<EpochSignature>, SignatureRepositoryError> {
+        let Some((start, end)) = epoch_range_to_sql(&range) else {
+            return Ok(Vec::new());
+        };
+        let connection = self.database.connection();
+        let mut statement = connection.prepare(
+            "SELECT signature FROM signatures WHERE namespace = ?1 AND epoch BETWEEN ?2 AND ?3 ORDER BY epoch LIMIT ?4",
+        )?;
+        let rows = statement.query_map(
+            params![self.namespace, start, end, limit_to_sql(limit)],
+            |row| row.get::<_, Vec<u8>>(0),
+        )?;
+
+        let mut signatures = Vec::new();
+        for bytes in rows {
+            signatures.push(bincode::decode_from_slice(&bytes?, BINCODE_CONFIG)?.0);
+        }
+        Ok(signatures)
+    }
+
+    async fn latest_epoch(&self) -> Result<Option<u64>, SignatureRepositoryError> {
+        let latest: Option<i64> = self.database.connection().query_row(
+            "SELECT MAX(epoch) FROM signatures WHERE namespace = ?1",
+            params![self.namespace],
+            |row| row.get(0),
+        )?;
+        Ok(latest.map(i64::cast_unsigned))
+    }
+
+    async fn count_signatures(&self) -> Result<u64, SignatureRepositoryError> {
+        let count: i64 = self.database.connection().query_row(
+            "SELECT COUNT(*) FROM signatures WHERE namespace = ?1",
+            params![self.namespace],
+            |row| row.get(0),
+        )?;
+        Ok(count.cast_unsigned())
+    }
+
+    async fn get_heartbeat(&self) -> Result<Option<Heartbeat>, SignatureRepositoryError> {
+        let bytes: Option<Vec<u8>> = self
+            .database
+            .connection()
+            .query_row(
+                "SELECT heartbeat FROM heartbeats WHERE namespace = ?1",
+                params![self.namespace],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match bytes {
+            Some(bytes) => Ok(Some(bincode::decode_from_slice(&bytes, BINCODE_CONFIG)?.0)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<(), SignatureRepositoryError> {
+        let content = bincode::encode_to_vec(heartbeat, BINCODE_CONFIG)?;
+        self.database.connection().execute(
+            "INSERT OR REPLACE INTO heartbeats (namespace, heartbeat) VALUES (?1, ?2)",
+            params![self.namespace, content],
+        )?;
+        Ok(())
+    }
+}
+
+/// SQLite treats a negative LIMIT as unlimited
+fn limit_to_sql(limit: usize) -> i64 {
+    i64::try_from(limit).unwrap_or(-1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Epoch,
+        storage::namespaces::{NamespaceRepository, SqliteNamespaceRepository},
+        testing::{sign_test_epoch, test_namespace},
+    };
+
+    #[tokio::test]
+    async fn test_set_and_get_signature() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let mut storage = SqliteSignatureStorage::new(database, "test");
+
+        assert!(!storage.has_signature(&1).await.unwrap());
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+
+        assert!(storage.has_signature(&1).await.unwrap());
+        let signature = storage.get_signature(&1).await.unwrap().unwrap();
+        assert_eq!(signature.epoch(), Epoch::new(1));
+        assert!(storage.get_signature(&2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_signature_advances_last_verified_epoch() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let mut namespaces = SqliteNamespaceRepository::new(database.clone());
+        namespaces
+            .add_namespace(test_namespace("test").update_last_verified_epoch(Epoch::new(1)))
+            .await
+            .unwrap();
+        let mut storage = SqliteSignatureStorage::new(database, "test");
+
+        storage
+            .set_signature(&2, sign_test_epoch(2, 1).await)
+            .await
+            .unwrap();
+        let info = namespaces
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.last_verified_epoch, Some(Epoch::new(2)));
+
+        // Re-storing an older epoch never moves last_verified_epoch backwards
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+        let info = namespaces
+            .get_namespace_info("test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.last_verified_epoch, Some(Epoch::new(2)));
+    }
+
+    #[tokio::test]
+    async fn test_signatures_are_scoped_to_namespace() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let mut first = SqliteSignatureStorage::new(database.clone(), "first");
+        let second = SqliteSignatureStorage::new(database, "second");
+
+        first
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+        assert!(first.has_signature(&1).await.unwrap());
+        assert!(!second.has_signature(&1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_set_signature_is_write_once() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let mut storage = SqliteSignatureStorage::new(database, "test");
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+
+        // Re-storing the same digest is idempotent
+        storage
+            .set_signature(&1, sign_test_epoch(1, 1).await)
+            .await
+            .unwrap();
+
+        let result = storage.set_signature(&1, sign_test_epoch(1, 2).await).await;
+        assert!(matches!(
+            result,
+            Err(SignatureRepositoryError::SignatureConflict { epoch: 1, .. })
+        ));
+        let stored = storage.get_signature(&1).await.unwrap().unwrap();
+        assert_eq!(stored.digest(), vec![1u8; 32]);
+    }
+
+    #[tokio::test]
+    async fn test_range_queries() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let mut storage = SqliteSignatureStorage::new(database.clone(), "test");
+        let mut other = SqliteSignatureStorage::new(database, "other");
+        assert_eq!(storage.latest_epoch().await.unwrap(), None);
+
+        for epoch in [1, 2, 10, 11] {
+            storage
+                .set_signature(&epoch, sign_test_epoch(epoch, 1).await)
+                .await
+                .unwrap();
+        }
+        other
+            .set_signature(&20, sign_test_epoch(20, 1).await)
+            .await
+            .unwrap();
+
+        assert_eq!(storage.latest_epoch().await.unwrap(), Some(11));
+        assert_eq!(storage.count_signatures().await.unwrap(), 4);
+        assert_eq!(
+            storage.list_epochs(0..=u64::MAX, 3).await.unwrap(),
+            vec![1, 2, 10]
+        );
+        assert_eq!(
+            storage.list_epochs(3..=u64::MAX, 3).await.unwrap(),
+            vec![10, 11]
+        );
+        assert!(
+            storage
+                .list_epochs(u64::MAX..=u64::MAX, 3)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let signatures = storage.get_signatures(2..=10, 10).await.unwrap();
+        let epochs: Vec<_> = signatures.iter().map(|s| s.epoch()).collect();
+        assert_eq!(epochs, vec![Epoch::new(2), Epoch::new(10)]);
+    }
+}
@@ -11,9 +11,13 @@ use uuid::Uuid;
 
 use crate::{
     crypto::{SigningKey, VerifyingKey},
-    storage::signing_keys::{
-        SigningKeyRepository, SigningKeyRepositoryError, VerifyingKeyRepository,
-        VerifyingKeyRepositoryError, VerifyingKeyStorage,
+    storage::{
+        RecoveryReport, remove_temp_files,
+        signing_keys::{
+            SigningKeyRepository, SigningKeyRepositoryError, VerifyingKeyRepository,
+            VerifyingKeyRepositoryError, VerifyingKeyStorage,
+        },
+        write_atomic,
     },
 };
 
@@ -53,34 +57,60 @@ impl FileSigningKeyRepository {
         format!("{data_directory}/keys")
     }
 
-    pub fn new(data_directory: &str, key_lifetime: Duration) -> Self {
+    pub fn new(
+        data_directory: &str,
+        key_lifetime: Duration,
+    ) -> Result<Self, SigningKeyRepositoryError> {
         let directory = Self::key_directory(data_directory);
 
         // Create the directory if it doesn't exist
-        std::fs::create_dir_all(&directory).expect("Failed to create signing key directory");
+        std::fs::create_dir_all(&directory)?;
 
         // Load from file if it exists, otherwise create a new one
-        let initial_key_state =
-            if std::path::Path::new(&Self::signing_key_path(&directory)).exists() {
-                let file_content = std::fs::read_to_string(Self::signing_key_path(&directory))
-                    .expect("Failed to read signing key file");
-                serde_json::from_str::<KeyState>(&file_content)
-                    .expect("Failed to deserialize signing key state")
-            } else {
-                KeyState {
-                    current_signing_key: SigningKey::generate(key_lifetime),
-                    expired_keys: Vec::new(),
-                }
-            };
+        let initial_key_state = match Self::load_key_state(&directory)? {
+            Some(key_state) => key_state,
+            None => KeyState {
+                current_signing_key: SigningKey::generate(key_lifetime),
+                expired_keys: Vec::new(),
+            },
+        };
 
         let new = Self {
             directory,
             keys: Arc::new(Mutex::new(initial_key_state)),
             key_lifetime,
         };
-        new.persist()
-            .expect("Failed to persist initial signing key");
-        new
+        new.persist(&new.keys.lock().expect("Mutex poisoned"))?;
+        Ok(new)
+    }
+
+    fn load_key_state(directory: &str) -> Result<Option<KeyState>, SigningKeyRepositoryError> {
+        let path = Self::signing_key_path(directory);
+        if !std::path::Path::new(&path).exists() {
+            return Ok(None);
+        }
+        let file_content = std::fs::read_to_string(&path)?;
+        let key_state = serde_json::from_str::<KeyState>(&file_content).map_err(|e| {
+            // Never replace a corrupt key file with a fresh key, that would orphan every signature
+            SigningKeyRepositoryError::Custom(format!(
+                "Signing key file {path} is corrupt and must be restored from backup: {e}"
+            ))
+        })?;
+        Ok(Some(key_state))
+    }
+
+    /// Startup recovery for the key directory.
+    ///
+    /// Removes temporary files left by an interrupted write and checks that the signing key file can
+    /// be parsed. The verifying key file is derived from the signing keys and is rewritten on load.
+    pub fn recover(data_directory: &str) -> Result<RecoveryReport, SigningKeyRepositoryError> {
+        let directory = Self::key_directory(data_directory);
+        let removed_temp_files = remove_temp_files(&directory, false)?;
+        Self::load_key_state(&directory)?;
+        Ok(RecoveryReport {
+            removed_temp_files,
+            ..Default::default()
+        })
     }
 
     fn _signing_key_path(&self) -> String {
@@ -112,26 +142,25 @@ impl FileSigningKeyRepository {
         key_state.expired_keys.push(existing_key);
 
         // Persist the new signing key to file
-        self.persist()?;
+        self.persist(&key_state)?;
 
         Ok(new_key)
     }
 
-    fn persist(&self) -> Result<(), SigningKeyRepositoryError> {
+    /// Writes `key_state`, which the caller has locked, to the key files
+    fn persist(&self, key_state: &KeyState) -> Result<(), SigningKeyRepositoryError> {
         // first persist the signing keys
         let path = self._signing_key_path();
-        let key_state = self.keys.lock().expect("Mutex poisoned");
-        let serialized = serde_json::to_string(&*key_state)?;
+        let serialized = serde_json::to_string(key_state)?;
         debug!("Persisting signing keys to {}", path);
-        std::fs::write(path, serialized).map_err(SigningKeyRepositoryError::IoError)?;
+        write_atomic(path, serialized.as_bytes())?;
 
         // then persist the verifying keys
         let verifying_keys = key_state.to_verifying_keys()?;
         let verifying_path = self._verifying_key_path();
         let serialized_verifying = serde_json::to_string(&verifying_keys)?;
         debug!("Persisting verifying keys to {}", verifying_path);
-        std::fs::write(verifying_path, serialized_verifying)
-            .map_err(SigningKeyRepositoryError::IoError)?;
+        write_atomic(verifying_path, serialized_verifying.as_bytes())?;
         Ok(())
     }
 }
@@ -228,3 +257,50 @@ impl VerifyingKeyRepository for FileVerifyingKeyRepository {
         Ok(keys.values().cloned().collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrupt_key_file_is_not_replaced() {
+        let data_directory = std::env::temp_dir()
+            .join(format!("akd-watch-{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        FileSigningKeyRepository::new(&data_directory, Duration::days(1)).unwrap();
+        let key_path = FileSigningKeyRepository::signing_key_path(
+            &FileSigningKeyRepository::key_directory(&data_directory),
+        );
+        std::fs::write(&key_path, "{\"current_signing_key\"").unwrap();
+
+        assert!(FileSigningKeyRepository::recover(&data_directory).is_err());
+        assert!(FileSigningKeyRepository::new(&data_directory, Duration::days(1)).is_err());
+        // The corrupt file is left in place for the operator to restore
+        assert_eq!(
+            std::fs::read_to_string(&key_path).unwrap(),
+            "{\"current_signing_key\""
+        );
+
+        std::fs::remove_dir_all(data_directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_force_key_rotation_persists_new_key() {
+        let data_directory = std::env::temp_dir()
+            .join(format!("akd-watch-{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let repository = FileSigningKeyRepository::new(&data_directory, Duration::days(1)).unwrap();
+        let before = repository.get_current_signing_key().await.unwrap();
+
+        repository.force_key_rotation().await.unwrap();
+
+        let reloaded = FileSigningKeyRepository::new(&data_directory, Duration::days(1)).unwrap();
+        let after = reloaded.get_current_signing_key().await.unwrap();
+        assert_ne!(before.key_id(), after.key_id());
+        assert_eq!(reloaded.keys.lock().unwrap().expired_keys.len(), 1);
+
+        std::fs::remove_dir_all(data_directory).unwrap();
+    }
+}
@@ -2,15 +2,14 @@ mod file_signing_key_repository;
 mod in_memory_signing_key_repository;
 
 pub use file_signing_key_repository::{FileSigningKeyRepository, FileVerifyingKeyRepository};
-pub use in_memory_signing_key_repository::InMemorySigningKeyRepository;
+pub use in_memory_signing_key_repository::{
+    InMemorySigningKeyRepository, InMemoryVerifyingKeyRepository,
+};
 
 use std::{fmt::Debug, future::Future};
 use uuid::Uuid;
 
-use crate::{
-    crypto::{SigningKey, VerifyingKey},
-    storage::signing_keys::in_memory_signing_key_repository::InMemoryVerifyingKeyRepository,
-};
+use crate::crypto::{SigningKey, VerifyingKey};
 
 pub trait SigningKeyRepository: Clone + Debug + Send + Sync {
     /// Retrieves the current signing key. If the latest key is expired, it will rotate to the next key and persist the new key.
@@ -0,0 +1,94 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rusqlite::Connection;
+use tracing::debug;
+
+const SQLITE_FILE_NAME: &str = "akd_watch.sqlite3";
+
+/// How long a connection waits for a lock held by another connection before failing
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS namespaces (
+        name TEXT PRIMARY KEY NOT NULL,
+        info TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS namespace_status_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        namespace TEXT NOT NULL,
+        transition TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS namespace_status_history_namespace
+        ON namespace_status_history (namespace);
+    CREATE TABLE IF NOT EXISTS signatures (
+        namespace TEXT NOT NULL,
+        epoch INTEGER NOT NULL,
+        signature BLOB NOT NULL,
+        PRIMARY KEY (namespace, epoch)
+    );
+    CREATE TABLE IF NOT EXISTS heartbeats (
+        namespace TEXT PRIMARY KEY NOT NULL,
+        heartbeat BLOB NOT NULL
+    );
+";
+
+/// Handle to the SQLite database shared by [`SqliteNamespaceRepository`] and
+/// [`SqliteSignatureStorage`].
+///
+/// Both repositories live in the same database file so that storing a signature and advancing the
+/// namespace's `last_verified_epoch` can be committed in a single transaction.
+///
+/// [`SqliteNamespaceRepository`]: crate::storage::namespaces::SqliteNamespaceRepository
+/// [`SqliteSignatureStorage`]: crate::storage::signatures::SqliteSignatureStorage
+#[derive(Clone, Debug)]
+pub struct SqliteDatabase {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    pub fn database_path(data_directory: &str) -> String {
+        format!("{data_directory}/{SQLITE_FILE_NAME}")
+    }
+
+    /// Opens (or creates) the database file in the given data directory and ensures the schema exists.
+    pub fn open(data_directory: &str) -> Result<Self, rusqlite::Error> {
+        let path = Self::database_path(data_directory);
+        debug!(path, "Opening SQLite database");
+        let connection = Connection::open(&path)?;
+        // WAL allows the web server to read while the auditor writes
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        Self::initialize(connection)
+    }
+
+    /// Opens a private in-memory database, mostly useful for testing.
+    pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
+        Self::initialize(Connection::open_in_memory()?)
+    }
+
+    fn initialize(connection: Connection) -> Result<Self, rusqlite::Error> {
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    pub(crate) fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .expect("SQLite connection lock poisoned")
+    }
+}
+
+/// Converts an epoch range to SQLite's signed integer representation, clamping the bounds to the
+/// representable range. Returns `None` if no stored epoch can fall within the range.
+pub(crate) fn epoch_range_to_sql(range: &std::ops::RangeInclusive<u64>) -> Option<(i64, i64)> {
+    let start = i64::try_from(*range.start()).ok()?;
+    let end = i64::try_from(*range.end()).unwrap_or(i64::MAX);
+    (start <= end).then_some((start, end))
+}
+
+/// Converts an epoch to SQLite's signed integer representation.
+pub(crate) fn epoch_to_sql(epoch: u64) -> Result<i64, rusqlite::Error> {
+    i64::try_from(epoch).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
@@ -0,0 +1,33 @@
+use crate::{
+    Epoch, EpochSignature, NamespaceInfo, NamespaceStatus,
+    akd_configurations::AkdConfiguration,
+    storage::signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+};
+
+/// An online namespace audited with the test AKD configuration, with no epoch verified yet
+pub fn test_namespace(name: &str) -> NamespaceInfo {
+    NamespaceInfo {
+        configuration: AkdConfiguration::TestConfiguration,
+        name: name.to_string(),
+        log_directory: "test".to_string(),
+        last_verified_epoch: None,
+        starting_epoch: Epoch::new(1),
+        status: NamespaceStatus::Online,
+        version: 0,
+    }
+}
+
+/// Signs `epoch` of the `test` namespace with root hash `[digest; 32]` under a freshly generated key
+pub async fn sign_test_epoch(epoch: u64, digest: u8) -> EpochSignature {
+    let key = InMemorySigningKeyRepository::new(chrono::Duration::days(1))
+        .get_current_signing_key()
+        .await
+        .expect("In-memory signing keys are always available");
+    EpochSignature::sign(
+        test_namespace("test"),
+        Epoch::new(epoch),
+        [digest; 32],
+        &key,
+    )
+    .expect("Test epochs can be signed")
+}
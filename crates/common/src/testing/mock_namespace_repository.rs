@@ -1,6 +1,8 @@
 use crate::{
-    NamespaceInfo,
-    storage::namespaces::{NamespaceRepository, NamespaceRepositoryError},
+    NamespaceInfo, StatusTransition,
+    storage::namespaces::{
+        NamespaceRepository, NamespaceRepositoryError, first_version, next_version, swap_version,
+    },
 };
 use std::{
     collections::HashMap,
@@ -11,6 +13,7 @@ use std::{
 #[derive(Clone, Debug)]
 pub struct MockNamespaceRepository {
     namespaces: Arc<RwLock<HashMap<String, NamespaceInfo>>>,
+    status_history: Arc<RwLock<HashMap<String, Vec<StatusTransition>>>>,
 }
 
 impl Default for MockNamespaceRepository {
@@ -23,6 +26,7 @@ impl MockNamespaceRepository {
     pub fn new() -> Self {
         Self {
             namespaces: Arc::new(RwLock::new(HashMap::new())),
+            status_history: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -83,11 +87,10 @@ impl NamespaceRepository for MockNamespaceRepository {
         info: NamespaceInfo,
     ) -> impl std::future::Future<Output = Result<(), NamespaceRepositoryError>> + Send {
         let result = {
-            self.namespaces
-                .write()
-                .expect("Namespaces lock poisoned")
-                .insert(info.name.clone(), info);
-            Ok(())
+            let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+            first_version(namespaces.get(&info.name), info).map(|info| {
+                namespaces.insert(info.name.clone(), info);
+            })
         };
         async move { result }
     }
@@ -97,15 +100,28 @@ impl NamespaceRepository for MockNamespaceRepository {
         info: NamespaceInfo,
     ) -> impl std::future::Future<Output = Result<(), NamespaceRepositoryError>> + Send {
         let result = {
-            self.namespaces
-                .write()
-                .expect("Namespaces lock poisoned")
-                .insert(info.name.clone(), info);
+            let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+            let info = next_version(namespaces.get(&info.name), info);
+            namespaces.insert(info.name.clone(), info);
             Ok(())
         };
         async move { result }
     }
 
+    fn compare_and_swap_namespace(
+        &mut self,
+        info: NamespaceInfo,
+    ) -> impl std::future::Future<Output = Result<NamespaceInfo, NamespaceRepositoryError>> + Send
+    {
+        let result = {
+            let mut namespaces = self.namespaces.write().expect("Namespaces lock poisoned");
+            swap_version(namespaces.get(&info.name), info).inspect(|info| {
+                namespaces.insert(info.name.clone(), info.clone());
+            })
+        };
+        async move { result }
+    }
+
     fn remove_namespace(
         &mut self,
         name: &str,
@@ -120,4 +136,37 @@ impl NamespaceRepository for MockNamespaceRepository {
         };
         async move { result }
     }
+
+    fn record_status_transition(
+        &mut self,
+        transition: StatusTransition,
+    ) -> impl std::future::Future<Output = Result<(), NamespaceRepositoryError>> + Send {
+        let result = {
+            self.status_history
+                .write()
+                .expect("Status history lock poisoned")
+                .entry(transition.namespace.clone())
+                .or_default()
+                .push(transition);
+            Ok(())
+        };
+        async move { result }
+    }
+
+    fn status_history(
+        &self,
+        name: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<StatusTransition>, NamespaceRepositoryError>> + Send
+    {
+        let result = {
+            Ok(self
+                .status_history
+                .read()
+                .expect("Status history lock poisoned")
+                .get(name)
+                .cloned()
+                .unwrap_or_default())
+        };
+        async move { result }
+    }
 }
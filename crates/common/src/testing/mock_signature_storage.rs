@@ -1,16 +1,18 @@
 use crate::{
-    EpochSignature,
-    storage::signatures::{SignatureRepository, SignatureRepositoryError},
+    EpochSignature, Heartbeat,
+    storage::signatures::{SignatureRepository, SignatureRepositoryError, ensure_same_digest},
 };
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
+    ops::RangeInclusive,
     sync::{Arc, RwLock},
 };
 
 /// Mock signature storage for testing
 #[derive(Clone, Debug)]
 pub struct MockSignatureStorage {
-    signatures: Arc<RwLock<HashMap<u64, EpochSignature>>>,
+    signatures: Arc<RwLock<BTreeMap<u64, EpochSignature>>>,
+    heartbeat: Arc<RwLock<Option<Heartbeat>>>,
     should_fail_get: Arc<RwLock<bool>>,
     should_fail_set: Arc<RwLock<bool>>,
 }
@@ -24,7 +26,8 @@ impl Default for MockSignatureStorage {
 impl MockSignatureStorage {
     pub fn new() -> Self {
         Self {
-            signatures: Arc::new(RwLock::new(HashMap::new())),
+            signatures: Arc::new(RwLock::new(BTreeMap::new())),
+            heartbeat: Arc::new(RwLock::new(None)),
             should_fail_get: Arc::new(RwLock::new(false)),
             should_fail_set: Arc::new(RwLock::new(false)),
         }
@@ -97,9 +100,86 @@ impl SignatureRepository for MockSignatureStorage {
         &mut self,
         epoch: &u64,
         signature: EpochSignature,
+    ) -> impl std::future::Future<Output = Result<(), SignatureRepositoryError>> + Send {
+        let result = if *self.should_fail_set.read().unwrap() {
+            Ok(())
+        } else {
+            let mut signatures = self.signatures.write().unwrap();
+            match signatures.get(epoch) {
+                Some(existing) => ensure_same_digest(epoch, existing, &signature),
+                None => {
+                    signatures.insert(*epoch, signature);
+                    Ok(())
+                }
+            }
+        };
+        async move { result }
+    }
+
+    fn list_epochs(
+        &self,
+        range: RangeInclusive<u64>,
+        limit: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<u64>, SignatureRepositoryError>> + Send {
+        let result = if *self.should_fail_get.read().unwrap() {
+            Vec::new()
+        } else {
+            self.signatures
+                .read()
+                .unwrap()
+                .range(range)
+                .map(|(epoch, _)| *epoch)
+                .take(limit)
+                .collect()
+        };
+        async move { Ok(result) }
+    }
+
+    fn latest_epoch(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Option<u64>, SignatureRepositoryError>> + Send
+    {
+        let result = if *self.should_fail_get.read().unwrap() {
+            None
+        } else {
+            self.signatures
+                .read()
+                .unwrap()
+                .last_key_value()
+                .map(|(epoch, _)| *epoch)
+        };
+        async move { Ok(result) }
+    }
+
+    fn count_signatures(
+        &self,
+    ) -> impl std::future::Future<Output = Result<u64, SignatureRepositoryError>> + Send {
+        let result = if *self.should_fail_get.read().unwrap() {
+            0
+        } else {
+            self.signatures.read().unwrap().len() as u64
+        };
+        async move { Ok(result) }
+    }
+
+    fn get_heartbeat(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Option<Heartbeat>, SignatureRepositoryError>> + Send
+    {
+        let result = if *self.should_fail_get.read().unwrap() {
+            None
+        } else {
+            self.heartbeat.read().unwrap().clone()
+        };
+        async move { Ok(result) }
+    }
+
+    fn set_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
     ) -> impl std::future::Future<Output = Result<(), SignatureRepositoryError>> + Send {
         if !*self.should_fail_set.read().unwrap() {
-            self.signatures.write().unwrap().insert(*epoch, signature);
+            *self.heartbeat.write().unwrap() = Some(heartbeat);
         }
         async move { Ok(()) }
     }
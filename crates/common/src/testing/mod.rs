@@ -13,10 +13,12 @@
 //
 // Note: These are designed for unit and component testing with mocked dependencies
 
+pub mod fixtures;
 pub mod mock_namespace_repository;
 pub mod mock_signature_storage;
 pub mod mock_signing_key_repository;
 
+pub use fixtures::{sign_test_epoch, test_namespace};
 pub use mock_namespace_repository::MockNamespaceRepository;
 pub use mock_signature_storage::MockSignatureStorage;
 pub use mock_signing_key_repository::{MockSigningKeyRepository, MockVerifyingKeyRepository};
@@ -0,0 +1,330 @@
+//! RFC 6962 Merkle tree hashing, proofs and proof verification.
+
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// `SHA-256(0x00 || data)`
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    Sha256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(data)
+        .finalize()
+        .into()
+}
+
+/// `SHA-256(0x01 || left || right)`
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Root of the empty tree, `SHA-256()`
+pub fn empty_root() -> Hash {
+    Sha256::digest([]).into()
+}
+
+/// Largest power of two strictly smaller than `n`, for `n > 1`
+fn split_point(n: u64) -> u64 {
+    1 << (63 - (n - 1).leading_zeros())
+}
+
+/// An append-only Merkle tree over leaf hashes.
+///
+/// Keeps the hash of every complete, aligned subtree so that roots and proofs for any earlier tree
+/// size can be computed in `O(log n)` hashes.
+#[derive(Clone, Debug, Default)]
+pub struct MerkleTree {
+    /// `levels[k][i]` is the hash of the subtree covering leaves `i * 2^k .. (i + 1) * 2^k`
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn size(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| leaves.len() as u64)
+    }
+
+    pub fn push(&mut self, leaf_hash: Hash) {
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels[0].push(leaf_hash);
+
+        let mut level = 0;
+        let mut index = self.levels[0].len() - 1;
+        while index % 2 == 1 {
+            let hash = node_hash(&self.levels[level][index - 1], &self.levels[level][index]);
+            if self.levels.len() == level + 1 {
+                self.levels.push(Vec::new());
+            }
+            self.levels[level + 1].push(hash);
+            level += 1;
+            index /= 2;
+        }
+    }
+
+    pub fn leaf(&self, index: u64) -> Option<Hash> {
+        self.levels.first()?.get(index as usize).copied()
+    }
+
+    /// Root hash of the tree made of the first `size` leaves
+    pub fn root(&self, size: u64) -> Option<Hash> {
+        match size {
+            0 => Some(empty_root()),
+            size if size <= self.size() => Some(self.subtree_hash(0, size)),
+            _ => None,
+        }
+    }
+
+    /// Audit path proving that leaf `index` is included in the tree of the first `size` leaves
+    pub fn inclusion_proof(&self, index: u64, size: u64) -> Option<Vec<Hash>> {
+        if index >= size || size > self.size() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        self.path(index, 0, size, &mut proof);
+        Some(proof)
+    }
+
+    /// Proof that the tree of the first `first` leaves is a prefix of the tree of the first `second`
+    pub fn consistency_proof(&self, first: u64, second: u64) -> Option<Vec<Hash>> {
+        if first > second || second > self.size() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        if first > 0 && first < second {
+            self.subproof(first, 0, second, true, &mut proof);
+        }
+        Some(proof)
+    }
+
+    /// Hash of the leaves `start..end`. Every range reached from the root is aligned on its
+    /// largest power-of-two prefix, so complete subtrees are always found in `levels`.
+    fn subtree_hash(&self, start: u64, end: u64) -> Hash {
+        let n = end - start;
+        if n.is_power_of_two() && start % n == 0 {
+            let level = n.trailing_zeros() as usize;
+            return self.levels[level][(start / n) as usize];
+        }
+        let k = split_point(n);
+        node_hash(
+            &self.subtree_hash(start, start + k),
+            &self.subtree_hash(start + k, end),
+        )
+    }
+
+    /// RFC 6962 `PATH(m, D[start:end])`
+    fn path(&self, m: u64, start: u64, end: u64, proof: &mut Vec<Hash>) {
+        let n = end - start;
+        if n <= 1 {
+            return;
+        }
+        let k = split_point(n);
+        if m < k {
+            self.path(m, start, start + k, proof);
+            proof.push(self.subtree_hash(start + k, end));
+        } else {
+            self.path(m - k, start + k, end, proof);
+            proof.push(self.subtree_hash(start, start + k));
+        }
+    }
+
+    /// RFC 6962 `SUBPROOF(m, D[start:end], b)`
+    fn subproof(&self, m: u64, start: u64, end: u64, complete: bool, proof: &mut Vec<Hash>) {
+        let n = end - start;
+        if m == n {
+            if !complete {
+                proof.push(self.subtree_hash(start, end));
+            }
+            return;
+        }
+        let k = split_point(n);
+        if m <= k {
+            self.subproof(m, start, start + k, complete, proof);
+            proof.push(self.subtree_hash(start + k, end));
+        } else {
+            self.subproof(m - k, start + k, end, false, proof);
+            proof.push(self.subtree_hash(start, start + k));
+        }
+    }
+}
+
+/// Verifies an inclusion proof as described in RFC 9162 section 2.1.3.2
+pub fn verify_inclusion(
+    leaf_hash: &Hash,
+    index: u64,
+    tree_size: u64,
+    proof: &[Hash],
+    root: &Hash,
+) -> bool {
+    if index >= tree_size {
+        return false;
+    }
+    let mut fn_ = index;
+    let mut sn = tree_size - 1;
+    let mut r = *leaf_hash;
+    for p in proof {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            r = node_hash(p, &r);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    sn == 0 && r == *root
+}
+
+/// Verifies a consistency proof as described in RFC 9162 section 2.1.4.2
+pub fn verify_consistency(
+    first: u64,
+    second: u64,
+    first_root: &Hash,
+    second_root: &Hash,
+    proof: &[Hash],
+) -> bool {
+    if first > second {
+        return false;
+    }
+    if first == second {
+        return proof.is_empty() && first_root == second_root;
+    }
+    if first == 0 {
+        return proof.is_empty();
+    }
+
+    let mut path = Vec::with_capacity(proof.len() + 1);
+    if first.is_power_of_two() {
+        path.push(*first_root);
+    }
+    path.extend_from_slice(proof);
+    let Some((seed, rest)) = path.split_first() else {
+        return false;
+    };
+
+    let mut fn_ = first - 1;
+    let mut sn = second - 1;
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    let mut fr = *seed;
+    let mut sr = *seed;
+    for c in rest {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            fr = node_hash(c, &fr);
+            sr = node_hash(c, &sr);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            sr = node_hash(&sr, c);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    fr == *first_root && sr == *second_root && sn == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(size: u64) -> MerkleTree {
+        let mut tree = MerkleTree::new();
+        for i in 0..size {
+            tree.push(leaf_hash(&i.to_le_bytes()));
+        }
+        tree
+    }
+
+    /// Direct recursive definition of `MTH` from RFC 6962
+    fn reference_root(leaves: &[Hash]) -> Hash {
+        match leaves.len() {
+            0 => empty_root(),
+            1 => leaves[0],
+            n => {
+                let k = split_point(n as u64) as usize;
+                node_hash(&reference_root(&leaves[..k]), &reference_root(&leaves[k..]))
+            }
+        }
+    }
+
+    #[test]
+    fn test_roots_match_reference() {
+        let tree = tree(33);
+        let leaves: Vec<Hash> = (0..33).map(|i| tree.leaf(i).unwrap()).collect();
+        for size in 0..=33 {
+            assert_eq!(
+                tree.root(size).unwrap(),
+                reference_root(&leaves[..size as usize])
+            );
+        }
+        assert!(tree.root(34).is_none());
+    }
+
+    #[test]
+    fn test_inclusion_proofs_verify() {
+        let tree = tree(21);
+        for size in 1..=21 {
+            let root = tree.root(size).unwrap();
+            for index in 0..size {
+                let proof = tree.inclusion_proof(index, size).unwrap();
+                let leaf = tree.leaf(index).unwrap();
+                assert!(verify_inclusion(&leaf, index, size, &proof, &root));
+                assert!(!verify_inclusion(&[0; 32], index, size, &proof, &root));
+            }
+        }
+        assert!(tree.inclusion_proof(5, 5).is_none());
+    }
+
+    #[test]
+    fn test_consistency_proofs_verify() {
+        let tree = tree(21);
+        for second in 1..=21 {
+            let second_root = tree.root(second).unwrap();
+            for first in 0..=second {
+                let first_root = tree.root(first).unwrap();
+                let proof = tree.consistency_proof(first, second).unwrap();
+                assert!(verify_consistency(
+                    first,
+                    second,
+                    &first_root,
+                    &second_root,
+                    &proof
+                ));
+                if first > 0 && first < second {
+                    assert!(!verify_consistency(
+                        first,
+                        second,
+                        &[0; 32],
+                        &second_root,
+                        &proof
+                    ));
+                }
+            }
+        }
+    }
+}
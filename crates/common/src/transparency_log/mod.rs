@@ -0,0 +1,445 @@
+//! Append-only Merkle-tree log of every [`EpochSignature`] the auditor emits.
+//!
+//! The log lives in `<data_directory>/transparency_log`:
+//!
+//! - `entries.log`: a header followed by records of `length (u32 LE) | payload`, where the payload is
+//!   the protobuf `LogEntry` encoding of the signature. Leaf `i` of the tree is the `i`th record.
+//! - `tree_head.json`: the most recently published [`SignedTreeHead`].
+//!
+//! Leaves and nodes are hashed as in RFC 6962, so inclusion and consistency proofs can be checked
+//! with any RFC 6962/9162 verifier. The log refuses to record two different digests for the same
+//! namespace and epoch, which keeps the auditor from equivocating even if its signature storage is
+//! reset; the tree heads make any rewrite of the log itself detectable by clients.
+
+mod merkle;
+mod tree_head;
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use prost::Message;
+use tracing::{debug, trace};
+
+use crate::{EpochSignature, storage::write_atomic};
+
+pub use merkle::{Hash, MerkleTree, leaf_hash, verify_consistency, verify_inclusion};
+pub use tree_head::SignedTreeHead;
+pub(crate) use tree_head::TreeHeadSignedMessage;
+
+/// Current version of the entries file format
+pub const LOG_FORMAT_VERSION: u32 = 1;
+
+const LOG_DIRECTORY: &str = "transparency_log";
+const ENTRIES_FILE: &str = "entries.log";
+const TREE_HEAD_FILE: &str = "tree_head.json";
+const ENTRIES_MAGIC: &[u8; 8] = b"AKDWLOG\0";
+/// Magic, format version and four reserved bytes
+const HEADER_LEN: u64 = 16;
+const RECORD_HEADER_LEN: u64 = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransparencyLogError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Corrupt transparency log: {0}")]
+    Corrupt(String),
+    #[error("Unsupported transparency log format version {0}")]
+    UnsupportedFormatVersion(u32),
+    #[error(
+        "Namespace {namespace} epoch {epoch} is already logged with digest {logged_digest}, refusing to log digest {new_digest}"
+    )]
+    ConflictingEntry {
+        namespace: String,
+        epoch: u64,
+        logged_digest: String,
+        new_digest: String,
+    },
+    #[error("Tree head serialization error: {0}")]
+    TreeHeadSerialization(#[from] serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, TransparencyLogError>;
+
+pub struct TransparencyLog {
+    directory: PathBuf,
+    tree: MerkleTree,
+    /// Position of each entry's record in the entries file
+    offsets: Vec<u64>,
+    /// End of the last complete record. Anything after it is a torn write and is overwritten by the
+    /// next append.
+    end_offset: u64,
+    /// Leaf index of every logged namespace and epoch
+    entries_by_epoch: HashMap<String, HashMap<u64, u64>>,
+}
+
+impl TransparencyLog {
+    /// Opens the log in `data_directory`, creating it if it does not exist yet
+    pub fn open_or_create(data_directory: &str) -> Result<Self> {
+        let directory = Path::new(data_directory).join(LOG_DIRECTORY);
+        std::fs::create_dir_all(&directory)?;
+        let path = directory.join(ENTRIES_FILE);
+        if !path.exists() {
+            let mut header = Vec::with_capacity(HEADER_LEN as usize);
+            header.extend_from_slice(ENTRIES_MAGIC);
+            header.extend_from_slice(&LOG_FORMAT_VERSION.to_le_bytes());
+            header.extend_from_slice(&[0; 4]);
+            write_atomic(&path, &header)?;
+        }
+        let mut log = Self::empty(directory);
+        log.refresh()?;
+        Ok(log)
+    }
+
+    /// Opens an existing log in `data_directory` for reading, returning `None` if there is none
+    pub fn open(data_directory: &str) -> Result<Option<Self>> {
+        let directory = Path::new(data_directory).join(LOG_DIRECTORY);
+        if !directory.join(ENTRIES_FILE).exists() {
+            return Ok(None);
+        }
+        let mut log = Self::empty(directory);
+        log.refresh()?;
+        Ok(Some(log))
+    }
+
+    fn empty(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            tree: MerkleTree::new(),
+            offsets: Vec::new(),
+            end_offset: HEADER_LEN,
+            entries_by_epoch: HashMap::new(),
+        }
+    }
+
+    fn entries_path(&self) -> PathBuf {
+        self.directory.join(ENTRIES_FILE)
+    }
+
+    fn tree_head_path(&self) -> PathBuf {
+        self.directory.join(TREE_HEAD_FILE)
+    }
+
+    fn check_header(file: &mut File) -> Result<()> {
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header)
+            .map_err(|e| TransparencyLogError::Corrupt(format!("unreadable header: {e}")))?;
+        if &header[..8] != ENTRIES_MAGIC {
+            return Err(TransparencyLogError::Corrupt("bad magic".to_string()));
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().expect("version is 4 bytes"));
+        if version != LOG_FORMAT_VERSION {
+            return Err(TransparencyLogError::UnsupportedFormatVersion(version));
+        }
+        Ok(())
+    }
+
+    /// Reads entries appended since the log was opened or last refreshed, e.g. by another process.
+    /// Returns the number of new entries.
+    pub fn refresh(&mut self) -> Result<u64> {
+        let mut file = File::open(self.entries_path())?;
+        if self.offsets.is_empty() {
+            Self::check_header(&mut file)?;
+        }
+        let file_len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(self.end_offset))?;
+        let mut reader = std::io::BufReader::new(file);
+
+        let mut added = 0;
+        let mut offset = self.end_offset;
+        while offset + RECORD_HEADER_LEN <= file_len {
+            let mut length = [0u8; RECORD_HEADER_LEN as usize];
+            reader.read_exact(&mut length)?;
+            let length = u64::from(u32::from_le_bytes(length));
+            if offset + RECORD_HEADER_LEN + length > file_len {
+                // Torn write at the end of the file
+                break;
+            }
+            let mut payload = vec![0u8; length as usize];
+            reader.read_exact(&mut payload)?;
+            let signature = decode_entry(&payload).map_err(|e| {
+                TransparencyLogError::Corrupt(format!("entry {}: {e}", self.offsets.len()))
+            })?;
+            self.push(offset, &payload, &signature);
+            offset += RECORD_HEADER_LEN + length;
+            added += 1;
+        }
+        self.end_offset = offset;
+        if added > 0 {
+            trace!(
+                added,
+                size = self.size(),
+                "Read new transparency log entries"
+            );
+        }
+        Ok(added)
+    }
+
+    fn push(&mut self, offset: u64, payload: &[u8], signature: &EpochSignature) {
+        let index = self.tree.size();
+        self.tree.push(leaf_hash(payload));
+        self.offsets.push(offset);
+        self.entries_by_epoch
+            .entry(signature.namespace().to_string())
+            .or_default()
+            .insert(*signature.epoch().value(), index);
+    }
+
+    /// Appends a signature to the log and returns its leaf index.
+    ///
+    /// If the namespace and epoch are already logged with the same digest, the existing index is
+    /// returned and nothing is written. A different digest is refused with
+    /// [`TransparencyLogError::ConflictingEntry`].
+    pub fn append(&mut self, signature: &EpochSignature) -> Result<u64> {
+        let namespace = signature.namespace();
+        let epoch = *signature.epoch().value();
+        if let Some(index) = self.find(namespace, epoch) {
+            let logged = self
+                .entry(index)?
+                .ok_or_else(|| TransparencyLogError::Corrupt(format!("missing entry {index}")))?;
+            if logged.digest() == signature.digest() {
+                return Ok(index);
+            }
+            return Err(TransparencyLogError::ConflictingEntry {
+                namespace: namespace.to_string(),
+                epoch,
+                logged_digest: logged.digest_hex(),
+                new_digest: signature.digest_hex(),
+            });
+        }
+
+        let payload = crate::proto::types::LogEntry::from(signature).encode_to_vec();
+        let length = u32::try_from(payload.len())
+            .map_err(|_| TransparencyLogError::Corrupt("entry too large".to_string()))?;
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN as usize + payload.len());
+        record.extend_from_slice(&length.to_le_bytes());
+        record.extend_from_slice(&payload);
+
+        let mut file = OpenOptions::new().write(true).open(self.entries_path())?;
+        let offset = self.end_offset;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&record)?;
+        // Drop any torn write that followed the last complete record
+        file.set_len(offset + record.len() as u64)?;
+        file.sync_data()?;
+
+        self.push(offset, &payload, signature);
+        self.end_offset = offset + record.len() as u64;
+        let index = self.size() - 1;
+        debug!(
+            namespace,
+            epoch, index, "Appended signature to transparency log"
+        );
+        Ok(index)
+    }
+
+    /// Number of entries in the log
+    pub fn size(&self) -> u64 {
+        self.tree.size()
+    }
+
+    /// Leaf index of the signature logged for `namespace` at `epoch`
+    pub fn find(&self, namespace: &str, epoch: u64) -> Option<u64> {
+        self.entries_by_epoch.get(namespace)?.get(&epoch).copied()
+    }
+
+    /// Raw leaf data of entry `index`
+    pub fn entry_bytes(&self, index: u64) -> Result<Option<Vec<u8>>> {
+        let Some(&offset) = self.offsets.get(index as usize) else {
+            return Ok(None);
+        };
+        let mut file = File::open(self.entries_path())?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut length = [0u8; RECORD_HEADER_LEN as usize];
+        file.read_exact(&mut length)?;
+        let mut payload = vec![0u8; u32::from_le_bytes(length) as usize];
+        match file.read_exact(&mut payload) {
+            Ok(()) => Ok(Some(payload)),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Err(TransparencyLogError::Corrupt(
+                format!("entry {index} was truncated"),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Signature logged as entry `index`
+    pub fn entry(&self, index: u64) -> Result<Option<EpochSignature>> {
+        self.entry_bytes(index)?
+            .map(|payload| {
+                decode_entry(&payload)
+                    .map_err(|e| TransparencyLogError::Corrupt(format!("entry {index}: {e}")))
+            })
+            .transpose()
+    }
+
+    pub fn leaf_hash(&self, index: u64) -> Option<Hash> {
+        self.tree.leaf(index)
+    }
+
+    /// Root hash of the first `tree_size` entries
+    pub fn root(&self, tree_size: u64) -> Option<Hash> {
+        self.tree.root(tree_size)
+    }
+
+    pub fn inclusion_proof(&self, index: u64, tree_size: u64) -> Option<Vec<Hash>> {
+        self.tree.inclusion_proof(index, tree_size)
+    }
+
+    pub fn consistency_proof(&self, first: u64, second: u64) -> Option<Vec<Hash>> {
+        self.tree.consistency_proof(first, second)
+    }
+
+    /// The most recently published tree head, if any
+    pub fn latest_tree_head(&self) -> Result<Option<SignedTreeHead>> {
+        match std::fs::read(self.tree_head_path()) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn publish_tree_head(&self, tree_head: &SignedTreeHead) -> Result<()> {
+        write_atomic(self.tree_head_path(), &serde_json::to_vec(tree_head)?)?;
+        debug!(
+            tree_size = tree_head.tree_size(),
+            "Published transparency log tree head"
+        );
+        Ok(())
+    }
+}
+
+fn decode_entry(payload: &[u8]) -> std::result::Result<EpochSignature, String> {
+    let entry = crate::proto::types::LogEntry::decode(payload).map_err(|e| e.to_string())?;
+    EpochSignature::try_from(entry).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Epoch,
+        crypto::SigningKey,
+        storage::signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+        testing::test_namespace,
+    };
+
+    fn temp_directory() -> String {
+        let path = std::env::temp_dir().join(format!("akd_watch_log_{}", uuid::Uuid::new_v4()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn signature(key: &SigningKey, namespace: &str, epoch: u64, digest: u8) -> EpochSignature {
+        EpochSignature::sign(
+            test_namespace(namespace),
+            Epoch::new(epoch),
+            [digest; 32],
+            key,
+        )
+        .unwrap()
+    }
+
+    async fn signing_key() -> SigningKey {
+        InMemorySigningKeyRepository::new(chrono::Duration::days(1))
+            .get_current_signing_key()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_append_reopen_and_prove() {
+        let directory = temp_directory();
+        let key = signing_key().await;
+        let mut log = TransparencyLog::open_or_create(&directory).unwrap();
+        for epoch in 1..=5 {
+            assert_eq!(
+                log.append(&signature(&key, "a", epoch, epoch as u8))
+                    .unwrap(),
+                epoch - 1
+            );
+        }
+        log.append(&signature(&key, "b", 1, 9)).unwrap();
+        let root = log.root(6).unwrap();
+
+        let reopened = TransparencyLog::open(&directory).unwrap().unwrap();
+        assert_eq!(reopened.size(), 6);
+        assert_eq!(reopened.root(6).unwrap(), root);
+        assert_eq!(reopened.find("b", 1), Some(5));
+        assert_eq!(reopened.entry(2).unwrap().unwrap().digest(), vec![3; 32]);
+
+        let leaf = leaf_hash(&reopened.entry_bytes(2).unwrap().unwrap());
+        let proof = reopened.inclusion_proof(2, 6).unwrap();
+        assert!(verify_inclusion(&leaf, 2, 6, &proof, &root));
+        let proof = reopened.consistency_proof(3, 6).unwrap();
+        assert!(verify_consistency(
+            3,
+            6,
+            &reopened.root(3).unwrap(),
+            &root,
+            &proof
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_digest_is_refused() {
+        let directory = temp_directory();
+        let key = signing_key().await;
+        let mut log = TransparencyLog::open_or_create(&directory).unwrap();
+        log.append(&signature(&key, "a", 1, 1)).unwrap();
+
+        // Re-signing the same digest is idempotent
+        assert_eq!(log.append(&signature(&key, "a", 1, 1)).unwrap(), 0);
+        assert!(matches!(
+            log.append(&signature(&key, "a", 1, 2)),
+            Err(TransparencyLogError::ConflictingEntry { epoch: 1, .. })
+        ));
+        assert_eq!(log.size(), 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_torn_write_is_ignored_and_overwritten() {
+        let directory = temp_directory();
+        let key = signing_key().await;
+        let mut log = TransparencyLog::open_or_create(&directory).unwrap();
+        log.append(&signature(&key, "a", 1, 1)).unwrap();
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(log.entries_path())
+            .unwrap();
+        file.write_all(&[200, 0, 0, 0, 1, 2]).unwrap();
+
+        let mut log = TransparencyLog::open_or_create(&directory).unwrap();
+        assert_eq!(log.size(), 1);
+        log.append(&signature(&key, "a", 2, 2)).unwrap();
+
+        let mut reader = TransparencyLog::open(&directory).unwrap().unwrap();
+        assert_eq!(reader.size(), 2);
+        log.append(&signature(&key, "a", 3, 3)).unwrap();
+        assert_eq!(reader.refresh().unwrap(), 1);
+        assert_eq!(reader.root(3), log.root(3));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tree_head_round_trip() {
+        let directory = temp_directory();
+        let key = signing_key().await;
+        let mut log = TransparencyLog::open_or_create(&directory).unwrap();
+        assert!(log.latest_tree_head().unwrap().is_none());
+        log.append(&signature(&key, "a", 1, 1)).unwrap();
+
+        let head = SignedTreeHead::sign(1, log.root(1).unwrap(), &key).unwrap();
+        log.publish_tree_head(&head).unwrap();
+        assert_eq!(log.latest_tree_head().unwrap(), Some(head));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
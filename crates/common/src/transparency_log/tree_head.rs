@@ -0,0 +1,191 @@
+use ed25519_dalek::Verifier;
+use ed25519_dalek::ed25519::signature::SignerMut;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    Ciphersuite, SignError, VerifyError,
+    crypto::{SigningKey, VerifyingKey},
+    error::SerializationError,
+    storage::signing_keys::VerifyingKeyRepository,
+};
+
+use super::merkle::Hash;
+
+/// Prefix prepended to every serialized tree head before signing, so a tree head signature can
+/// never be presented as an epoch signature or heartbeat (and vice versa).
+const TREE_HEAD_DOMAIN_SEPARATOR: &[u8] = b"akd-watch/tree-head/v1";
+
+/// A signed statement that the auditor's transparency log contained `tree_size` entries with Merkle
+/// root `root_hash` at `timestamp`.
+///
+/// Two tree heads signed by the auditor must always be provably consistent with each other; a pair
+/// that is not is evidence the auditor rewrote its log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "tree_head_version")]
+pub enum SignedTreeHead {
+    #[allow(private_interfaces)]
+    V1(SignedTreeHeadV1),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignedTreeHeadV1 {
+    pub ciphersuite: Ciphersuite,
+    pub tree_size: u64,
+    pub root_hash: Vec<u8>,
+    pub timestamp: i64,
+    pub signature: Vec<u8>,
+    pub key_id: Uuid,
+}
+
+impl SignedTreeHeadV1 {
+    fn verify(&self, verifying_key: &VerifyingKey) -> Result<(), VerifyError> {
+        let message = self.to_message().to_vec()?;
+
+        let signature =
+            ed25519_dalek::Signature::from_bytes(self.signature.as_slice().try_into().map_err(
+                |_| VerifyError::SignatureLengthError {
+                    expected: 64,
+                    actual: self.signature.len(),
+                },
+            )?);
+
+        verifying_key
+            .verifying_key
+            .verify(&message, &signature)
+            .map_err(VerifyError::from)
+    }
+
+    fn to_message(&self) -> TreeHeadSignedMessage {
+        TreeHeadSignedMessage {
+            ciphersuite: self.ciphersuite,
+            tree_size: self.tree_size,
+            root_hash: self.root_hash.clone(),
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeHeadSignedMessage {
+    pub(crate) ciphersuite: Ciphersuite,
+    pub(crate) tree_size: u64,
+    pub(crate) root_hash: Vec<u8>,
+    pub(crate) timestamp: i64,
+}
+
+impl TreeHeadSignedMessage {
+    pub fn to_vec(&self) -> Result<Vec<u8>, SerializationError> {
+        let body = match self.ciphersuite {
+            Ciphersuite::ProtobufEd25519 => {
+                crate::proto::types::TreeHeadMessage::from(self).encode_to_vec()
+            }
+            _ => {
+                return Err(SerializationError::UnknownFormat(format!(
+                    "{:?}",
+                    self.ciphersuite
+                )));
+            }
+        };
+        Ok([TREE_HEAD_DOMAIN_SEPARATOR, body.as_slice()].concat())
+    }
+}
+
+impl SignedTreeHead {
+    pub fn version_int(&self) -> u32 {
+        match self {
+            SignedTreeHead::V1(_) => 0x00_01,
+        }
+    }
+
+    /// Signs the head of a tree of `tree_size` entries with root `root_hash` at the current time
+    pub fn sign(
+        tree_size: u64,
+        root_hash: Hash,
+        signing_key: &SigningKey,
+    ) -> Result<Self, SignError> {
+        let message = TreeHeadSignedMessage {
+            ciphersuite: Ciphersuite::ProtobufEd25519,
+            tree_size,
+            root_hash: root_hash.to_vec(),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        let signature = signing_key
+            .signing_key()
+            .write()
+            .expect("Poisoned signing key")
+            .sign(&message.to_vec()?);
+        Ok(SignedTreeHead::V1(SignedTreeHeadV1 {
+            ciphersuite: message.ciphersuite,
+            tree_size: message.tree_size,
+            root_hash: message.root_hash,
+            timestamp: message.timestamp,
+            signature: signature.to_bytes().to_vec(),
+            key_id: signing_key.key_id(),
+        }))
+    }
+
+    pub fn tree_size(&self) -> u64 {
+        match self {
+            SignedTreeHead::V1(head) => head.tree_size,
+        }
+    }
+
+    pub fn root_hash(&self) -> &[u8] {
+        match self {
+            SignedTreeHead::V1(head) => &head.root_hash,
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            SignedTreeHead::V1(head) => head.timestamp,
+        }
+    }
+
+    pub fn signing_key_id(&self) -> Uuid {
+        match self {
+            SignedTreeHead::V1(head) => head.key_id,
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        verifying_key_repo: &impl VerifyingKeyRepository,
+    ) -> Result<(), VerifyError> {
+        let signing_key_id = self.signing_key_id();
+        let verifying_key = verifying_key_repo
+            .get_verifying_key(signing_key_id)
+            .await?
+            .ok_or_else(|| VerifyError::VerifyingKeyNotFound(signing_key_id))?;
+
+        match self {
+            SignedTreeHead::V1(head) => head.verify(&verifying_key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository};
+
+    #[tokio::test]
+    async fn test_tree_head_sign_and_verify() {
+        let repo = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let key = repo.get_current_signing_key().await.unwrap();
+        let verifying_keys = repo.verifying_key_repository().unwrap();
+
+        let head = SignedTreeHead::sign(3, [3u8; 32], &key).unwrap();
+        assert_eq!(head.tree_size(), 3);
+        head.verify(&verifying_keys).await.unwrap();
+
+        let SignedTreeHead::V1(mut tampered) = head;
+        tampered.tree_size = 2;
+        assert!(matches!(
+            SignedTreeHead::V1(tampered).verify(&verifying_keys).await,
+            Err(VerifyError::SignatureVerificationFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+use std::io::Result;
+
+const PROTOBUF_BASE_DIRECTORY: &str = "src/proto/specs";
+const PROTOBUF_FILES: [&str; 1] = ["auditor"];
+
+fn build_protobufs() -> Result<()> {
+    let files = PROTOBUF_FILES.map(|file| format!("{PROTOBUF_BASE_DIRECTORY}/{file}.proto"));
+    tonic_build::configure()
+        .build_client(false)
+        .compile_protos(&files, &[PROTOBUF_BASE_DIRECTORY])?;
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    build_protobufs()?;
+    Ok(())
+}
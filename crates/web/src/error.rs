@@ -12,6 +12,8 @@ pub enum ApiError {
     NotFound,
     #[error("Bad request: {0}")]
     BadRequest(String),
+    #[error("Not acceptable: {0}")]
+    NotAcceptable(String),
     #[error("Internal server error")]
     Internal,
 }
@@ -27,6 +29,10 @@ impl IntoResponse for ApiError {
                 info!("Bad request: {}", e);
                 (StatusCode::BAD_REQUEST, e)
             }
+            ApiError::NotAcceptable(e) => {
+                info!("Not acceptable: {}", e);
+                (StatusCode::NOT_ACCEPTABLE, e)
+            }
             ApiError::Internal => {
                 error!("Internal server error: {}", self);
                 (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
@@ -35,3 +41,14 @@ impl IntoResponse for ApiError {
         (status, msg).into_response()
     }
 }
+
+impl From<ApiError> for tonic::Status {
+    fn from(error: ApiError) -> Self {
+        match error {
+            ApiError::NotFound => tonic::Status::not_found(error.to_string()),
+            ApiError::BadRequest(e) => tonic::Status::invalid_argument(e),
+            ApiError::NotAcceptable(e) => tonic::Status::invalid_argument(e),
+            ApiError::Internal => tonic::Status::internal(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use std::pin::Pin;
+
+use akd_watch_common::{
+    EpochSignature, NamespaceInfo, StatusTransition,
+    crypto::VerifyingKey,
+    storage::{
+        namespaces::NamespaceRepository,
+        signatures::{SignatureRepository, SignatureStorage},
+        signing_keys::VerifyingKeyRepository,
+    },
+};
+use futures_util::{Stream, TryStreamExt, stream};
+use tonic::{Request, Response, Status};
+use tracing::{error, info, instrument, trace};
+
+use crate::{
+    AppState,
+    proto::auditor::{
+        self, GetKeysRequest, GetKeysResponse, GetNamespaceRequest, GetSignatureRequest,
+        ListNamespacesRequest, ListNamespacesResponse, ListSignaturesRequest,
+        WatchSignaturesRequest,
+        auditor_server::{Auditor, AuditorServer},
+    },
+    routes::{audits::read_page, events::NamespaceWaker},
+};
+
+/// Signatures read from storage per batch of a signature stream
+const STREAM_BATCH_SIZE: usize = 1000;
+
+type SignatureStream = Pin<Box<dyn Stream<Item = Result<auditor::Signature, Status>> + Send>>;
+
+pub(crate) fn auditor_service(app_state: AppState) -> AuditorServer<AuditorService> {
+    AuditorServer::new(AuditorService { app_state })
+}
+
+/// gRPC view of the same state the HTTP routes serve
+pub(crate) struct AuditorService {
+    app_state: AppState,
+}
+
+impl AuditorService {
+    async fn signature_storage(&self, namespace: &str) -> Result<SignatureStorage, Status> {
+        Ok(self
+            .app_state
+            .signature_storage
+            .get(&self.app_state.namespace_storage, namespace)
+            .await?)
+    }
+}
+
+fn namespace_message(
+    info: NamespaceInfo,
+    status_history: Vec<StatusTransition>,
+) -> auditor::Namespace {
+    auditor::Namespace {
+        name: info.name,
+        configuration: format!("{:?}", info.configuration),
+        log_directory: info.log_directory,
+        last_verified_epoch: info.last_verified_epoch.map(Into::into),
+        starting_epoch: info.starting_epoch.into(),
+        status: format!("{:?}", info.status),
+        version: info.version,
+        status_history: status_history
+            .into_iter()
+            .map(|transition| auditor::StatusTransition {
+                previous_status: transition
+                    .previous_status
+                    .map(|status| format!("{status:?}")),
+                new_status: format!("{:?}", transition.new_status),
+                epoch: transition.epoch.map(Into::into),
+                reason: transition.reason,
+                actor: format!("{:?}", transition.actor),
+                at: transition.at,
+            })
+            .collect(),
+    }
+}
+
+// `Status` is the error type of every tonic handler, so its size is not worth boxing here
+#[allow(clippy::result_large_err)]
+fn signature_message(signature: EpochSignature) -> Result<auditor::Signature, Status> {
+    let version = signature.version_int();
+    let signed_message = signature.signed_message().map_err(|e| {
+        error!(error = %e, "Failed to encode signed message");
+        Status::internal("Internal server error")
+    })?;
+    match signature {
+        EpochSignature::V1(sig) => Ok(auditor::Signature {
+            version,
+            ciphersuite: sig.ciphersuite.into(),
+            namespace: sig.namespace,
+            timestamp: sig.timestamp as u64,
+            epoch: sig.epoch.into(),
+            digest: sig.digest,
+            signed_message,
+            signature: sig.signature,
+            key_id: sig.key_id.to_string(),
+        }),
+    }
+}
+
+fn key_message(key: &VerifyingKey) -> auditor::VerifyingKey {
+    auditor::VerifyingKey {
+        public_key: key.verifying_key.to_bytes().to_vec(),
+        key_id: key.key_id.to_string(),
+        not_before: key.not_before.timestamp() as u64,
+    }
+}
+
+/// Reads the next batch of signatures from `start` on, returning them with the epoch to continue
+/// from. Storage errors end the stream with an internal error.
+async fn read_batch(
+    storage: &SignatureStorage,
+    namespace: &str,
+    start: u64,
+    end: u64,
+) -> Result<(Vec<auditor::Signature>, Option<u64>), Status> {
+    let (signatures, next) = read_page(storage, start..=end, STREAM_BATCH_SIZE)
+        .await
+        .map_err(|e| {
+            error!(namespace, error = %e, "Failed to stream signatures");
+            Status::internal("Internal server error")
+        })?;
+    let signatures = signatures
+        .into_iter()
+        .map(signature_message)
+        .collect::<Result<_, _>>()?;
+    Ok((signatures, next))
+}
+
+fn flatten_batches(
+    batches: impl Stream<Item = Result<Vec<auditor::Signature>, Status>> + Send + 'static,
+) -> SignatureStream {
+    Box::pin(
+        batches
+            .map_ok(|batch| stream::iter(batch.into_iter().map(Ok)))
+            .try_flatten(),
+    )
+}
+
+#[tonic::async_trait]
+impl Auditor for AuditorService {
+    type ListSignaturesStream = SignatureStream;
+    type WatchSignaturesStream = SignatureStream;
+
+    #[instrument(skip_all, fields(namespace = %request.get_ref().namespace))]
+    async fn get_namespace(
+        &self,
+        request: Request<GetNamespaceRequest>,
+    ) -> Result<Response<auditor::Namespace>, Status> {
+        let namespace = request.into_inner().namespace;
+        info!("Handling gRPC namespace query for namespace: {}", namespace);
+        let namespace_storage = &self.app_state.namespace_storage;
+        let info = namespace_storage
+            .get_namespace_info(&namespace)
+            .await
+            .map_err(|e| {
+                error!("Failed to get namespace info: {}", e);
+                Status::internal("Internal server error")
+            })?
+            .ok_or_else(|| Status::not_found(format!("namespace {namespace} not found")))?;
+        let status_history = namespace_storage
+            .status_history(&namespace)
+            .await
+            .map_err(|e| {
+                error!("Failed to get namespace status history: {}", e);
+                Status::internal("Internal server error")
+            })?;
+        Ok(Response::new(namespace_message(info, status_history)))
+    }
+
+    #[instrument(skip_all)]
+    async fn list_namespaces(
+        &self,
+        _request: Request<ListNamespacesRequest>,
+    ) -> Result<Response<ListNamespacesResponse>, Status> {
+        info!("Listing all namespaces over gRPC");
+        let namespaces = self
+            .app_state
+            .namespace_storage
+            .list_namespaces()
+            .await
+            .map_err(|e| {
+                error!("Failed to list namespaces: {}", e);
+                Status::internal("Internal server error")
+            })?;
+        Ok(Response::new(ListNamespacesResponse {
+            namespaces: namespaces
+                .into_iter()
+                .map(|info| namespace_message(info, Vec::new()))
+                .collect(),
+        }))
+    }
+
+    #[instrument(
+        skip_all,
+        fields(namespace = %request.get_ref().namespace, epoch = request.get_ref().epoch)
+    )]
+    async fn get_signature(
+        &self,
+        request: Request<GetSignatureRequest>,
+    ) -> Result<Response<auditor::Signature>, Status> {
+        let GetSignatureRequest { namespace, epoch } = request.into_inner();
+        info!(
+            "Handling gRPC audit query for namespace: {}, epoch: {}",
+            namespace, epoch
+        );
+        let signature = self
+            .signature_storage(&namespace)
+            .await?
+            .get_signature(&epoch)
+            .await
+            .map_err(|e| {
+                error!(
+                    "Failed to get signature for namespace {} at epoch {}: {}",
+                    namespace, epoch, e
+                );
+                Status::internal("Internal server error")
+            })?
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "no signature for namespace {namespace} at epoch {epoch}"
+                ))
+            })?;
+        Ok(Response::new(signature_message(signature)?))
+    }
+
+    #[instrument(skip_all, fields(namespace = %request.get_ref().namespace))]
+    async fn list_signatures(
+        &self,
+        request: Request<ListSignaturesRequest>,
+    ) -> Result<Response<Self::ListSignaturesStream>, Status> {
+        let ListSignaturesRequest {
+            namespace,
+            from_epoch,
+            to_epoch,
+        } = request.into_inner();
+        info!(
+            "Handling gRPC signature listing for namespace: {}",
+            namespace
+        );
+        let start = from_epoch.unwrap_or(0);
+        let end = to_epoch.unwrap_or(u64::MAX);
+        if start > end {
+            return Err(Status::invalid_argument("from_epoch is after to_epoch"));
+        }
+        let storage = self.signature_storage(&namespace).await?;
+
+        let batches = stream::try_unfold(Some(start), move |start| {
+            let storage = storage.clone();
+            let namespace = namespace.clone();
+            async move {
+                let Some(start) = start else {
+                    return Ok(None);
+                };
+                let (signatures, next) = read_batch(&storage, &namespace, start, end).await?;
+                Ok(Some((signatures, next)))
+            }
+        });
+        Ok(Response::new(flatten_batches(batches)))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_keys(
+        &self,
+        _request: Request<GetKeysRequest>,
+    ) -> Result<Response<GetKeysResponse>, Status> {
+        info!("Handling gRPC key listing");
+        let keys = self
+            .app_state
+            .verifying_key_storage
+            .list_keys()
+            .await
+            .map_err(|e| {
+                error!("Failed to list keys: {}", e);
+                Status::internal("Internal server error")
+            })?;
+        Ok(Response::new(GetKeysResponse {
+            keys: keys.iter().map(key_message).collect(),
+        }))
+    }
+
+    #[instrument(skip_all, fields(namespace = %request.get_ref().namespace))]
+    async fn watch_signatures(
+        &self,
+        request: Request<WatchSignaturesRequest>,
+    ) -> Result<Response<Self::WatchSignaturesStream>, Status> {
+        let WatchSignaturesRequest {
+            namespace,
+            from_epoch,
+        } = request.into_inner();
+        info!("Handling gRPC signature watch for namespace: {}", namespace);
+        let storage = self.signature_storage(&namespace).await?;
+        let waker = NamespaceWaker::new(namespace.clone(), self.app_state.events.as_ref());
+        let start = match from_epoch {
+            Some(epoch) => epoch,
+            None => storage
+                .latest_epoch()
+                .await
+                .map_err(|e| {
+                    error!(namespace, error = %e, "Failed to get latest epoch");
+                    Status::internal("Internal server error")
+                })?
+                .map_or(0, |epoch| epoch.saturating_add(1)),
+        };
+
+        // The watch ends when the client disconnects and the stream is dropped
+        let batches = stream::try_unfold((start, waker), move |(start, mut waker)| {
+            let storage = storage.clone();
+            let namespace = namespace.clone();
+            async move {
+                loop {
+                    let (signatures, next) =
+                        read_batch(&storage, &namespace, start, u64::MAX).await?;
+                    if let Some(last) = signatures.last() {
+                        trace!(namespace, epoch = last.epoch, "Watched new signatures");
+                        let next = next.unwrap_or(last.epoch.saturating_add(1));
+                        return Ok(Some((signatures, (next, waker))));
+                    }
+                    waker.wait().await;
+                }
+            }
+        });
+        Ok(Response::new(flatten_batches(batches)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use akd_watch_common::events::AuditEvents;
+    use tonic::Code;
+
+    use super::*;
+    use crate::testing::{NAMESPACE, TestApp};
+
+    fn service(app: &TestApp) -> AuditorService {
+        AuditorService {
+            app_state: app.state.clone(),
+        }
+    }
+
+    async fn list(
+        service: &AuditorService,
+        from_epoch: Option<u64>,
+        to_epoch: Option<u64>,
+    ) -> Result<Vec<u64>, Status> {
+        let stream = service
+            .list_signatures(Request::new(ListSignaturesRequest {
+                namespace: NAMESPACE.to_string(),
+                from_epoch,
+                to_epoch,
+            }))
+            .await?
+            .into_inner();
+        stream
+            .map_ok(|signature| signature.epoch)
+            .try_collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_missing_resources_map_to_status_codes() {
+        let app = TestApp::new().await;
+        let service = service(&app);
+        app.store(1).await;
+
+        let status = service
+            .get_namespace(Request::new(GetNamespaceRequest {
+                namespace: "unknown".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        let status = service
+            .get_signature(Request::new(GetSignatureRequest {
+                namespace: NAMESPACE.to_string(),
+                epoch: 2,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        // An unknown namespace is a bad request, as over HTTP
+        let status = service
+            .get_signature(Request::new(GetSignatureRequest {
+                namespace: "unknown".to_string(),
+                epoch: 1,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let signature = service
+            .get_signature(Request::new(GetSignatureRequest {
+                namespace: NAMESPACE.to_string(),
+                epoch: 1,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(signature.epoch, 1);
+        assert_eq!(signature.namespace, NAMESPACE);
+    }
+
+    #[tokio::test]
+    async fn test_list_signatures_bounds_are_inclusive() {
+        let app = TestApp::new().await;
+        let service = service(&app);
+        for epoch in [1, 2, 4, 5, 7, u64::MAX] {
+            app.store(epoch).await;
+        }
+
+        assert_eq!(
+            list(&service, None, None).await.unwrap(),
+            [1, 2, 4, 5, 7, u64::MAX]
+        );
+        assert_eq!(list(&service, Some(2), Some(5)).await.unwrap(), [2, 4, 5]);
+        assert!(list(&service, Some(3), Some(3)).await.unwrap().is_empty());
+        assert_eq!(list(&service, Some(6), None).await.unwrap(), [7, u64::MAX]);
+        assert_eq!(
+            list(&service, Some(u64::MAX), None).await.unwrap(),
+            [u64::MAX]
+        );
+        let status = list(&service, Some(5), Some(4)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch_emits_signature_stored_after_subscribing() {
+        let app = TestApp::new().await;
+        let service = service(&app);
+        app.store(1).await;
+
+        let mut stream = service
+            .watch_signatures(Request::new(WatchSignaturesRequest {
+                namespace: NAMESPACE.to_string(),
+                from_epoch: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        // Epoch 1 was stored before the watch started, so it is not sent
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), stream.try_next())
+                .await
+                .is_err()
+        );
+        app.store(2).await;
+        app.store(3).await;
+
+        let signature = stream.try_next().await.unwrap().unwrap();
+        assert_eq!(signature.epoch, 2);
+        let signature = stream.try_next().await.unwrap().unwrap();
+        assert_eq!(signature.epoch, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch_is_woken_by_audit_events() {
+        let app = TestApp::with_events(AuditEvents::default()).await;
+        let service = service(&app);
+
+        let mut stream = service
+            .watch_signatures(Request::new(WatchSignaturesRequest {
+                namespace: NAMESPACE.to_string(),
+                from_epoch: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), stream.try_next())
+                .await
+                .is_err()
+        );
+        let stored_at = tokio::time::Instant::now();
+        app.store(1).await;
+
+        let signature = stream.try_next().await.unwrap().unwrap();
+        assert_eq!(signature.epoch, 1);
+        // Time is paused, so any wait for a storage poll would show up as elapsed time
+        assert_eq!(stored_at.elapsed(), Duration::ZERO);
+    }
+}
@@ -1,28 +1,52 @@
-use std::collections::HashMap;
+use std::sync::Arc;
 
-use akd_watch_common::storage::{
-    namespaces::NamespaceStorage, signatures::SignatureStorage, signing_keys::VerifyingKeyStorage,
+use akd_watch_common::storage::{namespaces::NamespaceStorage, signing_keys::VerifyingKeyStorage};
+use akd_watch_common::{
+    events::AuditEvents, evidence::EvidenceStore, transparency_log::TransparencyLog,
 };
 use anyhow::{Context, Result};
 use axum::Router;
-use tokio::net::TcpListener;
+use tokio::{net::TcpListener, sync::Mutex};
+use tonic::transport::server::TcpIncoming;
 use tracing::{error, info, instrument, trace};
 
+use crate::signature_storage_cache::SignatureStorageCache;
 use crate::web_config::WebConfig;
 
 mod error;
+mod grpc;
+mod proto;
 mod routes;
+mod signature_storage_cache;
+#[cfg(test)]
+mod testing;
 mod web_config;
 
 #[derive(Clone)]
 pub(crate) struct AppState {
     namespace_storage: NamespaceStorage,
-    signature_storage: HashMap<String, SignatureStorage>,
+    signature_storage: SignatureStorageCache,
     verifying_key_storage: VerifyingKeyStorage,
+    evidence_store: EvidenceStore,
+    /// The auditor's transparency log, opened once the auditor has created it
+    transparency_log: Arc<Mutex<Option<TransparencyLog>>>,
+    /// Events from an auditor in the same process. Without them, event streams poll storage.
+    events: Option<AuditEvents>,
+    data_directory: String,
 }
 
-#[instrument(skip_all, name = "start_web")]
 pub async fn start() -> Result<()> {
+    run(None).await
+}
+
+/// Starts the web server, pushing `events` from an auditor in the same process to event streams
+/// as they happen
+pub async fn start_with_events(events: AuditEvents) -> Result<()> {
+    run(Some(events)).await
+}
+
+#[instrument(skip_all, name = "start_web")]
+async fn run(events: Option<AuditEvents>) -> Result<()> {
     trace!("Starting web server");
 
     // Load configuration
@@ -35,11 +59,13 @@ pub async fn start() -> Result<()> {
         }
     }
     info!("Starting web server with configuration: {:?}", config);
+    akd_watch_common::metrics::install();
 
     // Initialize application state
     let namespace_storage = config
         .namespace_storage
-        .build_namespace_storage(&config.data_directory());
+        .build_namespace_storage(&config.data_directory())
+        .context("Failed to initialize namespace storage")?;
     let signature_storage = config
         .signature_storage
         .build_signature_storage(&namespace_storage, &config.data_directory())
@@ -49,26 +75,54 @@ pub async fn start() -> Result<()> {
         .signing
         .build_verifying_key_storage(&config.data_directory())
         .context("Failed to initialize verifying key storage")?;
+    let transparency_log = TransparencyLog::open(&config.data_directory())
+        .context("Failed to open transparency log")?;
     let app_state = AppState {
         namespace_storage,
-        signature_storage,
+        signature_storage: SignatureStorageCache::new(
+            config.signature_storage.clone(),
+            config.data_directory(),
+            signature_storage,
+        ),
         verifying_key_storage,
+        evidence_store: EvidenceStore::new(&config.data_directory()),
+        transparency_log: Arc::new(Mutex::new(transparency_log)),
+        events,
+        data_directory: config.data_directory(),
     };
 
     // Build API
+    let grpc_service = grpc::auditor_service(app_state.clone());
     let app = Router::new()
         .merge(routes::api_routes())
         .with_state(app_state);
 
-    // Start server
+    // Start servers
     let addr = config.socket_addr();
     let listener = TcpListener::bind(addr)
         .await
         .context("Socket binding failed")?;
-    println!("Listening on http://{}", addr);
-    axum::serve(listener, app.into_make_service())
+    let grpc_addr = config.grpc_socket_addr();
+    let grpc_listener = TcpListener::bind(grpc_addr)
         .await
-        .context("Server failed")?;
+        .context("gRPC socket binding failed")?;
+    let grpc_incoming = TcpIncoming::from_listener(grpc_listener, true, None)
+        .map_err(|e| anyhow::anyhow!("gRPC listener setup failed: {e}"))?;
+    println!("Listening on http://{}", addr);
+    println!("Serving gRPC on {}", grpc_addr);
+    let http = async {
+        axum::serve(listener, app.into_make_service())
+            .await
+            .context("Server failed")
+    };
+    let grpc = async {
+        tonic::transport::Server::builder()
+            .add_service(grpc_service)
+            .serve_with_incoming(grpc_incoming)
+            .await
+            .context("gRPC server failed")
+    };
+    tokio::try_join!(http, grpc)?;
 
     Ok(())
 }
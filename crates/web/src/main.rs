@@ -1,14 +1,16 @@
+use akd_watch_common::observability::Observability;
 use akd_watch_web::start;
 use tracing::error;
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::TRACE)
-        .init();
+    let observability = Observability::from_config("akd-watch-web")
+        .and_then(Observability::init)
+        .expect("Failed to initialize observability");
 
     if let Err(e) = start().await {
         error!(error = ?e, "Application error");
+        drop(observability);
         std::process::exit(1);
     }
 }
@@ -0,0 +1,3 @@
+pub mod auditor {
+    include!(concat!(env!("OUT_DIR"), "/akd_watch_web.auditor.rs"));
+}
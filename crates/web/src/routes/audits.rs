@@ -1,10 +1,23 @@
+use std::ops::RangeInclusive;
+
 use akd_watch_common::{
-    Ciphersuite, Epoch, EpochSignature, storage::signatures::SignatureRepository,
+    Ciphersuite, Epoch, EpochSignature,
+    storage::signatures::{SignatureRepository, SignatureRepositoryError, SignatureStorage},
+};
+use axum::{
+    Json,
+    body::Body,
+    extract::Query,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH, VARY},
+    },
+    response::{IntoResponse, Response},
 };
-use axum::Json;
 use serde::{Deserialize, Serialize};
-use tracing::{info, instrument, trace};
+use tracing::{error, info, instrument, trace};
 
+use super::negotiation::SignatureFormat;
 use crate::{AppState, error::ApiError};
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
@@ -40,33 +53,34 @@ impl From<EpochSignature> for SignatureResponse {
 #[instrument(skip_all, fields(namespace = %namespace, epoch))]
 pub async fn audit_query_handler(
     axum::extract::State(AppState {
-        signature_storage, ..
+        namespace_storage,
+        signature_storage,
+        ..
     }): axum::extract::State<AppState>,
     axum::extract::Path((namespace, epoch)): axum::extract::Path<(String, String)>,
-) -> Result<Json<Option<SignatureResponse>>, ApiError> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     info!(
         "Handling audit query for namespace: {}, epoch: {}",
         namespace, epoch
     );
+    let format = SignatureFormat::negotiate(&headers)?;
     let epoch: u64 = epoch
         .parse()
         .map_err(|_| ApiError::BadRequest("epoch is not an integer".to_string()))?;
-    let namespace_signature_storage =
-        signature_storage
-            .get(&namespace)
-            .ok_or(ApiError::BadRequest(format!(
-                "namespace {namespace} not found"
-            )))?;
+    let namespace_signature_storage = signature_storage
+        .get(&namespace_storage, &namespace)
+        .await?;
     trace!(namespace, epoch, "Found namespace storage for audit query");
 
     match namespace_signature_storage.get_signature(&epoch).await {
-        Ok(Some(maybe_sig)) => Ok(Json(Some(maybe_sig.into()))),
+        Ok(Some(maybe_sig)) => format.respond(Some(maybe_sig)),
         Ok(None) => {
             info!(
                 "No signature found for namespace {} at epoch {}",
                 namespace, epoch
             );
-            Ok(Json(None))
+            format.respond(None)
         }
         Err(e) => {
             tracing::error!(
@@ -79,3 +93,553 @@ pub async fn audit_query_handler(
         }
     }
 }
+
+/// The latest signature changes whenever an epoch is audited, so caches must revalidate every use
+const LATEST_CACHE_CONTROL: &str = "public, no-cache";
+
+/// Entity tag of the latest signature. Signatures are write-once, so the epoch and digest identify
+/// the response in a given format.
+fn latest_etag(signature: &EpochSignature, format: SignatureFormat) -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "\"{}-{}-{}\"",
+        signature.epoch(),
+        signature.digest_hex(),
+        format.name()
+    ))
+    .expect("Entity tag is a valid header value")
+}
+
+fn etag_matches(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || etag.to_str().is_ok_and(|etag| etag == tag))
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+pub async fn latest_audit_query_handler(
+    axum::extract::State(AppState {
+        namespace_storage,
+        signature_storage,
+        ..
+    }): axum::extract::State<AppState>,
+    axum::extract::Path(namespace): axum::extract::Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    info!("Handling latest audit query for namespace: {}", namespace);
+    let format = SignatureFormat::negotiate(&headers)?;
+    let namespace_signature_storage = signature_storage
+        .get(&namespace_storage, &namespace)
+        .await?;
+
+    // Read the signature of the highest stored epoch directly, rather than the namespace's
+    // last_verified_epoch, so the response cannot name an epoch whose signature is not yet stored
+    let signature = match namespace_signature_storage.latest_epoch().await {
+        Ok(Some(epoch)) => namespace_signature_storage.get_signature(&epoch).await,
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    }
+    .map_err(|e| {
+        tracing::error!(
+            "Failed to get latest signature for namespace {}: {}",
+            namespace,
+            e
+        );
+        ApiError::Internal
+    })?;
+
+    let cache_control = HeaderValue::from_static(LATEST_CACHE_CONTROL);
+    let etag = match &signature {
+        Some(signature) => {
+            trace!(namespace, epoch = %signature.epoch(), "Found latest signature");
+            Some(latest_etag(signature, format))
+        }
+        None => {
+            info!("No signature found for namespace {}", namespace);
+            None
+        }
+    };
+    if let Some(etag) = etag.as_ref().filter(|etag| etag_matches(&headers, etag)) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [
+                (CACHE_CONTROL, cache_control),
+                (ETAG, etag.clone()),
+                (VARY, HeaderValue::from_static("accept")),
+            ],
+        )
+            .into_response());
+    }
+
+    let mut response = format.respond(signature)?;
+    response.headers_mut().insert(CACHE_CONTROL, cache_control);
+    if let Some(etag) = etag {
+        response.headers_mut().insert(ETAG, etag);
+    }
+    Ok(response)
+}
+
+const DEFAULT_PAGE_LIMIT: usize = 100;
+const MAX_PAGE_LIMIT: usize = 1000;
+/// Signatures read from storage per chunk of an NDJSON stream
+const STREAM_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct AuditRangeQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+    limit: Option<usize>,
+    /// `next_cursor` of the previous page; continues the listing after it
+    cursor: Option<String>,
+}
+
+impl AuditRangeQuery {
+    /// The epochs left to list: from the cursor if there is one, otherwise from `from`, up to `to`
+    fn range(&self) -> Result<RangeInclusive<u64>, ApiError> {
+        let end = self.to.unwrap_or(u64::MAX);
+        if self.from.is_some_and(|from| from > end) {
+            return Err(ApiError::BadRequest("from is after to".to_string()));
+        }
+        let start = match &self.cursor {
+            Some(cursor) => cursor
+                .parse()
+                .map_err(|_| ApiError::BadRequest("invalid cursor".to_string()))?,
+            None => self.from.unwrap_or(0),
+        };
+        Ok(start..=end)
+    }
+
+    fn limit(&self) -> Result<usize, ApiError> {
+        match self.limit {
+            None => Ok(DEFAULT_PAGE_LIMIT),
+            Some(limit) if (1..=MAX_PAGE_LIMIT).contains(&limit) => Ok(limit),
+            Some(_) => Err(ApiError::BadRequest(format!(
+                "limit must be between 1 and {MAX_PAGE_LIMIT}"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct AuditPageResponse {
+    signatures: Vec<SignatureResponse>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    next_cursor: Option<String>,
+}
+
+/// Reads up to `limit` signatures in `range`, returning them with the first epoch of the next page
+/// if the range may hold more.
+pub(crate) async fn read_page(
+    storage: &SignatureStorage,
+    range: RangeInclusive<u64>,
+    limit: usize,
+) -> Result<(Vec<EpochSignature>, Option<u64>), SignatureRepositoryError> {
+    if range.is_empty() {
+        return Ok((Vec::new(), None));
+    }
+    let end = *range.end();
+    let epochs = storage.list_epochs(range, limit).await?;
+    let next = match epochs.last() {
+        Some(&last) if epochs.len() == limit && last < end => Some(last + 1),
+        _ => None,
+    };
+
+    let mut signatures = Vec::with_capacity(epochs.len());
+    for epoch in epochs {
+        if let Some(signature) = storage.get_signature(&epoch).await? {
+            signatures.push(signature);
+        }
+    }
+    Ok((signatures, next))
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+pub async fn audit_range_query_handler(
+    axum::extract::State(AppState {
+        namespace_storage,
+        signature_storage,
+        ..
+    }): axum::extract::State<AppState>,
+    axum::extract::Path(namespace): axum::extract::Path<String>,
+    Query(query): Query<AuditRangeQuery>,
+) -> Result<Json<AuditPageResponse>, ApiError> {
+    info!(
+        ?query,
+        "Handling audit range query for namespace: {}", namespace
+    );
+    let range = query.range()?;
+    let limit = query.limit()?;
+    let namespace_signature_storage = signature_storage
+        .get(&namespace_storage, &namespace)
+        .await?;
+
+    let (signatures, next) = read_page(&namespace_signature_storage, range, limit)
+        .await
+        .map_err(|e| {
+            error!(namespace, error = %e, "Failed to list signatures");
+            ApiError::Internal
+        })?;
+    trace!(namespace, count = signatures.len(), "Listed signatures");
+    Ok(Json(AuditPageResponse {
+        signatures: signatures.into_iter().map(Into::into).collect(),
+        next_cursor: next.map(|epoch| epoch.to_string()),
+    }))
+}
+
+/// Streams every signature in the range as newline-delimited JSON, for bulk sync. `limit` is
+/// ignored. If the stream is cut short, resume with `from` set after the last epoch received.
+#[instrument(skip_all, fields(namespace = %namespace))]
+pub async fn audit_stream_handler(
+    axum::extract::State(AppState {
+        namespace_storage,
+        signature_storage,
+        ..
+    }): axum::extract::State<AppState>,
+    axum::extract::Path(namespace): axum::extract::Path<String>,
+    Query(query): Query<AuditRangeQuery>,
+) -> Result<Response, ApiError> {
+    info!(?query, "Handling audit stream for namespace: {}", namespace);
+    let range = query.range()?;
+    let namespace_signature_storage = signature_storage
+        .get(&namespace_storage, &namespace)
+        .await?;
+
+    let end = *range.end();
+    let chunks = futures_util::stream::try_unfold(Some(*range.start()), move |start| {
+        let storage = namespace_signature_storage.clone();
+        let namespace = namespace.clone();
+        async move {
+            let Some(start) = start else {
+                return Ok(None);
+            };
+            let (signatures, next) = read_page(&storage, start..=end, STREAM_BATCH_SIZE)
+                .await
+                .map_err(|e| {
+                    error!(namespace, error = %e, "Failed to stream signatures");
+                    std::io::Error::other(e.to_string())
+                })?;
+            let mut lines = Vec::new();
+            for signature in signatures {
+                serde_json::to_writer(&mut lines, &SignatureResponse::from(signature))
+                    .map_err(std::io::Error::other)?;
+                lines.push(b'\n');
+            }
+            Ok::<_, std::io::Error>(Some((lines, next)))
+        }
+    });
+
+    Ok((
+        [(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-ndjson"),
+        )],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::{Path, State};
+
+    use super::*;
+    use crate::testing::{NAMESPACE, TestApp};
+
+    async fn latest(app: &TestApp, namespace: &str, headers: HeaderMap) -> Response {
+        latest_audit_query_handler(
+            State(app.state.clone()),
+            Path(namespace.to_string()),
+            headers,
+        )
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn if_none_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_latest_without_signatures_is_null() {
+        let app = TestApp::new().await;
+
+        let response = latest(&app, NAMESPACE, HeaderMap::new()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], LATEST_CACHE_CONTROL);
+        assert!(response.headers().get(ETAG).is_none());
+        assert_eq!(json_body(response).await, serde_json::Value::Null);
+
+        // Without an entity tag there is nothing for a wildcard to match
+        let response = latest(&app, NAMESPACE, if_none_match("*")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_latest_returns_newest_signature_with_cache_headers() {
+        let app = TestApp::new().await;
+        app.store(3).await;
+        let newest = app.store(9).await;
+        app.store(5).await;
+
+        let response = latest(&app, NAMESPACE, HeaderMap::new()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, no-cache");
+        assert_eq!(response.headers()[VARY], "accept");
+        assert_eq!(
+            response.headers()[ETAG],
+            format!("\"9-{}-json\"", newest.digest_hex())
+        );
+        let body: SignatureResponse = serde_json::from_value(json_body(response).await).unwrap();
+        assert_eq!(body, SignatureResponse::from(newest));
+    }
+
+    #[tokio::test]
+    async fn test_latest_if_none_match_is_not_modified() {
+        let app = TestApp::new().await;
+        app.store(4).await;
+        let etag = latest(&app, NAMESPACE, HeaderMap::new()).await.headers()[ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        for value in [
+            etag.clone(),
+            format!("W/{etag}"),
+            format!("\"stale\", {etag}"),
+            "*".to_string(),
+        ] {
+            let response = latest(&app, NAMESPACE, if_none_match(&value)).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{value}");
+            assert_eq!(response.headers()[ETAG], etag.as_str());
+            assert_eq!(response.headers()[CACHE_CONTROL], LATEST_CACHE_CONTROL);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(body.is_empty());
+        }
+
+        // A tag from before the next epoch was stored no longer matches
+        app.store(5).await;
+        let response = latest(&app, NAMESPACE, if_none_match(&etag)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_latest_unknown_namespace_is_bad_request() {
+        let app = TestApp::new().await;
+
+        let response = latest(&app, "unknown", HeaderMap::new()).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    fn range_query(from: Option<u64>, to: Option<u64>, limit: Option<usize>) -> AuditRangeQuery {
+        AuditRangeQuery {
+            from,
+            to,
+            limit,
+            cursor: None,
+        }
+    }
+
+    /// Stores signatures at epochs 1..=20 except multiples of 3, returning the stored epochs
+    async fn store_with_gaps(app: &TestApp) -> Vec<u64> {
+        let epochs: Vec<u64> = (1..=20).filter(|epoch| epoch % 3 != 0).collect();
+        for &epoch in &epochs {
+            app.store(epoch).await;
+        }
+        epochs
+    }
+
+    async fn page(app: &TestApp, query: AuditRangeQuery) -> Result<AuditPageResponse, ApiError> {
+        audit_range_query_handler(
+            State(app.state.clone()),
+            Path(NAMESPACE.to_string()),
+            Query(query),
+        )
+        .await
+        .map(|Json(page)| page)
+    }
+
+    #[tokio::test]
+    async fn test_pages_cover_every_epoch_once() {
+        let app = TestApp::new().await;
+        let stored = store_with_gaps(&app).await;
+
+        for (from, to, limit) in [
+            (None, None, 1),
+            (None, None, 4),
+            (Some(3), Some(3), 2),
+            (Some(2), Some(17), 3),
+            (Some(5), None, 7),
+            (None, Some(18), 13),
+            (None, None, 1000),
+        ] {
+            let mut query = range_query(from, to, Some(limit));
+            let mut listed = Vec::new();
+            loop {
+                let page = page(&app, query).await.unwrap();
+                assert!(page.signatures.len() <= limit);
+                listed.extend(
+                    page.signatures
+                        .iter()
+                        .map(|signature| *signature.epoch.value()),
+                );
+                let Some(cursor) = page.next_cursor else {
+                    break;
+                };
+                query = AuditRangeQuery {
+                    cursor: Some(cursor),
+                    ..range_query(from, to, Some(limit))
+                };
+            }
+            let expected: Vec<u64> = stored
+                .iter()
+                .copied()
+                .filter(|&epoch| from.is_none_or(|from| epoch >= from))
+                .filter(|&epoch| to.is_none_or(|to| epoch <= to))
+                .collect();
+            assert_eq!(listed, expected, "from {from:?} to {to:?} limit {limit}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cursor_after_to_is_an_empty_last_page() {
+        let app = TestApp::new().await;
+        store_with_gaps(&app).await;
+
+        let query = AuditRangeQuery {
+            cursor: Some("11".to_string()),
+            ..range_query(None, Some(10), None)
+        };
+        assert!(query.range().unwrap().is_empty());
+        let page = page(&app, query).await.unwrap();
+        assert!(page.signatures.is_empty());
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_range_rejects_invalid_queries() {
+        assert!(range_query(Some(5), Some(4), None).range().is_err());
+        let query = AuditRangeQuery {
+            cursor: Some("next".to_string()),
+            ..range_query(None, None, None)
+        };
+        assert!(query.range().is_err());
+
+        assert_eq!(
+            range_query(None, None, None).limit().unwrap(),
+            DEFAULT_PAGE_LIMIT
+        );
+        assert_eq!(range_query(None, None, Some(1)).limit().unwrap(), 1);
+        assert_eq!(
+            range_query(None, None, Some(MAX_PAGE_LIMIT))
+                .limit()
+                .unwrap(),
+            MAX_PAGE_LIMIT
+        );
+        assert!(range_query(None, None, Some(0)).limit().is_err());
+        assert!(
+            range_query(None, None, Some(MAX_PAGE_LIMIT + 1))
+                .limit()
+                .is_err()
+        );
+
+        let app = TestApp::new().await;
+        let error = page(&app, range_query(None, None, Some(0)))
+            .await
+            .unwrap_err();
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_read_page_up_to_max_epoch() {
+        let app = TestApp::new().await;
+        app.store(u64::MAX - 1).await;
+        app.store(u64::MAX).await;
+        let storage = app
+            .state
+            .signature_storage
+            .get(&app.state.namespace_storage, NAMESPACE)
+            .await
+            .unwrap();
+        let (signatures, next) = read_page(&storage, 0..=u64::MAX, 2).await.unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(next, None);
+
+        let query = range_query(Some(u64::MAX - 1), Some(u64::MAX), Some(1));
+        let first = page(&app, query).await.unwrap();
+        assert_eq!(*first.signatures[0].epoch.value(), u64::MAX - 1);
+        assert_eq!(first.next_cursor, Some(u64::MAX.to_string()));
+
+        let query = AuditRangeQuery {
+            cursor: first.next_cursor,
+            ..range_query(None, None, Some(1))
+        };
+        let last = page(&app, query).await.unwrap();
+        assert_eq!(*last.signatures[0].epoch.value(), u64::MAX);
+        // The range ends at u64::MAX, so there is no epoch after it to continue from
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_lists_range_and_ends() {
+        let app = TestApp::new().await;
+        let stored = store_with_gaps(&app).await;
+
+        let response = audit_stream_handler(
+            State(app.state.clone()),
+            Path(NAMESPACE.to_string()),
+            Query(range_query(Some(4), None, Some(1))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+        // Collecting the body only returns once the stream has ended
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let streamed: Vec<u64> = body
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                *serde_json::from_slice::<SignatureResponse>(line)
+                    .unwrap()
+                    .epoch
+                    .value()
+            })
+            .collect();
+        let expected: Vec<u64> = stored.into_iter().filter(|&epoch| epoch >= 4).collect();
+        assert_eq!(streamed, expected);
+        assert!(body.ends_with(b"\n"));
+
+        app.store(u64::MAX).await;
+        let response = audit_stream_handler(
+            State(app.state.clone()),
+            Path(NAMESPACE.to_string()),
+            Query(range_query(Some(u64::MAX), None, None)),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.iter().filter(|&&byte| byte == b'\n').count(), 1);
+    }
+}
@@ -0,0 +1,456 @@
+use std::{collections::VecDeque, convert::Infallible, time::Duration};
+
+use akd_watch_common::{
+    events::{AuditEvent, AuditEvents},
+    storage::{
+        namespaces::{NamespaceRepository, NamespaceStorage},
+        signatures::{SignatureRepository, SignatureStorage},
+    },
+};
+use axum::{
+    extract::Query,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{Stream, stream};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, info, instrument, trace, warn};
+
+use super::audits::{SignatureResponse, read_page};
+use crate::{AppState, error::ApiError};
+
+/// How often an event stream polls storage when no auditor runs in the same process
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Signatures read from storage per catch up
+const CATCH_UP_BATCH_SIZE: usize = 100;
+/// Header a reconnecting `EventSource` sends with the id of the last event it received
+const LAST_EVENT_ID: &str = "last-event-id";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EventsQuery {
+    /// Replay the signatures from this epoch on before streaming new ones
+    from: Option<u64>,
+}
+
+/// Wakes a reader of one namespace's storage when the storage may hold something new.
+///
+/// Storage is the source of truth: audit events from an auditor in the same process only wake the
+/// reader up, so lagging behind the channel loses no events. Without an auditor in the process the
+/// reader polls storage instead.
+pub(crate) struct NamespaceWaker {
+    namespace: String,
+    live: Option<broadcast::Receiver<AuditEvent>>,
+}
+
+impl NamespaceWaker {
+    /// Subscribes to `events`. Create the waker before reading storage so nothing stored in between
+    /// is missed.
+    pub(crate) fn new(namespace: String, events: Option<&AuditEvents>) -> Self {
+        Self {
+            namespace,
+            live: events.map(AuditEvents::subscribe),
+        }
+    }
+
+    /// Waits until storage may hold something new for this namespace
+    pub(crate) async fn wait(&mut self) {
+        let Some(live) = &mut self.live else {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            return;
+        };
+        loop {
+            match live.recv().await {
+                Ok(event) if event.namespace() != self.namespace => continue,
+                Ok(_) => return,
+                Err(RecvError::Lagged(skipped)) => {
+                    trace!(namespace = self.namespace, skipped, "Event stream lagged");
+                    return;
+                }
+                Err(RecvError::Closed) => {
+                    warn!(
+                        namespace = self.namespace,
+                        "Audit events closed, falling back to polling"
+                    );
+                    self.live = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Follows one namespace's signature and status history storage
+struct EventCursor {
+    namespace: String,
+    namespace_storage: NamespaceStorage,
+    signature_storage: SignatureStorage,
+    waker: NamespaceWaker,
+    next_epoch: u64,
+    history_len: usize,
+    pending: VecDeque<Event>,
+}
+
+impl EventCursor {
+    fn push_json(&mut self, event: Event, data: &impl Serialize) {
+        match event.json_data(data) {
+            Ok(event) => self.pending.push_back(event),
+            Err(e) => error!(namespace = self.namespace, error = %e, "Failed to encode event"),
+        }
+    }
+
+    /// Queues the status transitions recorded since the last catch up, or, when resuming, those
+    /// made while auditing an epoch at or after the resume point
+    async fn catch_up_status(&mut self, resume_from: Option<u64>) -> Result<(), ApiError> {
+        let history = self
+            .namespace_storage
+            .status_history(&self.namespace)
+            .await
+            .map_err(|e| {
+                error!(
+                    namespace = self.namespace,
+                    error = %e,
+                    "Failed to get namespace status history"
+                );
+                ApiError::Internal
+            })?;
+        let new = history.get(self.history_len..).unwrap_or_default();
+        for transition in new {
+            let resumed = match (resume_from, transition.epoch) {
+                (None, _) => true,
+                (Some(from), Some(epoch)) => *epoch.value() >= from,
+                (Some(_), None) => false,
+            };
+            if resumed {
+                self.push_json(Event::default().event("status"), transition);
+            }
+        }
+        self.history_len = history.len();
+        Ok(())
+    }
+
+    /// Queues the signatures stored since the last catch up
+    async fn catch_up_signatures(&mut self) -> Result<(), ApiError> {
+        let (signatures, next) = read_page(
+            &self.signature_storage,
+            self.next_epoch..=u64::MAX,
+            CATCH_UP_BATCH_SIZE,
+        )
+        .await
+        .map_err(|e| {
+            error!(namespace = self.namespace, error = %e, "Failed to read new signatures");
+            ApiError::Internal
+        })?;
+        if let Some(next) = next {
+            self.next_epoch = next;
+        } else if let Some(last) = signatures.last() {
+            self.next_epoch = last.epoch().value().saturating_add(1);
+        }
+        for signature in signatures {
+            let event = Event::default()
+                .event("signature")
+                .id(signature.epoch().to_string());
+            self.push_json(event, &SignatureResponse::from(signature));
+        }
+        Ok(())
+    }
+
+    async fn next(mut self) -> Option<(Result<Event, Infallible>, Self)> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some((Ok(event), self));
+            }
+            // A failed read ends the stream; the client reconnects with the last event id
+            self.catch_up_signatures().await.ok()?;
+            self.catch_up_status(None).await.ok()?;
+            if self.pending.is_empty() {
+                self.waker.wait().await;
+            }
+        }
+    }
+}
+
+/// The epoch to resume from: after the last event a reconnecting client received, otherwise `from`
+fn resume_from(headers: &HeaderMap, query: &EventsQuery) -> Result<Option<u64>, ApiError> {
+    match headers.get(LAST_EVENT_ID) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|id| id.parse::<u64>().ok())
+            .map(|epoch| Some(epoch.saturating_add(1)))
+            .ok_or_else(|| ApiError::BadRequest("invalid Last-Event-ID".to_string())),
+        None => Ok(query.from),
+    }
+}
+
+/// Streams the namespace's newly stored signatures and status transitions as server-sent events.
+/// Signature events carry the epoch as their id, so a reconnecting `EventSource` resumes after
+/// the last signature it received.
+#[instrument(skip_all, fields(namespace = %namespace))]
+pub async fn events_handler(
+    axum::extract::State(AppState {
+        namespace_storage,
+        signature_storage,
+        events,
+        ..
+    }): axum::extract::State<AppState>,
+    axum::extract::Path(namespace): axum::extract::Path<String>,
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    info!(?query, "Handling event stream for namespace: {}", namespace);
+    let resume_from = resume_from(&headers, &query)?;
+    let namespace_signature_storage = signature_storage
+        .get(&namespace_storage, &namespace)
+        .await?;
+
+    let waker = NamespaceWaker::new(namespace.clone(), events.as_ref());
+    let next_epoch = match resume_from {
+        Some(epoch) => epoch,
+        None => namespace_signature_storage
+            .latest_epoch()
+            .await
+            .map_err(|e| {
+                error!(namespace, error = %e, "Failed to get latest epoch");
+                ApiError::Internal
+            })?
+            .map_or(0, |epoch| epoch.saturating_add(1)),
+    };
+    let mut cursor = EventCursor {
+        namespace,
+        namespace_storage,
+        signature_storage: namespace_signature_storage,
+        waker,
+        next_epoch,
+        history_len: 0,
+        pending: VecDeque::new(),
+    };
+    // Start from the current status history, replaying only what happened after the resume point
+    cursor
+        .catch_up_status(Some(resume_from.unwrap_or(u64::MAX)))
+        .await?;
+
+    Ok(Sse::new(stream::unfold(cursor, EventCursor::next)).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use akd_watch_common::{NamespaceStatus, StatusTransition};
+    use axum::{
+        body::BodyDataStream,
+        extract::{Path, State},
+        http::HeaderValue,
+        response::IntoResponse,
+    };
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::testing::{NAMESPACE, TestApp};
+
+    /// A server-sent event as a client parses it
+    #[derive(Debug)]
+    struct ReceivedEvent {
+        event: String,
+        id: Option<String>,
+        data: serde_json::Value,
+    }
+
+    async fn connect(
+        app: &TestApp,
+        from: Option<u64>,
+        last_event_id: Option<&str>,
+    ) -> BodyDataStream {
+        let mut headers = HeaderMap::new();
+        if let Some(id) = last_event_id {
+            headers.insert(LAST_EVENT_ID, HeaderValue::from_str(id).unwrap());
+        }
+        events_handler(
+            State(app.state.clone()),
+            Path(NAMESPACE.to_string()),
+            Query(EventsQuery { from }),
+            headers,
+        )
+        .await
+        .unwrap()
+        .into_response()
+        .into_body()
+        .into_data_stream()
+    }
+
+    /// Reads the next event, skipping keep-alive comments
+    async fn next_event(stream: &mut BodyDataStream) -> ReceivedEvent {
+        loop {
+            let chunk = stream.next().await.unwrap().unwrap();
+            let text = std::str::from_utf8(&chunk).unwrap();
+            let mut event = None;
+            let mut id = None;
+            let mut data = None;
+            for line in text.lines() {
+                if let Some(value) = line.strip_prefix("event: ") {
+                    event = Some(value.to_string());
+                } else if let Some(value) = line.strip_prefix("id: ") {
+                    id = Some(value.to_string());
+                } else if let Some(value) = line.strip_prefix("data: ") {
+                    data = Some(serde_json::from_str(value).unwrap());
+                }
+            }
+            if let (Some(event), Some(data)) = (event, data) {
+                return ReceivedEvent { event, id, data };
+            }
+        }
+    }
+
+    async fn assert_no_event(stream: &mut BodyDataStream) {
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), next_event(stream))
+                .await
+                .is_err()
+        );
+    }
+
+    fn signature_epoch(event: &ReceivedEvent) -> u64 {
+        assert_eq!(event.event, "signature");
+        let epoch = event.data["epoch"].as_u64().unwrap();
+        assert_eq!(event.id, Some(epoch.to_string()));
+        epoch
+    }
+
+    #[tokio::test]
+    async fn test_resumes_from_query() {
+        let app = TestApp::with_events(AuditEvents::default()).await;
+        for epoch in 1..=3 {
+            app.store(epoch).await;
+        }
+
+        let mut stream = connect(&app, Some(2), None).await;
+
+        assert_eq!(signature_epoch(&next_event(&mut stream).await), 2);
+        assert_eq!(signature_epoch(&next_event(&mut stream).await), 3);
+        assert_no_event(&mut stream).await;
+        app.store(4).await;
+        assert_eq!(signature_epoch(&next_event(&mut stream).await), 4);
+    }
+
+    #[tokio::test]
+    async fn test_without_resume_point_streams_only_new_signatures() {
+        let app = TestApp::with_events(AuditEvents::default()).await;
+        app.store(1).await;
+
+        let mut stream = connect(&app, None, None).await;
+
+        assert_no_event(&mut stream).await;
+        app.store(2).await;
+        assert_eq!(signature_epoch(&next_event(&mut stream).await), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resumes_after_last_event_id() {
+        let app = TestApp::with_events(AuditEvents::default()).await;
+        for epoch in 1..=3 {
+            app.store(epoch).await;
+        }
+
+        // A reconnecting client's last event id takes precedence over the query it first used
+        let mut stream = connect(&app, Some(1), Some("2")).await;
+
+        assert_eq!(signature_epoch(&next_event(&mut stream).await), 3);
+        assert_no_event(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn test_malformed_last_event_id_is_bad_request() {
+        let app = TestApp::new().await;
+
+        for id in ["", "abc", "-1", "2.0"] {
+            let mut headers = HeaderMap::new();
+            headers.insert(LAST_EVENT_ID, HeaderValue::from_str(id).unwrap());
+            let error = events_handler(
+                State(app.state.clone()),
+                Path(NAMESPACE.to_string()),
+                Query(EventsQuery { from: None }),
+                headers,
+            )
+            .await
+            .err()
+            .unwrap();
+            assert!(
+                matches!(error, ApiError::BadRequest(_)),
+                "Last-Event-ID: {id:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streams_status_changes() {
+        let app = TestApp::with_events(AuditEvents::default()).await;
+        app.record_status(NamespaceStatus::Disabled, Some(1)).await;
+        let replayed = app.record_status(NamespaceStatus::Online, Some(3)).await;
+        app.record_status(NamespaceStatus::Disabled, None).await;
+
+        // Only transitions made while auditing an epoch from the resume point on are replayed
+        let mut stream = connect(&app, Some(2), None).await;
+
+        let event = next_event(&mut stream).await;
+        assert_eq!(event.event, "status");
+        assert_eq!(event.id, None);
+        assert_eq!(
+            serde_json::from_value::<StatusTransition>(event.data).unwrap(),
+            replayed
+        );
+        assert_no_event(&mut stream).await;
+
+        let live = app
+            .record_status(NamespaceStatus::SignatureVerificationFailed, None)
+            .await;
+        let event = next_event(&mut stream).await;
+        assert_eq!(event.event, "status");
+        assert_eq!(
+            serde_json::from_value::<StatusTransition>(event.data).unwrap(),
+            live
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_polls_storage_without_audit_events() {
+        let app = TestApp::new().await;
+        let mut stream = connect(&app, None, None).await;
+        assert_no_event(&mut stream).await;
+
+        let stored_at = tokio::time::Instant::now();
+        app.store(1).await;
+
+        assert_eq!(signature_epoch(&next_event(&mut stream).await), 1);
+        // Nothing wakes the stream, so the signature is only found by the next poll
+        let elapsed = stored_at.elapsed();
+        assert!(elapsed > Duration::ZERO && elapsed <= POLL_INTERVAL);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_waker_ignores_other_namespaces_and_polls_once_closed() {
+        let events = AuditEvents::default();
+        let other = TestApp::new()
+            .await
+            .record_status(NamespaceStatus::Disabled, None)
+            .await;
+        let mut waker = NamespaceWaker::new(NAMESPACE.to_string(), Some(&events));
+
+        events.publish(AuditEvent::StatusChanged(StatusTransition {
+            namespace: "other".to_string(),
+            ..other.clone()
+        }));
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), waker.wait())
+                .await
+                .is_err()
+        );
+        events.publish(AuditEvent::StatusChanged(other));
+        waker.wait().await;
+
+        drop(events);
+        waker.wait().await;
+        assert!(waker.live.is_none());
+        let started = tokio::time::Instant::now();
+        waker.wait().await;
+        assert_eq!(started.elapsed(), POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,149 @@
+use akd_watch_common::{
+    evidence::{EvidenceRecord, EvidenceSummary},
+    storage::namespaces::NamespaceRepository,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use tracing::{error, info, instrument};
+
+use crate::{AppState, error::ApiError};
+
+async fn check_namespace(state: &AppState, namespace: &str) -> Result<(), ApiError> {
+    match state.namespace_storage.get_namespace_info(namespace).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(ApiError::NotFound),
+        Err(e) => {
+            error!(namespace, error = %e, "Failed to get namespace info");
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+#[instrument(skip_all, fields(namespace = %namespace))]
+pub async fn list_evidence_handler(
+    State(state): State<AppState>,
+    Path(namespace): Path<String>,
+) -> Result<Json<Vec<EvidenceSummary>>, ApiError> {
+    info!("Handling evidence list for namespace: {}", namespace);
+    check_namespace(&state, &namespace).await?;
+    state
+        .evidence_store
+        .list(&namespace)
+        .map(Json)
+        .map_err(|e| {
+            error!(namespace, error = %e, "Failed to list evidence");
+            ApiError::Internal
+        })
+}
+
+#[instrument(skip_all, fields(namespace = %namespace, id = %id))]
+pub async fn evidence_query_handler(
+    State(state): State<AppState>,
+    Path((namespace, id)): Path<(String, String)>,
+) -> Result<Json<EvidenceRecord>, ApiError> {
+    info!(
+        "Handling evidence query for namespace: {}, id: {}",
+        namespace, id
+    );
+    check_namespace(&state, &namespace).await?;
+    match state.evidence_store.get(&namespace, &id) {
+        Ok(Some(record)) => Ok(Json(record)),
+        Ok(None) => Err(ApiError::NotFound),
+        Err(e) => {
+            error!(namespace, id, error = %e, "Failed to read evidence");
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use akd::{SingleAppendOnlyProof, local_auditing::AuditBlob};
+    use akd_watch_common::{Epoch, testing::test_namespace};
+
+    use super::*;
+    use crate::testing::{NAMESPACE, TestApp};
+
+    /// Stores evidence that a blob for epoch 2 failed verification
+    fn store_evidence(app: &TestApp) -> String {
+        let blob = AuditBlob::new(
+            [1; 32],
+            [2; 32],
+            2,
+            &SingleAppendOnlyProof {
+                inserted: vec![],
+                unchanged_nodes: vec![],
+            },
+        )
+        .unwrap();
+        let record = EvidenceRecord::sign(
+            &test_namespace(NAMESPACE),
+            &blob,
+            Some([1; 32]),
+            "verification failed".to_string(),
+            1,
+            &app.signing_key,
+        )
+        .unwrap();
+        app.state.evidence_store.save(&record).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_served_evidence_verifies() {
+        let app = TestApp::new().await;
+        let id = store_evidence(&app);
+
+        let Json(summaries) =
+            list_evidence_handler(State(app.state.clone()), Path(NAMESPACE.to_string()))
+                .await
+                .unwrap();
+        assert_eq!(summaries.len(), 1);
+
+        let Json(record) = evidence_query_handler(
+            State(app.state.clone()),
+            Path((NAMESPACE.to_string(), id.clone())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(record.id(), id);
+        assert_eq!(record.epoch(), Epoch::new(2));
+        record
+            .verify(&app.state.verifying_key_storage)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unknown_namespace_is_not_found() {
+        let app = TestApp::new().await;
+        let id = store_evidence(&app);
+
+        let error =
+            evidence_query_handler(State(app.state.clone()), Path(("unknown".to_string(), id)))
+                .await
+                .unwrap_err();
+        assert!(matches!(error, ApiError::NotFound));
+        let error = list_evidence_handler(State(app.state.clone()), Path("unknown".to_string()))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::NotFound));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_epoch_is_not_found() {
+        let app = TestApp::new().await;
+        let id = store_evidence(&app);
+        // The same record id, but for epoch 3
+        let unknown = format!("3{}", id.trim_start_matches('2'));
+
+        let error = evidence_query_handler(
+            State(app.state.clone()),
+            Path((NAMESPACE.to_string(), unknown)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, ApiError::NotFound));
+    }
+}
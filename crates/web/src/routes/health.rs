@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+use akd_watch_common::{
+    NamespaceStatus,
+    storage::{
+        namespaces::NamespaceRepository, signatures::SignatureRepository,
+        signing_keys::VerifyingKeyRepository,
+    },
+};
+use axum::{Json, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use tracing::{instrument, warn};
+
+use crate::AppState;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    status: String,
+}
+
+/// Liveness: the server is up and handling requests
+#[instrument(skip_all)]
+pub async fn healthz_handler() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    ready: bool,
+    /// Each check's error, or `None` if it passed
+    checks: BTreeMap<String, Option<String>>,
+    /// Namespaces whose audits stopped on a failure, with their status. They degrade the response
+    /// without failing it, since their signatures and evidence are still served.
+    failed_namespaces: BTreeMap<String, NamespaceStatus>,
+}
+
+/// Readiness: the data directory, namespace storage, every namespace's signature storage and the
+/// verifying keys can all be read. Responds with `503 Service Unavailable` if any check fails, and
+/// lists the namespaces in a failure status.
+#[instrument(skip_all)]
+pub async fn readyz_handler(
+    axum::extract::State(AppState {
+        namespace_storage,
+        signature_storage,
+        verifying_key_storage,
+        data_directory,
+        ..
+    }): axum::extract::State<AppState>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let mut checks = BTreeMap::new();
+    let mut failed_namespaces = BTreeMap::new();
+
+    let data_directory_check = match std::fs::read_dir(&data_directory) {
+        Ok(_) => None,
+        Err(e) => Some(format!("cannot read {data_directory}: {e}")),
+    };
+    checks.insert("data_directory".to_string(), data_directory_check);
+
+    match namespace_storage.list_namespaces().await {
+        Ok(namespaces) => {
+            checks.insert("namespace_storage".to_string(), None);
+            for namespace in namespaces {
+                let check = match signature_storage
+                    .get(&namespace_storage, &namespace.name)
+                    .await
+                {
+                    Ok(storage) => storage.latest_epoch().await.err().map(|e| e.to_string()),
+                    Err(e) => Some(e.to_string()),
+                };
+                checks.insert(format!("signature_storage:{}", namespace.name), check);
+                if !namespace.status.is_active() && namespace.status != NamespaceStatus::Disabled {
+                    failed_namespaces.insert(namespace.name, namespace.status);
+                }
+            }
+        }
+        Err(e) => {
+            checks.insert("namespace_storage".to_string(), Some(e.to_string()));
+        }
+    }
+
+    let keys_check = verifying_key_storage
+        .list_keys()
+        .await
+        .err()
+        .map(|e| e.to_string());
+    checks.insert("verifying_keys".to_string(), keys_check);
+
+    let ready = checks.values().all(Option::is_none);
+    if !ready {
+        warn!(?checks, "Web server is not ready");
+    }
+    if !failed_namespaces.is_empty() {
+        warn!(?failed_namespaces, "Namespaces are in a failure status");
+    }
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            checks,
+            failed_namespaces,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use akd_watch_common::storage::namespaces::{FileNamespaceRepository, NamespaceStorage};
+    use axum::extract::State;
+
+    use super::*;
+    use crate::testing::{NAMESPACE, TestApp};
+
+    async fn ready_app() -> TestApp {
+        let app = TestApp::new().await;
+        std::fs::create_dir_all(&app.state.data_directory).unwrap();
+        app
+    }
+
+    #[tokio::test]
+    async fn test_ready_when_all_checks_pass() {
+        let app = ready_app().await;
+        app.store(1).await;
+
+        let (status, Json(response)) = readyz_handler(State(app.state.clone())).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.ready);
+        assert_eq!(
+            response.checks.keys().collect::<Vec<_>>(),
+            [
+                "data_directory",
+                "namespace_storage",
+                &format!("signature_storage:{NAMESPACE}"),
+                "verifying_keys"
+            ]
+        );
+        assert!(response.checks.values().all(Option::is_none));
+        assert!(response.failed_namespaces.is_empty());
+        assert_eq!(healthz_handler().await.status, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_namespace_storage_is_not_ready() {
+        let mut app = ready_app().await;
+        let directory = format!("{}/namespaces", app.state.data_directory);
+        let repository = FileNamespaceRepository::new(&directory).unwrap();
+        std::fs::write(FileNamespaceRepository::file_path(&directory), "{corrupt").unwrap();
+        app.state.namespace_storage = NamespaceStorage::File(repository);
+
+        let (status, Json(response)) = readyz_handler(State(app.state.clone())).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!response.ready);
+        assert!(response.checks["namespace_storage"].is_some());
+        assert!(response.checks["data_directory"].is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_namespace_degrades_readiness() {
+        let app = ready_app().await;
+        let mut namespaces = app.state.namespace_storage.clone();
+        let info = namespaces
+            .get_namespace_info(NAMESPACE)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // A disabled namespace was stopped on purpose and is not a failure
+        namespaces
+            .update_namespace(info.update_status(NamespaceStatus::Disabled))
+            .await
+            .unwrap();
+        let (_, Json(response)) = readyz_handler(State(app.state.clone())).await;
+        assert!(response.failed_namespaces.is_empty());
+
+        let info = namespaces
+            .get_namespace_info(NAMESPACE)
+            .await
+            .unwrap()
+            .unwrap();
+        namespaces
+            .update_namespace(info.update_status(NamespaceStatus::SignatureVerificationFailed))
+            .await
+            .unwrap();
+        let (status, Json(response)) = readyz_handler(State(app.state.clone())).await;
+
+        // The failed namespace's signatures are still served, so the server stays ready
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.ready);
+        assert_eq!(
+            response.failed_namespaces,
+            BTreeMap::from([(
+                NAMESPACE.to_string(),
+                NamespaceStatus::SignatureVerificationFailed
+            )])
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use akd_watch_common::{Ciphersuite, Epoch, Heartbeat, storage::signatures::SignatureRepository};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, trace};
+
+use crate::{AppState, error::ApiError};
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct HeartbeatResponse {
+    version: u32,
+    ciphersuite: Ciphersuite,
+    namespace: String,
+    observed_at: u64,
+    epoch: Epoch,
+    digest: String,
+    signature: String,
+    key_id: String,
+}
+
+impl From<Heartbeat> for HeartbeatResponse {
+    fn from(heartbeat: Heartbeat) -> Self {
+        let version = heartbeat.version_int();
+        match heartbeat {
+            Heartbeat::V1(heartbeat) => HeartbeatResponse {
+                version,
+                ciphersuite: heartbeat.ciphersuite,
+                namespace: heartbeat.namespace,
+                observed_at: heartbeat.observed_at as u64,
+                epoch: heartbeat.epoch,
+                digest: hex::encode(heartbeat.digest),
+                signature: hex::encode(heartbeat.signature),
+                key_id: heartbeat.key_id.to_string(),
+            },
+        }
+    }
+}
+
+/// Serves the namespace's latest heartbeat, or not found until the auditor has signed one
+#[instrument(skip_all, fields(namespace = %namespace))]
+pub async fn heartbeat_query_handler(
+    axum::extract::State(AppState {
+        namespace_storage,
+        signature_storage,
+        ..
+    }): axum::extract::State<AppState>,
+    axum::extract::Path(namespace): axum::extract::Path<String>,
+) -> Result<Json<HeartbeatResponse>, ApiError> {
+    info!("Handling heartbeat query for namespace: {}", namespace);
+    let namespace_signature_storage = signature_storage
+        .get(&namespace_storage, &namespace)
+        .await?;
+    trace!(namespace, "Found namespace storage for heartbeat query");
+
+    match namespace_signature_storage.get_heartbeat().await {
+        Ok(Some(heartbeat)) => Ok(Json(heartbeat.into())),
+        Ok(None) => Err(ApiError::NotFound),
+        Err(e) => {
+            tracing::error!("Failed to get heartbeat for namespace {}: {}", namespace, e);
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use akd_watch_common::storage::signatures::SignatureRepository;
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+        response::IntoResponse,
+    };
+
+    use super::*;
+    use crate::testing::{NAMESPACE, TestApp};
+
+    /// Rebuilds the heartbeat a client would verify from the served JSON
+    fn heartbeat_from_response(response: &HeartbeatResponse) -> Heartbeat {
+        let mut value = serde_json::to_value(response).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("version");
+        fields.insert("heartbeat_version".to_string(), "V1".into());
+        for field in ["digest", "signature"] {
+            let bytes = hex::decode(fields[field].as_str().unwrap()).unwrap();
+            fields.insert(field.to_string(), bytes.into());
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_served_heartbeat_verifies() {
+        let app = TestApp::new().await;
+        let latest = app.store(7).await;
+        let heartbeat = Heartbeat::sign(NAMESPACE, &latest, &app.signing_key).unwrap();
+        app.storage.clone().set_heartbeat(heartbeat).await.unwrap();
+
+        let Json(response) =
+            heartbeat_query_handler(State(app.state.clone()), Path(NAMESPACE.to_string()))
+                .await
+                .unwrap();
+
+        assert_eq!(response.namespace, NAMESPACE);
+        assert_eq!(response.epoch, Epoch::new(7));
+        assert_eq!(response.digest, latest.digest_hex());
+        heartbeat_from_response(&response)
+            .verify(&app.state.verifying_key_storage)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_heartbeat_is_not_found() {
+        let app = TestApp::new().await;
+        app.store(7).await;
+
+        let error = heartbeat_query_handler(State(app.state.clone()), Path(NAMESPACE.to_string()))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.into_response().status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,87 @@
+use akd_watch_common::metrics;
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::instrument;
+
+use crate::AppState;
+
+/// Prometheus metrics for this process. In the all-in-one binary these include the auditor's.
+#[instrument(skip_all)]
+pub async fn metrics_handler(
+    axum::extract::State(AppState {
+        verifying_key_storage,
+        ..
+    }): axum::extract::State<AppState>,
+) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::render(&verifying_key_storage).await,
+    )
+}
+
+/// Counts each request and records its latency under the route pattern it matched. Streaming
+/// responses are timed until their headers are sent.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let tic = tokio::time::Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let response = next.run(request).await;
+    metrics::record_http_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        tic.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use akd_watch_common::NamespaceStatus;
+    use axum::extract::State;
+
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    async fn test_scrape_exposes_namespace_gauges() {
+        let app = TestApp::new().await;
+        metrics::install();
+        metrics::record_namespace_status("scraped", &NamespaceStatus::SignatureConflict);
+        metrics::record_epochs_behind_head("scraped", 3);
+
+        let response = metrics_handler(State(app.state.clone()))
+            .await
+            .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            metrics::CONTENT_TYPE
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        for line in [
+            "# TYPE akd_watch_namespace_status gauge",
+            "akd_watch_namespace_status{namespace=\"scraped\",status=\"SignatureConflict\"} 1",
+            "akd_watch_namespace_status{namespace=\"scraped\",status=\"Online\"} 0",
+            "# TYPE akd_watch_epochs_behind_head gauge",
+            "akd_watch_epochs_behind_head{namespace=\"scraped\"} 3",
+            "# TYPE akd_watch_signing_key_age_seconds gauge",
+        ] {
+            assert!(
+                body.lines().any(|rendered| rendered == line),
+                "{line} in {body}"
+            );
+        }
+    }
+}
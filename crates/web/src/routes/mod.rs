@@ -1,21 +1,71 @@
-use axum::{Router, routing::get};
+use axum::{Router, middleware, routing::get};
 
 use crate::AppState;
 
-mod audits;
+pub(crate) mod audits;
+pub(crate) mod events;
+mod evidence;
+mod health;
+mod heartbeats;
 mod info;
+mod metrics;
 mod namespaces;
+mod negotiation;
+mod transparency_log;
 
 pub fn api_routes() -> Router<AppState> {
     Router::new()
+        .route("/healthz", get(health::healthz_handler))
+        .route("/readyz", get(health::readyz_handler))
+        .route("/metrics", get(metrics::metrics_handler))
         .route("/info", get(info::info_handler))
         .route("/namespaces", get(namespaces::list_namespaces_handler))
         .route(
             "/namespaces/:namespace",
             get(namespaces::namespace_query_handler),
         )
+        .route(
+            "/namespaces/:namespace/heartbeat",
+            get(heartbeats::heartbeat_query_handler),
+        )
+        .route(
+            "/namespaces/:namespace/audits",
+            get(audits::audit_range_query_handler),
+        )
+        .route(
+            "/namespaces/:namespace/audits/stream",
+            get(audits::audit_stream_handler),
+        )
+        .route(
+            "/namespaces/:namespace/audits/latest",
+            get(audits::latest_audit_query_handler),
+        )
         .route(
             "/namespaces/:namespace/audits/:epoch",
             get(audits::audit_query_handler),
         )
+        .route("/namespaces/:namespace/events", get(events::events_handler))
+        .route(
+            "/namespaces/:namespace/evidence",
+            get(evidence::list_evidence_handler),
+        )
+        .route(
+            "/namespaces/:namespace/evidence/:id",
+            get(evidence::evidence_query_handler),
+        )
+        .route(
+            "/namespaces/:namespace/audits/:epoch/inclusion-proof",
+            get(transparency_log::audit_inclusion_proof_handler),
+        )
+        .route("/log/tree-head", get(transparency_log::tree_head_handler))
+        .route("/log/entries/:index", get(transparency_log::entry_handler))
+        .route(
+            "/log/proofs/inclusion",
+            get(transparency_log::inclusion_proof_handler),
+        )
+        .route(
+            "/log/proofs/consistency",
+            get(transparency_log::consistency_proof_handler),
+        )
+        .route_layer(middleware::from_fn(metrics::track_requests))
 }
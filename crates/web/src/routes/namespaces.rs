@@ -1,21 +1,41 @@
-use akd_watch_common::{NamespaceInfo, storage::namespaces::NamespaceRepository};
+use akd_watch_common::{NamespaceInfo, StatusTransition, storage::namespaces::NamespaceRepository};
 use axum::Json;
+use serde::{Deserialize, Serialize};
 use tracing::{info, instrument};
 
 use crate::{error::ApiError, routes::AppState};
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NamespaceResponse {
+    #[serde(flatten)]
+    info: NamespaceInfo,
+    /// Status transitions, oldest first
+    status_history: Vec<StatusTransition>,
+}
+
 #[instrument(skip_all, fields(namespace))]
 pub async fn namespace_query_handler(
     axum::extract::State(AppState {
         namespace_storage, ..
     }): axum::extract::State<AppState>,
     axum::extract::Path(namespace): axum::extract::Path<String>,
-) -> Result<Json<Option<NamespaceInfo>>, ApiError> {
+) -> Result<Json<Option<NamespaceResponse>>, ApiError> {
     info!("Handling namespace query for namespace: {}", namespace);
-    match namespace_storage.get_namespace_info(&namespace).await {
-        Ok(info) => Ok(Json(info)),
+    let info = match namespace_storage.get_namespace_info(&namespace).await {
+        Ok(Some(info)) => info,
+        Ok(None) => return Ok(Json(None)),
         Err(e) => {
             tracing::error!("Failed to get namespace info: {}", e);
+            return Err(ApiError::Internal);
+        }
+    };
+    match namespace_storage.status_history(&namespace).await {
+        Ok(status_history) => Ok(Json(Some(NamespaceResponse {
+            info,
+            status_history,
+        }))),
+        Err(e) => {
+            tracing::error!("Failed to get namespace status history: {}", e);
             Err(ApiError::Internal)
         }
     }
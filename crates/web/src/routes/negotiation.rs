@@ -0,0 +1,349 @@
+use akd_watch_common::EpochSignature;
+use axum::{
+    Json,
+    http::{
+        HeaderMap, HeaderName, HeaderValue,
+        header::{ACCEPT, CONTENT_TYPE, VARY},
+    },
+    response::{IntoResponse, Response},
+};
+use tracing::error;
+
+use super::audits::SignatureResponse;
+use crate::error::ApiError;
+
+const JSON: &str = "application/json";
+const PROTOBUF: &str = "application/x-protobuf";
+const SIGNED_MESSAGE: &str = "application/octet-stream";
+
+/// Hex encoded signature over the body of a signed message response
+pub(crate) const SIGNATURE_HEADER: HeaderName = HeaderName::from_static("akd-watch-signature");
+/// Id of the key that produced the signature of a signed message response
+pub(crate) const KEY_ID_HEADER: HeaderName = HeaderName::from_static("akd-watch-key-id");
+
+/// Representation of an epoch signature selected by the request's `Accept` header
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SignatureFormat {
+    /// `SignatureResponse` as JSON, with hex encoded bytes
+    Json,
+    /// Protobuf `SignatureEnvelope` from `proto/specs/types.proto`
+    Protobuf,
+    /// The exact bytes that were signed, with the signature and key id in headers
+    SignedMessage,
+}
+
+impl SignatureFormat {
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            JSON | "application/*" | "*/*" => Some(SignatureFormat::Json),
+            PROTOBUF => Some(SignatureFormat::Protobuf),
+            SIGNED_MESSAGE => Some(SignatureFormat::SignedMessage),
+            _ => None,
+        }
+    }
+
+    /// Short name distinguishing the representations of a resource, e.g. in entity tags
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SignatureFormat::Json => "json",
+            SignatureFormat::Protobuf => "protobuf",
+            SignatureFormat::SignedMessage => "signed-message",
+        }
+    }
+
+    /// Picks the supported format the client prefers most. JSON is served when there is no
+    /// `Accept` header.
+    pub(crate) fn negotiate(headers: &HeaderMap) -> Result<Self, ApiError> {
+        let mut accepted = Vec::new();
+        for value in headers.get_all(ACCEPT) {
+            let value = value
+                .to_str()
+                .map_err(|_| ApiError::BadRequest("invalid Accept header".to_string()))?;
+            for media_range in value.split(',') {
+                let mut parameters = media_range.split(';');
+                let media_type = parameters.next().unwrap_or_default().trim();
+                let quality = parameters
+                    .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                    .find_map(|quality| quality.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                if quality > 0.0 {
+                    accepted.push((media_type.to_ascii_lowercase(), quality));
+                }
+            }
+        }
+        if accepted.is_empty() && headers.get(ACCEPT).is_none() {
+            return Ok(SignatureFormat::Json);
+        }
+
+        // Stable sort keeps the client's order among equal qualities
+        accepted.sort_by(|a, b| b.1.total_cmp(&a.1));
+        accepted
+            .iter()
+            .find_map(|(media_type, _)| Self::from_media_type(media_type))
+            .ok_or_else(|| {
+                ApiError::NotAcceptable(format!(
+                    "supported media types are {JSON}, {PROTOBUF} and {SIGNED_MESSAGE}"
+                ))
+            })
+    }
+
+    /// Renders `signature` in this format. A missing signature is `null` in JSON and not found
+    /// otherwise.
+    pub(crate) fn respond(self, signature: Option<EpochSignature>) -> Result<Response, ApiError> {
+        let vary = (VARY, HeaderValue::from_static("accept"));
+        let encode_error = |e: &dyn std::fmt::Display| {
+            error!(error = %e, "Failed to encode signature");
+            ApiError::Internal
+        };
+        match self {
+            SignatureFormat::Json => {
+                Ok(([vary], Json(signature.map(SignatureResponse::from))).into_response())
+            }
+            SignatureFormat::Protobuf => {
+                let signature = signature.ok_or(ApiError::NotFound)?;
+                let body = signature.to_protobuf().map_err(|e| encode_error(&e))?;
+                Ok((
+                    [vary, (CONTENT_TYPE, HeaderValue::from_static(PROTOBUF))],
+                    body,
+                )
+                    .into_response())
+            }
+            SignatureFormat::SignedMessage => {
+                let signature = signature.ok_or(ApiError::NotFound)?;
+                let body = signature.signed_message().map_err(|e| encode_error(&e))?;
+                let signature_header =
+                    HeaderValue::from_str(&hex::encode(signature.signature_bytes()))
+                        .map_err(|e| encode_error(&e))?;
+                let key_id_header = HeaderValue::from_str(&signature.signing_key_id().to_string())
+                    .map_err(|e| encode_error(&e))?;
+                Ok((
+                    [
+                        vary,
+                        (CONTENT_TYPE, HeaderValue::from_static(SIGNED_MESSAGE)),
+                        (SIGNATURE_HEADER, signature_header),
+                        (KEY_ID_HEADER, key_id_header),
+                    ],
+                    body,
+                )
+                    .into_response())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use akd_watch_common::crypto::VerifyingKey;
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use prost::Message;
+
+    use super::*;
+    use crate::{
+        routes::{audits::audit_query_handler, info::info_handler},
+        testing::{NAMESPACE, TestApp},
+    };
+
+    /// Client side copy of `SignatureEnvelope` from `proto/specs/types.proto`
+    #[derive(Clone, PartialEq, Message)]
+    struct SignatureEnvelope {
+        #[prost(uint32, required, tag = "1")]
+        version: u32,
+        #[prost(message, required, tag = "2")]
+        message: SignatureMessage,
+        #[prost(bytes = "vec", required, tag = "3")]
+        signed_message: Vec<u8>,
+        #[prost(bytes = "vec", required, tag = "4")]
+        signature: Vec<u8>,
+        #[prost(string, required, tag = "5")]
+        key_id: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct SignatureMessage {
+        #[prost(string, required, tag = "2")]
+        namespace: String,
+        #[prost(message, required, tag = "4")]
+        epoch: EpochMessage,
+        #[prost(bytes = "vec", required, tag = "5")]
+        digest: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct EpochMessage {
+        #[prost(uint64, required, tag = "1")]
+        inner: u64,
+    }
+
+    fn accept(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(ACCEPT, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    async fn audit(app: &TestApp, epoch: u64, accepted: &str) -> Result<Response, ApiError> {
+        audit_query_handler(
+            State(app.state.clone()),
+            Path((NAMESPACE.to_string(), epoch.to_string())),
+            accept(&[accepted]),
+        )
+        .await
+    }
+
+    async fn body(response: Response) -> Vec<u8> {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    async fn published_keys(app: &TestApp) -> Vec<VerifyingKey> {
+        let info = info_handler(State(app.state.clone())).await.into_response();
+        VerifyingKey::from_published_json(&body(info).await).unwrap()
+    }
+
+    #[test]
+    fn test_negotiate_prefers_highest_quality() {
+        let cases = [
+            (vec![], SignatureFormat::Json),
+            (vec!["*/*"], SignatureFormat::Json),
+            (vec!["application/*"], SignatureFormat::Json),
+            (vec![PROTOBUF], SignatureFormat::Protobuf),
+            (vec!["Application/X-Protobuf"], SignatureFormat::Protobuf),
+            (vec![SIGNED_MESSAGE], SignatureFormat::SignedMessage),
+            (
+                vec!["application/json;q=0.5, application/x-protobuf"],
+                SignatureFormat::Protobuf,
+            ),
+            (
+                vec![
+                    "application/x-protobuf; q=0.2",
+                    "application/octet-stream;q=0.9",
+                ],
+                SignatureFormat::SignedMessage,
+            ),
+            // Unsupported types are skipped in favour of the next supported one
+            (
+                vec!["text/html, application/octet-stream;q=0.1"],
+                SignatureFormat::SignedMessage,
+            ),
+            // q=0 excludes a type, even one matched by a wildcard
+            (
+                vec!["application/json;q=0, application/x-protobuf;q=0.1"],
+                SignatureFormat::Protobuf,
+            ),
+        ];
+        for (accepted, expected) in cases {
+            assert_eq!(
+                SignatureFormat::negotiate(&accept(&accepted)).unwrap(),
+                expected,
+                "Accept: {accepted:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_negotiate_keeps_client_order_among_ties() {
+        assert_eq!(
+            SignatureFormat::negotiate(&accept(&["application/octet-stream, application/json"]))
+                .unwrap(),
+            SignatureFormat::SignedMessage
+        );
+        assert_eq!(
+            SignatureFormat::negotiate(&accept(&[
+                "application/x-protobuf;q=0.5, application/octet-stream;q=0.5"
+            ]))
+            .unwrap(),
+            SignatureFormat::Protobuf
+        );
+    }
+
+    #[test]
+    fn test_negotiate_rejects_unsupported_types() {
+        for accepted in [
+            "text/html",
+            "application/json;q=0",
+            "application/json;q=0, */*;q=0",
+            "",
+        ] {
+            let error = SignatureFormat::negotiate(&accept(&[accepted])).unwrap_err();
+            assert!(
+                matches!(error, ApiError::NotAcceptable(_)),
+                "Accept: {accepted:?}"
+            );
+            assert_eq!(error.into_response().status(), StatusCode::NOT_ACCEPTABLE);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protobuf_body_is_a_signature_envelope() {
+        let app = TestApp::new().await;
+        let signature = app.store(4).await;
+
+        let response = audit(&app, 4, PROTOBUF).await.unwrap();
+
+        assert_eq!(response.headers()[CONTENT_TYPE], PROTOBUF);
+        assert_eq!(response.headers()[VARY], "accept");
+        let envelope = SignatureEnvelope::decode(body(response).await.as_slice()).unwrap();
+        assert_eq!(envelope.version, signature.version_int());
+        assert_eq!(envelope.message.namespace, NAMESPACE);
+        assert_eq!(envelope.message.epoch.inner, 4);
+        assert_eq!(envelope.message.digest, signature.digest());
+        assert_eq!(envelope.signed_message, signature.signed_message().unwrap());
+        assert_eq!(envelope.signature, signature.signature_bytes());
+        assert_eq!(envelope.key_id, signature.signing_key_id().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_signed_message_verifies_against_published_keys() {
+        let app = TestApp::new().await;
+        app.store(4).await;
+
+        let response = audit(&app, 4, SIGNED_MESSAGE).await.unwrap();
+
+        assert_eq!(response.headers()[CONTENT_TYPE], SIGNED_MESSAGE);
+        let key_id: uuid::Uuid = response.headers()[KEY_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let signature = hex::decode(response.headers()[SIGNATURE_HEADER].as_bytes()).unwrap();
+        let signature = ed25519_dalek::Signature::from_slice(&signature).unwrap();
+        let signed_message = body(response).await;
+        let key = published_keys(&app)
+            .await
+            .into_iter()
+            .find(|key| key.key_id == key_id)
+            .unwrap();
+        key.verifying_key
+            .verify_strict(&signed_message, &signature)
+            .unwrap();
+
+        let mut tampered = signed_message;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(
+            key.verifying_key
+                .verify_strict(&tampered, &signature)
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_signature_is_null_only_in_json() {
+        let app = TestApp::new().await;
+
+        let response = audit(&app, 4, JSON).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, b"null");
+
+        for accepted in [PROTOBUF, SIGNED_MESSAGE] {
+            let error = audit(&app, 4, accepted).await.unwrap_err();
+            assert!(matches!(error, ApiError::NotFound), "Accept: {accepted}");
+        }
+    }
+}
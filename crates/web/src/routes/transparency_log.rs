@@ -0,0 +1,300 @@
+use akd_watch_common::{
+    Ciphersuite,
+    transparency_log::{Hash, SignedTreeHead, TransparencyLog},
+};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument, trace};
+
+use super::audits::SignatureResponse;
+use crate::{AppState, error::ApiError};
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TreeHeadResponse {
+    version: u32,
+    ciphersuite: Ciphersuite,
+    tree_size: u64,
+    root_hash: String,
+    timestamp: i64,
+    signature: String,
+    key_id: String,
+}
+
+impl From<SignedTreeHead> for TreeHeadResponse {
+    fn from(tree_head: SignedTreeHead) -> Self {
+        let version = tree_head.version_int();
+        match tree_head {
+            SignedTreeHead::V1(head) => TreeHeadResponse {
+                version,
+                ciphersuite: head.ciphersuite,
+                tree_size: head.tree_size,
+                root_hash: hex::encode(head.root_hash),
+                timestamp: head.timestamp,
+                signature: hex::encode(head.signature),
+                key_id: head.key_id.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct LogEntryResponse {
+    index: u64,
+    leaf_hash: String,
+    /// Protobuf `LogEntry` encoding of the signature; the leaf hash is computed over these bytes
+    leaf: String,
+    signature: SignatureResponse,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct InclusionProofResponse {
+    index: u64,
+    tree_size: u64,
+    leaf_hash: String,
+    audit_path: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ConsistencyProofResponse {
+    first: u64,
+    second: u64,
+    consistency_path: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InclusionProofQuery {
+    index: u64,
+    tree_size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreeSizeQuery {
+    tree_size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyProofQuery {
+    first: u64,
+    second: Option<u64>,
+}
+
+fn encode_path(path: Vec<Hash>) -> Vec<String> {
+    path.into_iter().map(hex::encode).collect()
+}
+
+fn internal(e: impl std::fmt::Display) -> ApiError {
+    error!(error = %e, "Failed to read transparency log");
+    ApiError::Internal
+}
+
+/// Runs `f` against the auditor's transparency log after picking up any entries it has appended.
+/// The log is opened on first use, since the auditor may create it after the web server starts.
+async fn with_log<T>(
+    state: &AppState,
+    f: impl FnOnce(&TransparencyLog) -> Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    let mut transparency_log = state.transparency_log.lock().await;
+    match transparency_log.as_mut() {
+        Some(log) => {
+            log.refresh().map_err(internal)?;
+        }
+        None => {
+            *transparency_log = TransparencyLog::open(&state.data_directory).map_err(internal)?;
+        }
+    }
+    let log = transparency_log.as_ref().ok_or(ApiError::NotFound)?;
+    f(log)
+}
+
+/// Tree size to prove against when the client does not name one: the latest signed tree head
+fn signed_tree_size(log: &TransparencyLog, tree_size: Option<u64>) -> Result<u64, ApiError> {
+    match tree_size {
+        Some(tree_size) => Ok(tree_size),
+        None => Ok(log
+            .latest_tree_head()
+            .map_err(internal)?
+            .ok_or(ApiError::NotFound)?
+            .tree_size()),
+    }
+}
+
+fn inclusion_proof(
+    log: &TransparencyLog,
+    index: u64,
+    tree_size: u64,
+) -> Result<InclusionProofResponse, ApiError> {
+    let audit_path = log.inclusion_proof(index, tree_size).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "no inclusion proof for entry {index} in a tree of size {tree_size} (log size {})",
+            log.size()
+        ))
+    })?;
+    let leaf_hash = log.leaf_hash(index).ok_or(ApiError::NotFound)?;
+    Ok(InclusionProofResponse {
+        index,
+        tree_size,
+        leaf_hash: hex::encode(leaf_hash),
+        audit_path: encode_path(audit_path),
+    })
+}
+
+#[instrument(skip_all)]
+pub async fn tree_head_handler(
+    State(state): State<AppState>,
+) -> Result<Json<TreeHeadResponse>, ApiError> {
+    with_log(&state, |log| {
+        let tree_head = log
+            .latest_tree_head()
+            .map_err(internal)?
+            .ok_or(ApiError::NotFound)?;
+        trace!(tree_size = tree_head.tree_size(), "Serving tree head");
+        Ok(Json(tree_head.into()))
+    })
+    .await
+}
+
+#[instrument(skip_all, fields(index))]
+pub async fn entry_handler(
+    State(state): State<AppState>,
+    Path(index): Path<u64>,
+) -> Result<Json<LogEntryResponse>, ApiError> {
+    with_log(&state, |log| {
+        let leaf = log
+            .entry_bytes(index)
+            .map_err(internal)?
+            .ok_or(ApiError::NotFound)?;
+        let signature = log
+            .entry(index)
+            .map_err(internal)?
+            .ok_or(ApiError::NotFound)?;
+        let leaf_hash = log.leaf_hash(index).ok_or(ApiError::NotFound)?;
+        Ok(Json(LogEntryResponse {
+            index,
+            leaf_hash: hex::encode(leaf_hash),
+            leaf: hex::encode(leaf),
+            signature: signature.into(),
+        }))
+    })
+    .await
+}
+
+#[instrument(skip_all, fields(index = query.index, tree_size = query.tree_size))]
+pub async fn inclusion_proof_handler(
+    State(state): State<AppState>,
+    Query(query): Query<InclusionProofQuery>,
+) -> Result<Json<InclusionProofResponse>, ApiError> {
+    with_log(&state, |log| {
+        let tree_size = signed_tree_size(log, query.tree_size)?;
+        inclusion_proof(log, query.index, tree_size).map(Json)
+    })
+    .await
+}
+
+#[instrument(skip_all, fields(namespace = %namespace, epoch))]
+pub async fn audit_inclusion_proof_handler(
+    State(state): State<AppState>,
+    Path((namespace, epoch)): Path<(String, String)>,
+    Query(query): Query<TreeSizeQuery>,
+) -> Result<Json<InclusionProofResponse>, ApiError> {
+    let epoch: u64 = epoch
+        .parse()
+        .map_err(|_| ApiError::BadRequest("epoch is not an integer".to_string()))?;
+    with_log(&state, |log| {
+        let index = log.find(&namespace, epoch).ok_or(ApiError::NotFound)?;
+        let tree_size = signed_tree_size(log, query.tree_size)?;
+        inclusion_proof(log, index, tree_size).map(Json)
+    })
+    .await
+}
+
+#[instrument(skip_all, fields(first = query.first, second = query.second))]
+pub async fn consistency_proof_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ConsistencyProofQuery>,
+) -> Result<Json<ConsistencyProofResponse>, ApiError> {
+    with_log(&state, |log| {
+        let second = signed_tree_size(log, query.second)?;
+        let consistency_path = log.consistency_proof(query.first, second).ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "no consistency proof from tree size {} to {second} (log size {})",
+                query.first,
+                log.size()
+            ))
+        })?;
+        Ok(Json(ConsistencyProofResponse {
+            first: query.first,
+            second,
+            consistency_path: encode_path(consistency_path),
+        }))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    async fn test_served_tree_head_verifies() {
+        let app = TestApp::new().await;
+        let mut log = TransparencyLog::open_or_create(&app.state.data_directory).unwrap();
+        for epoch in 1..=3 {
+            log.append(&app.store(epoch).await).unwrap();
+        }
+        let root_hash = log.root(log.size()).unwrap();
+        log.publish_tree_head(
+            &SignedTreeHead::sign(log.size(), root_hash, &app.signing_key).unwrap(),
+        )
+        .unwrap();
+
+        let Json(response) = tree_head_handler(State(app.state.clone())).await.unwrap();
+        assert_eq!(response.tree_size, 3);
+        assert_eq!(response.root_hash, hex::encode(root_hash));
+        assert_eq!(response.key_id, app.signing_key.key_id().to_string());
+
+        // Verifying prepends the tree head domain separator to the signed message
+        let tree_head = |tree_size: u64| -> SignedTreeHead {
+            serde_json::from_value(serde_json::json!({
+                "tree_head_version": "V1",
+                "ciphersuite": response.ciphersuite,
+                "tree_size": tree_size,
+                "root_hash": hex::decode(&response.root_hash).unwrap(),
+                "timestamp": response.timestamp,
+                "signature": hex::decode(&response.signature).unwrap(),
+                "key_id": response.key_id,
+            }))
+            .unwrap()
+        };
+        tree_head(response.tree_size)
+            .verify(&app.state.verifying_key_storage)
+            .await
+            .unwrap();
+        assert!(
+            tree_head(2)
+                .verify(&app.state.verifying_key_storage)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_log_has_no_tree_head() {
+        let app = TestApp::new().await;
+        // Before the auditor creates the log
+        let error = tree_head_handler(State(app.state.clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::NotFound));
+
+        // Once it exists, but before its first tree head is published
+        TransparencyLog::open_or_create(&app.state.data_directory).unwrap();
+        let error = tree_head_handler(State(app.state.clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::NotFound));
+    }
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashMap, sync::Arc};
+
+use akd_watch_common::{
+    config::SignatureStorageConfig,
+    storage::{
+        namespaces::{NamespaceRepository, NamespaceStorage},
+        signatures::SignatureStorage,
+    },
+};
+use tokio::sync::RwLock;
+use tracing::{debug, error};
+
+use crate::error::ApiError;
+
+/// Signature storage handles for the namespaces known to the namespace repository.
+///
+/// Handles are built on first use, so namespaces the auditor adds after the web server started are
+/// served without a restart.
+#[derive(Clone)]
+pub(crate) struct SignatureStorageCache {
+    config: SignatureStorageConfig,
+    data_directory: String,
+    storage: Arc<RwLock<HashMap<String, SignatureStorage>>>,
+}
+
+impl SignatureStorageCache {
+    pub(crate) fn new(
+        config: SignatureStorageConfig,
+        data_directory: String,
+        storage: HashMap<String, SignatureStorage>,
+    ) -> Self {
+        Self {
+            config,
+            data_directory,
+            storage: Arc::new(RwLock::new(storage)),
+        }
+    }
+
+    /// Returns the signature storage of `namespace`, building it if the namespace was added since
+    /// startup. Fails with a bad request if the namespace does not exist.
+    pub(crate) async fn get(
+        &self,
+        namespace_storage: &NamespaceStorage,
+        namespace: &str,
+    ) -> Result<SignatureStorage, ApiError> {
+        if let Some(storage) = self.storage.read().await.get(namespace) {
+            return Ok(storage.clone());
+        }
+
+        let namespace_info = namespace_storage
+            .get_namespace_info(namespace)
+            .await
+            .map_err(|e| {
+                error!(namespace, error = %e, "Failed to get namespace info");
+                ApiError::Internal
+            })?
+            .ok_or_else(|| ApiError::BadRequest(format!("namespace {namespace} not found")))?;
+
+        let mut storage = self.storage.write().await;
+        // Another request may have built it while the lock was released
+        if let Some(existing) = storage.get(namespace) {
+            return Ok(existing.clone());
+        }
+        debug!(namespace, "Building signature storage for new namespace");
+        let built = self
+            .config
+            .build_namespace_signature_storage(&namespace_info, &self.data_directory)
+            .await
+            .map_err(|e| {
+                error!(namespace, error = %e, "Failed to build signature storage");
+                ApiError::Internal
+            })?;
+        storage.insert(namespace.to_string(), built.clone());
+        Ok(built)
+    }
+}
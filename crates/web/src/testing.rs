@@ -0,0 +1,126 @@
+// Fixture for calling the HTTP and gRPC handlers directly against in-memory storage
+
+use std::{collections::HashMap, sync::Arc};
+
+use akd_watch_common::{
+    Epoch, EpochSignature, NamespaceInfo, NamespaceStatus, StatusActor, StatusTransition,
+    config::SignatureStorageConfig,
+    crypto::SigningKey,
+    events::{AuditEvent, AuditEvents},
+    evidence::EvidenceStore,
+    storage::{
+        namespaces::{InMemoryNamespaceRepository, NamespaceRepository, NamespaceStorage},
+        signatures::{InMemorySignatureStorage, SignatureRepository, SignatureStorage},
+        signing_keys::{InMemorySigningKeyRepository, SigningKeyRepository},
+    },
+    testing::test_namespace,
+};
+use tokio::sync::Mutex;
+
+use crate::{AppState, signature_storage_cache::SignatureStorageCache};
+
+pub(crate) const NAMESPACE: &str = "test";
+
+/// An [`AppState`] serving one namespace, [`NAMESPACE`], from in-memory storage
+pub(crate) struct TestApp {
+    pub(crate) state: AppState,
+    pub(crate) storage: InMemorySignatureStorage,
+    namespace_info: NamespaceInfo,
+    pub(crate) signing_key: SigningKey,
+}
+
+impl TestApp {
+    pub(crate) async fn new() -> Self {
+        Self::build(None).await
+    }
+
+    /// Publishes stored signatures to `events`, as an auditor in the same process does
+    pub(crate) async fn with_events(events: AuditEvents) -> Self {
+        Self::build(Some(events)).await
+    }
+
+    async fn build(events: Option<AuditEvents>) -> Self {
+        let namespace_info = test_namespace(NAMESPACE);
+        let mut namespaces = InMemoryNamespaceRepository::new();
+        namespaces
+            .add_namespace(namespace_info.clone())
+            .await
+            .unwrap();
+        let signing_keys = InMemorySigningKeyRepository::new(chrono::Duration::days(1));
+        let signing_key = signing_keys.get_current_signing_key().await.unwrap();
+        let storage = InMemorySignatureStorage::new();
+        let data_directory = std::env::temp_dir()
+            .join(format!("akd-watch-web-{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+
+        let state = AppState {
+            namespace_storage: NamespaceStorage::InMemory(namespaces),
+            signature_storage: SignatureStorageCache::new(
+                SignatureStorageConfig::InMemory,
+                data_directory.clone(),
+                HashMap::from([(
+                    NAMESPACE.to_string(),
+                    SignatureStorage::InMemory(storage.clone()),
+                )]),
+            ),
+            verifying_key_storage: signing_keys.verifying_key_repository().unwrap(),
+            evidence_store: EvidenceStore::new(&data_directory),
+            transparency_log: Arc::new(Mutex::new(None)),
+            events,
+            data_directory,
+        };
+        Self {
+            state,
+            storage,
+            namespace_info,
+            signing_key,
+        }
+    }
+
+    /// Signs and stores `epoch`, publishing it if the app has events
+    pub(crate) async fn store(&self, epoch: u64) -> EpochSignature {
+        let signature = EpochSignature::sign(
+            self.namespace_info.clone(),
+            Epoch::new(epoch),
+            [epoch as u8; 32],
+            &self.signing_key,
+        )
+        .unwrap();
+        self.storage
+            .clone()
+            .set_signature(&epoch, signature.clone())
+            .await
+            .unwrap();
+        if let Some(events) = &self.state.events {
+            events.publish(AuditEvent::SignatureStored(signature.clone()));
+        }
+        signature
+    }
+
+    /// Records a transition of the namespace to `status` while auditing `epoch`, publishing it if
+    /// the app has events
+    pub(crate) async fn record_status(
+        &self,
+        status: NamespaceStatus,
+        epoch: Option<u64>,
+    ) -> StatusTransition {
+        let transition = StatusTransition::new(
+            &self.namespace_info,
+            status,
+            epoch.map(Epoch::new),
+            None,
+            StatusActor::Auditor,
+        );
+        self.state
+            .namespace_storage
+            .clone()
+            .record_status_transition(transition.clone())
+            .await
+            .unwrap();
+        if let Some(events) = &self.state.events {
+            events.publish(AuditEvent::StatusChanged(transition.clone()));
+        }
+        transition
+    }
+}
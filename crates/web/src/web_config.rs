@@ -6,6 +6,10 @@ fn default_bind_address() -> String {
     "127.0.0.1:3000".to_string()
 }
 
+fn default_grpc_bind_address() -> String {
+    "127.0.0.1:3001".to_string()
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WebConfig {
     /// Address to bind the web server to
@@ -13,6 +17,11 @@ pub struct WebConfig {
     #[serde(default = "default_bind_address")]
     pub bind_address: String,
 
+    /// Address to bind the gRPC server to
+    /// Defaults to 127.0.0.1:3001
+    #[serde(default = "default_grpc_bind_address")]
+    pub grpc_bind_address: String,
+
     /// Directory for storing runtime data (e.g. namespace info, signatures, keys)
     data_directory: Option<String>,
 
@@ -36,6 +45,7 @@ impl WebConfig {
     /// - Uses double underscore (__) as separator
     /// - For field `data_directory`, use `AKD_WATCH__DATA_DIRECTORY`
     /// - For field `bind_address`, use `AKD_WATCH__BIND_ADDRESS`
+    /// - For field `grpc_bind_address`, use `AKD_WATCH__GRPC_BIND_ADDRESS`
     /// - For nested fields like `signing.public_key_file`, use `AKD_WATCH__SIGNING__PUBLIC_KEY_FILE`
     ///
     /// Note: Only one config file source is used - either custom path OR default location
@@ -74,6 +84,19 @@ impl WebConfig {
                 "Web bind_address is not a valid socket address: {e}"
             )));
         }
+        match self.grpc_bind_address.parse::<std::net::SocketAddr>() {
+            Err(e) => {
+                return Err(ConfigError::Message(format!(
+                    "Web grpc_bind_address is not a valid socket address: {e}"
+                )));
+            }
+            Ok(addr) if self.bind_address.parse() == Ok(addr) => {
+                return Err(ConfigError::Message(
+                    "Web grpc_bind_address must differ from bind_address".to_string(),
+                ));
+            }
+            Ok(_) => {}
+        }
 
         // Validate data directory
         let data_directory = self
@@ -112,4 +135,12 @@ impl WebConfig {
             .parse()
             .expect("Failed to parse bind address")
     }
+
+    /// Get the socket address to bind the gRPC server to
+    /// Will panic if the configured grpc_bind_address string is not valid
+    pub fn grpc_socket_addr(&self) -> std::net::SocketAddr {
+        self.grpc_bind_address
+            .parse()
+            .expect("Failed to parse gRPC bind address")
+    }
 }